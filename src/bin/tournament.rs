@@ -0,0 +1,102 @@
+use std::{fs, process, thread};
+
+use tetris_rust::{
+    bot::tournament::{self, Pairing},
+    enums::BotDifficulty,
+};
+
+const USAGE: &str = "Usage: tournament [--bots easy,medium,hard] [--games N] [--seconds S] [--seed N] [--threads N] [--csv FILE] [--json FILE]
+
+Plays seeded headless games between the built-in bots, every two of them against each other,
+or a single bot alone as a benchmark, and reports pieces per second, attack per piece,
+win rates and lines survived.";
+
+/// Settings read from the command line.
+struct Options {
+    bots: Vec<BotDifficulty>,
+    games: usize,
+    seconds: f64,
+    seed: u64,
+    threads: usize,
+    csv_path: Option<String>,
+    json_path: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        bots: vec![BotDifficulty::Easy, BotDifficulty::Medium, BotDifficulty::Hard],
+        games: 10,
+        seconds: 180.0,
+        seed: 1,
+        threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        csv_path: None,
+        json_path: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("`{}` needs a value", flag));
+        let number_error = |value: &String| format!("`{}` expects a number, found `{}`", flag, value);
+        match flag.as_str() {
+            "--bots" => {
+                options.bots = value()?
+                    .split(',')
+                    .map(|name| BotDifficulty::by_name(name.trim()).ok_or_else(|| format!("unknown bot `{}`", name)))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--games" => options.games = value().and_then(|value| value.parse().map_err(|_| number_error(value)))?,
+            "--seconds" => options.seconds = value().and_then(|value| value.parse().map_err(|_| number_error(value)))?,
+            "--seed" => options.seed = value().and_then(|value| value.parse().map_err(|_| number_error(value)))?,
+            "--threads" => options.threads = value().and_then(|value| value.parse().map_err(|_| number_error(value)))?,
+            "--csv" => options.csv_path = Some(value()?.clone()),
+            "--json" => options.json_path = Some(value()?.clone()),
+            other => return Err(format!("unknown option `{}`", other)),
+        }
+    }
+    if options.bots.is_empty() {
+        return Err("no bots given".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = parse_options(&args).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    let pairings = Pairing::round_robin(&options.bots, options.games, options.seed);
+    println!("Playing {} games on {} threads...", pairings.len(), options.threads);
+    let results = tournament::run(&pairings, options.seconds, options.threads);
+
+    println!("{:<8} {:>6} {:>8} {:>7} {:>7} {:>15}", "Bot", "Games", "Win rate", "PPS", "APP", "Lines survived");
+    for summary in tournament::summarize(&results) {
+        println!(
+            "{:<8} {:>6} {:>7.0}% {:>7.2} {:>7.3} {:>15.1}",
+            summary.bot.name(),
+            summary.games,
+            summary.win_rate() * 100.0,
+            summary.pieces_per_second(),
+            summary.attack_per_piece(),
+            summary.lines_survived()
+        );
+    }
+
+    let reports = [
+        (&options.csv_path, tournament::to_csv(&results)),
+        (&options.json_path, tournament::to_json(&results).to_string()),
+    ];
+    for (path, contents) in reports {
+        if let Some(path) = path
+            && let Err(error) = fs::write(path, contents)
+        {
+            eprintln!("Could not write {}: {}", path, error);
+            process::exit(1);
+        }
+    }
+}
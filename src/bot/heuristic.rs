@@ -0,0 +1,195 @@
+use crate::core::{board::Board, game::Game};
+
+/// Weight of each feature of the placement evaluation. The defaults mix El-Tetris (row and
+/// column transitions, holes, wells, lines) with Yiyuan Lee's genetic tuning (aggregate height,
+/// bumpiness), scaled so neither set dominates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub aggregate_height: f64,
+    pub holes: f64,
+    pub bumpiness: f64,
+    pub row_transitions: f64,
+    pub column_transitions: f64,
+    pub wells: f64,
+    pub lines: f64,
+    /// Reward for each garbage line sent, so the bot prefers clears that attack.
+    pub attack: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            aggregate_height: -0.51,
+            holes: -3.6,
+            bumpiness: -0.18,
+            row_transitions: -0.32,
+            column_transitions: -0.93,
+            wells: -0.34,
+            lines: 0.76,
+            attack: 0.5,
+        }
+    }
+}
+
+/// Measurements of the board after a placement, plus what the placement achieved.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Features {
+    /// Sum of the column heights.
+    pub aggregate_height: usize,
+    /// Empty cells with a filled cell above them in the same column.
+    pub holes: usize,
+    /// Sum of the height differences between neighbouring columns.
+    pub bumpiness: usize,
+    /// Changes between filled and empty cells along the rows, walls counting as filled.
+    pub row_transitions: usize,
+    /// Changes between filled and empty cells down the columns, the floor counting as filled.
+    pub column_transitions: usize,
+    /// Sum of the depths of every cell of the wells, so a well of depth 3 counts 1 + 2 + 3.
+    pub wells: usize,
+    /// Lines cleared since `before`.
+    pub lines: usize,
+    /// Garbage lines sent since `before`.
+    pub attack: usize,
+}
+
+impl Features {
+    /// Measures the game `after` one or more placements made from `before`.
+    pub fn measure(before: &Game, after: &Game) -> Self {
+        let board = &after.board;
+        let heights = column_heights(board);
+        Self {
+            aggregate_height: heights.iter().sum(),
+            holes: count_holes(board),
+            bumpiness: heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum(),
+            row_transitions: row_transitions(board),
+            column_transitions: column_transitions(board),
+            wells: well_depths(board),
+            lines: after.lines_cleared - before.lines_cleared,
+            attack: after.lines_sent - before.lines_sent,
+        }
+    }
+
+    pub fn score(&self, weights: &Weights) -> f64 {
+        weights.aggregate_height * self.aggregate_height as f64
+            + weights.holes * self.holes as f64
+            + weights.bumpiness * self.bumpiness as f64
+            + weights.row_transitions * self.row_transitions as f64
+            + weights.column_transitions * self.column_transitions as f64
+            + weights.wells * self.wells as f64
+            + weights.lines * self.lines as f64
+            + weights.attack * self.attack as f64
+    }
+}
+
+/// Scores the game `after` placements made from `before`, higher is better.
+/// Topping out is the worst possible outcome.
+pub fn evaluate(before: &Game, after: &Game, weights: &Weights) -> f64 {
+    if after.is_game_over {
+        return f64::NEG_INFINITY;
+    }
+    Features::measure(before, after).score(weights)
+}
+
+fn is_filled(board: &Board, row: isize, col: isize) -> bool {
+    // Walls and floor count as filled, the space above the board as empty
+    row >= board.rows as isize || col < 0 || col >= board.cols as isize || board.is_occupied(row, col)
+}
+
+/// Height of the highest filled cell of each column, 0 for an empty column.
+pub fn column_heights(board: &Board) -> Vec<usize> {
+    (0..board.cols)
+        .map(|col| {
+            (0..board.rows)
+                .find(|&row| board.is_occupied(row as isize, col as isize))
+                .map_or(0, |row| board.rows - row)
+        })
+        .collect()
+}
+
+/// Number of empty cells with a filled cell above them in the same column.
+pub fn count_holes(board: &Board) -> usize {
+    (0..board.cols)
+        .map(|col| {
+            (0..board.rows)
+                .skip_while(|&row| !board.is_occupied(row as isize, col as isize))
+                .filter(|&row| !board.is_occupied(row as isize, col as isize))
+                .count()
+        })
+        .sum()
+}
+
+fn row_transitions(board: &Board) -> usize {
+    (0..board.rows as isize)
+        .map(|row| {
+            (0..=board.cols as isize)
+                .filter(|&col| is_filled(board, row, col - 1) != is_filled(board, row, col))
+                .count()
+        })
+        .sum()
+}
+
+fn column_transitions(board: &Board) -> usize {
+    (0..board.cols as isize)
+        .map(|col| {
+            (0..board.rows as isize)
+                .filter(|&row| board.is_occupied(row, col) != is_filled(board, row + 1, col))
+                .count()
+        })
+        .sum()
+}
+
+fn well_depths(board: &Board) -> usize {
+    let mut total = 0;
+    for col in 0..board.cols as isize {
+        let mut depth = 0;
+        for row in 0..board.rows as isize {
+            if !board.is_occupied(row, col) && is_filled(board, row, col - 1) && is_filled(board, row, col + 1) {
+                depth += 1;
+                total += depth;
+            } else {
+                depth = 0;
+            }
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::constants::{COLS, ROWS},
+        enums::CellType,
+    };
+
+    #[test]
+    fn holes_and_heights_are_measured_from_the_top_of_each_column() {
+        let mut board = Board::new();
+        board.set_cell(ROWS - 3, 0, CellType::Filled(BLUE));
+        board.set_cell(ROWS - 1, 0, CellType::Filled(BLUE));
+        board.set_cell(ROWS - 1, 1, CellType::Filled(BLUE));
+
+        assert_eq!(column_heights(&board)[..3], [3, 1, 0]);
+        assert_eq!(count_holes(&board), 1);
+    }
+
+    #[test]
+    fn transitions_and_wells_count_walls_and_floor_as_filled() {
+        let mut game = Game::new();
+        // A bottom row full but for a well in the last column
+        for col in 0..COLS - 1 {
+            game.board.set_cell(ROWS - 1, col, CellType::Filled(BLUE));
+        }
+
+        let features = Features::measure(&game, &game);
+
+        // Every row changes to empty and back to filled once, at the well or at the walls
+        assert_eq!(features.row_transitions, 2 * ROWS);
+        // The well column changes once at the floor, every other column once above its cell
+        assert_eq!(features.column_transitions, COLS);
+        // Only the cell between the stack and the wall is a well
+        assert_eq!(features.wells, 1);
+    }
+}
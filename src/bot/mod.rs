@@ -0,0 +1,252 @@
+pub mod heuristic;
+pub mod perfect_clear;
+pub mod tbp;
+pub mod tournament;
+
+use std::collections::VecDeque;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    bot::heuristic::{Weights, evaluate},
+    core::{game::Game, piece::Piece, placement::Placement},
+    enums::{BotDifficulty, GameAction},
+};
+
+/// Placements of each piece explored further by the lookahead, the best ones by evaluation.
+const BEAM_WIDTH: usize = 4;
+
+/// A computer player that plays a [`Game`] at the speed allowed by its difficulty. Moves,
+/// rotations, holds and hard drops go through the same inputs as a human player
+/// ([`Game::press`] and [`Game::release`]). Soft drops do not: they move the piece exactly one
+/// row with [`Game::apply_placement_step`], as placements are planned, to tuck pieces under
+/// overhangs whatever the soft drop speed of the rules.
+pub struct Bot {
+    pub difficulty: BotDifficulty,
+    /// Weights of the features placements are evaluated with.
+    pub weights: Weights,
+    rng: StdRng,
+    /// Inputs still to send for the current piece.
+    inputs: VecDeque<GameAction>,
+    /// Value of [`Game::pieces_placed`] when the current piece was planned.
+    planned_piece: Option<usize>,
+    /// Seconds between two inputs, spreading the inputs of a piece over its time budget.
+    input_interval: f64,
+    /// Seconds since the last input was sent.
+    waited_seconds: f64,
+}
+
+impl Bot {
+    /// Creates a bot whose mistakes are drawn from a generator seeded with `seed`.
+    pub fn new(difficulty: BotDifficulty, seed: u64) -> Self {
+        Self {
+            difficulty,
+            weights: Weights::default(),
+            rng: StdRng::seed_from_u64(seed),
+            inputs: VecDeque::new(),
+            planned_piece: None,
+            input_interval: 0.0,
+            waited_seconds: 0.0,
+        }
+    }
+
+    /// Plans the current piece when it appears, then sends its inputs over time.
+    /// Call before [`Game::update`] every frame.
+    pub fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if game.is_game_over || game.current_piece.is_none() {
+            return;
+        }
+
+        if self.planned_piece != Some(game.pieces_placed) {
+            // A soft drop held in place of a hard drop ends with the piece it dropped
+            game.release(GameAction::SoftDrop);
+            self.plan(game);
+        }
+
+        self.waited_seconds += delta_seconds;
+        while self.waited_seconds >= self.input_interval {
+            let Some(action) = self.inputs.pop_front() else {
+                break;
+            };
+            self.waited_seconds -= self.input_interval;
+            match action {
+                // One row, where holding the key would drop as far as the soft drop speed goes
+                GameAction::SoftDrop => game.apply_placement_step(action),
+                // Without hard drop the piece is soft dropped until it locks
+                GameAction::HardDrop if !game.rules.has_hard_drop => game.press(GameAction::SoftDrop),
+                _ => {
+                    game.press(action);
+                    game.release(action);
+                }
+            }
+        }
+    }
+
+    /// Chooses where the current piece goes, its best placement or, now and then,
+    /// a random one as a deliberate mistake.
+    fn plan(&mut self, game: &Game) {
+        self.planned_piece = Some(game.pieces_placed);
+        self.waited_seconds = 0.0;
+
+        let mut candidates = self.candidates(game);
+        let chosen = if !candidates.is_empty() && self.rng.random_bool(self.difficulty.mistake_chance()) {
+            let index = self.rng.random_range(0..candidates.len());
+            Some(candidates.swap_remove(index).0)
+        } else {
+            // Only the best placements by their own evaluation are looked further ahead from
+            let mut scored: Vec<(f64, Placement, Vec<Piece>)> = candidates
+                .into_iter()
+                .map(|(placement, queue)| (evaluate(game, &placement.result, &self.weights), placement, queue))
+                .collect();
+            scored.sort_by(|(a, ..), (b, ..)| b.total_cmp(a));
+            scored
+                .into_iter()
+                .take(BEAM_WIDTH)
+                .map(|(score, placement, queue)| (self.lookahead(game, &placement.result, score, &queue), placement))
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(_, placement)| placement)
+        };
+
+        self.inputs = chosen
+            .map(|placement| placement.inputs.iter().copied().collect())
+            .unwrap_or_else(|| VecDeque::from([GameAction::HardDrop]));
+        // The inputs of a piece are spread over the time the difficulty allows per piece
+        self.input_interval = 1.0 / self.difficulty.pieces_per_second() / self.inputs.len() as f64;
+    }
+
+    /// Returns the placements of the current piece and, when the rules allow holding, those of
+    /// the piece swapped in from the hold, their inputs starting with [`GameAction::Hold`].
+    /// Each comes with the pieces known to follow it.
+    fn candidates(&self, game: &Game) -> Vec<(Placement, Vec<Piece>)> {
+        let queue = |game: &Game| game.previews().into_iter().cloned().collect::<Vec<_>>();
+        let mut candidates: Vec<(Placement, Vec<Piece>)> =
+            game.placements().into_iter().map(|placement| (placement, queue(game))).collect();
+
+        let mut held = game.clone();
+        held.press(GameAction::Hold);
+        if held.has_held() && !game.has_held() && !held.is_game_over {
+            for mut placement in held.placements() {
+                placement.inputs.insert(0, GameAction::Hold);
+                candidates.push((placement, queue(&held)));
+            }
+        }
+        candidates
+    }
+
+    /// Scores the game `after` a placement made from `root`, whose own evaluation is `score`, by the
+    /// best score reachable by also placing the pieces of `queue`, as far as the difficulty looks
+    /// ahead. Only the best placements of each piece are explored further.
+    fn lookahead(&self, root: &Game, after: &Game, score: f64, queue: &[Piece]) -> f64 {
+        let depth = self.difficulty.lookahead().min(queue.len());
+        if depth == 0 || score == f64::NEG_INFINITY {
+            return score;
+        }
+
+        let mut next = after.clone();
+        next.current_piece = Some(queue[0].clone());
+        let mut scored: Vec<(f64, Game)> = next
+            .placements()
+            .into_iter()
+            .map(|placement| (evaluate(root, &placement.result, &self.weights), placement.result))
+            .collect();
+        if scored.is_empty() {
+            return score;
+        }
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        let rest = &queue[1..depth];
+        scored
+            .iter()
+            .take(BEAM_WIDTH)
+            .map(|(score, result)| self.lookahead(root, result, *score, rest))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::{
+            constants::{COLS, ROWS},
+            rules::RuleSet,
+        },
+        enums::{CellType, PieceType},
+    };
+
+    #[test]
+    fn bot_fills_the_well_with_an_i_piece() {
+        let mut game = Game::new();
+        game.start();
+        for row in ROWS - 4..ROWS {
+            for col in 0..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        game.current_piece = game.spawn_piece_of_type(&PieceType::I);
+        let mut bot = Bot::new(BotDifficulty::Hard, 1);
+
+        for _ in 0..120 {
+            bot.update(&mut game, 1.0 / 60.0);
+            if game.pieces_placed > 0 {
+                break;
+            }
+        }
+
+        assert_eq!(game.lines_cleared, 4);
+    }
+
+    #[test]
+    fn bot_places_pieces_no_faster_than_its_difficulty_allows() {
+        let mut game = Game::new();
+        game.set_seed(5);
+        game.start();
+        let mut bot = Bot::new(BotDifficulty::Medium, 5);
+
+        for _ in 0..60 * 10 {
+            bot.update(&mut game, 1.0 / 60.0);
+            game.update(1.0 / 60.0);
+        }
+
+        let most = (BotDifficulty::Medium.pieces_per_second() * 10.0).ceil() as usize;
+        assert!(game.pieces_placed <= most, "{} pieces placed", game.pieces_placed);
+        assert!(game.pieces_placed >= most - 2, "{} pieces placed", game.pieces_placed);
+        assert!(!game.is_game_over);
+    }
+
+    #[test]
+    fn bot_keeps_clearing_lines_without_topping_out() {
+        let mut game = Game::new();
+        game.set_seed(11);
+        game.start();
+        let mut bot = Bot::new(BotDifficulty::Medium, 11);
+
+        while game.pieces_placed < 30 && !game.is_game_over {
+            bot.update(&mut game, 1.0 / 60.0);
+            game.update(1.0 / 60.0);
+        }
+
+        assert!(!game.is_game_over);
+        // 30 pieces cover 12 rows, most of which should be cleared
+        assert!(game.lines_cleared >= 8, "{} lines cleared", game.lines_cleared);
+    }
+
+    #[test]
+    fn bot_soft_drops_when_the_rules_have_no_hard_drop() {
+        let mut game = Game::new();
+        game.apply_rules(RuleSet::nes());
+        game.set_seed(3);
+        game.start();
+        let mut bot = Bot::new(BotDifficulty::Hard, 3);
+
+        for _ in 0..60 * 10 {
+            bot.update(&mut game, 1.0 / 60.0);
+            game.update(1.0 / 60.0);
+        }
+
+        assert!(game.pieces_placed >= 5, "{} pieces placed", game.pieces_placed);
+        assert_eq!(heuristic::count_holes(&game.board), 0);
+    }
+}
@@ -0,0 +1,591 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread,
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    core::{board::Board, game::Game, placement::Placement},
+    enums::{CellType, GameAction, PieceType},
+};
+
+/// Rows used by the classic perfect clear opener.
+pub const CLASSIC_PERFECT_CLEAR_LINES: usize = 4;
+
+/// Sequences of unknown pieces tried when working out the probability of a solution. When there
+/// are more possible sequences, this many are drawn at random and the probability is an estimate.
+const MAX_SEQUENCES: usize = 300;
+
+/// A way to a perfect clear from the current position.
+#[derive(Clone)]
+pub struct Solution {
+    /// Placements of the pieces known so far, the current one first. The inputs of a placement
+    /// start with [`GameAction::Hold`] when it plays the held or the next piece instead.
+    pub placements: Vec<Placement>,
+    /// Rows the perfect clear uses, counted from the bottom.
+    pub lines: usize,
+    /// Share of the possible pieces after the known ones with which the placements can be
+    /// followed up to a perfect clear. 1 when the placements clear the board by themselves.
+    pub probability: f64,
+}
+
+impl Solution {
+    /// Whether the placements clear the board without any piece still unknown.
+    pub fn is_complete(&self) -> bool {
+        self.placements.last().is_some_and(|placement| is_cleared(&placement.result.board))
+    }
+}
+
+/// Searches for sequences of placements that clear the whole board within a number of lines,
+/// using the current piece, the hold and the pieces of the queue. Every reachable placement is
+/// tried, so it suits positions a few pieces away from a perfect clear: from an empty board
+/// the search seldom ends within its budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerfectClearSolver {
+    /// Rows a perfect clear may use, counted from the bottom. Every height up to this one
+    /// is tried.
+    pub max_lines: usize,
+    pub max_solutions: usize,
+    /// Placement searches made at most, a few milliseconds each, which bounds the time a solve
+    /// takes. Solutions found by then are returned, without those whose probability was not
+    /// worked out yet.
+    pub max_searches: usize,
+}
+
+impl Default for PerfectClearSolver {
+    fn default() -> Self {
+        Self {
+            max_lines: CLASSIC_PERFECT_CLEAR_LINES,
+            max_solutions: 10,
+            max_searches: 2000,
+        }
+    }
+}
+
+impl PerfectClearSolver {
+    /// Returns the solutions from the position of `game`, the most likely to succeed first and
+    /// then the shortest. Pieces after the queue are unknown: they are dealt from the rest of
+    /// the bag when the randomizer deals bags, any piece of the set otherwise, and there are
+    /// none after a fixed queue.
+    pub fn solve(&self, game: &Game) -> Vec<Solution> {
+        self.solve_until(game, &AtomicBool::new(false))
+    }
+
+    /// Solves as [`PerfectClearSolver::solve`], giving up once `is_cancelled` is set.
+    fn solve_until(&self, game: &Game, is_cancelled: &AtomicBool) -> Vec<Solution> {
+        if game.current_piece.is_none() || game.is_game_over {
+            return Vec::new();
+        }
+
+        // Pieces spawn right after a lock, and only the pieces the player can see are dealt
+        let mut root = game.clone();
+        root.timings.are = 0;
+        root.timings.line_clear_delay = 0;
+        let unknown = if game.has_fixed_queue() {
+            UnknownPieces::None
+        } else {
+            root.set_fixed_queue(&[]);
+            if root.rules.previews == 0 {
+                root.next_piece = None;
+            }
+            match game.unseen_bag_pieces() {
+                Some(bag) => UnknownPieces::Bag(bag),
+                None => UnknownPieces::Random,
+            }
+        };
+
+        let mut search = Search {
+            solver: self,
+            is_cancelled,
+            root_lines: root.lines_cleared,
+            lines: 0,
+            piece_types: game.piece_set.definitions.iter().map(|definition| definition.piece_type.clone()).collect(),
+            unknown,
+            searches: 0,
+            visited: HashSet::new(),
+            finishable: HashMap::new(),
+            solutions: Vec::new(),
+        };
+        for lines in 1..=self.max_lines {
+            if fits_within(&root.board, lines) {
+                search.lines = lines;
+                search.visited.clear();
+                search.finishable.clear();
+                search.solve(&root);
+            }
+        }
+
+        let mut solutions = search.solutions;
+        solutions.sort_by(|a, b| {
+            b.probability
+                .total_cmp(&a.probability)
+                .then(a.placements.len().cmp(&b.placements.len()))
+        });
+        solutions.truncate(self.max_solutions);
+        solutions
+    }
+}
+
+/// What is known of the pieces dealt after the queue.
+enum UnknownPieces {
+    /// No more pieces come.
+    None,
+    /// The rest of the bag being dealt, then whole bags.
+    Bag(Vec<PieceType>),
+    /// Any piece of the set, each time.
+    Random,
+}
+
+/// The state of one solve at one height.
+struct Search<'a> {
+    solver: &'a PerfectClearSolver,
+    is_cancelled: &'a AtomicBool,
+    /// Lines cleared before the solve, so the lines cleared since tell the rows left.
+    root_lines: usize,
+    lines: usize,
+    piece_types: Vec<PieceType>,
+    unknown: UnknownPieces,
+    searches: usize,
+    visited: HashSet<SearchKey>,
+    /// Whether a position, with its queue, can be finished into a perfect clear.
+    finishable: HashMap<SearchKey, bool>,
+    solutions: Vec<Solution>,
+}
+
+impl Search<'_> {
+    /// Rows left for the perfect clear in `game`.
+    fn lines_left(&self, game: &Game) -> usize {
+        self.lines.saturating_sub(game.lines_cleared - self.root_lines)
+    }
+
+    /// Placements of the current piece, or of the piece the hold swaps in, that keep a perfect
+    /// clear possible. Empty once the search budget is spent.
+    fn moves(&mut self, game: &Game) -> Vec<Placement> {
+        let mut starts = vec![(game.clone(), false)];
+        let mut held = game.clone();
+        held.hold();
+        if piece_type_of(&held.hold_piece) != piece_type_of(&game.hold_piece) && held.current_piece.is_some() {
+            starts.push((held, true));
+        }
+
+        let mut moves = Vec::new();
+        for (start, is_held) in starts {
+            if self.is_stopped() {
+                break;
+            }
+            self.searches += 1;
+            for mut placement in start.placements() {
+                let result = &placement.result;
+                if result.is_game_over || !fits_within(&result.board, self.lines_left(result)) {
+                    continue;
+                }
+                if is_held {
+                    placement.inputs.insert(0, GameAction::Hold);
+                }
+                moves.push(placement);
+            }
+        }
+        moves
+    }
+
+    /// Whether the budget is spent or the solve was given up.
+    fn is_stopped(&self) -> bool {
+        self.searches >= self.solver.max_searches || self.is_cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Plays the known pieces from `game` in every way, recording the placements that clear
+    /// the board and keeping the positions where the known pieces run out in `leaves`.
+    fn explore(&mut self, game: &Game, path: &mut Vec<Placement>, leaves: &mut Vec<(Vec<Placement>, Game)>) {
+        for placement in self.moves(game) {
+            let result = placement.result.clone();
+            path.push(placement);
+            if is_cleared(&result.board) {
+                self.record(path, 1.0);
+            } else if self.visited.insert(SearchKey::new(&result)) {
+                if result.current_piece.is_some() {
+                    self.explore(&result, path, leaves);
+                } else {
+                    leaves.push((path.clone(), result));
+                }
+            }
+            path.pop();
+        }
+    }
+
+    /// Explores the known pieces, then works out how likely each position they lead to is to
+    /// be finished. Positions the budget runs out on are left out rather than given too low
+    /// a probability.
+    fn solve(&mut self, root: &Game) {
+        let mut leaves = Vec::new();
+        self.explore(root, &mut Vec::new(), &mut leaves);
+        for (path, leaf) in leaves {
+            match self.finish_probability(&leaf) {
+                Some(probability) if probability > 0.0 => self.record(&path, probability),
+                Some(_) => {}
+                None => break,
+            }
+        }
+    }
+
+    /// Keeps a solution unless the same placements were found for fewer lines already, as
+    /// every height from the lowest one that fits is searched.
+    fn record(&mut self, path: &[Placement], probability: f64) {
+        let is_known = self.solutions.iter().any(|solution| {
+            solution.placements.len() == path.len()
+                && solution.placements.iter().zip(path).all(|(known, placement)| known.cells == placement.cells)
+        });
+        if is_known {
+            return;
+        }
+        self.solutions.push(Solution {
+            placements: path.to_vec(),
+            lines: self.lines,
+            probability,
+        });
+    }
+
+    /// Share of the possible sequences of unknown pieces with which `game`, out of known
+    /// pieces, can be finished into a perfect clear. `None` when the search stopped first.
+    fn finish_probability(&mut self, game: &Game) -> Option<f64> {
+        let board = &game.board;
+        let lines_left = self.lines_left(game);
+        let empty_cells = (board.rows - lines_left..board.rows)
+            .flat_map(|row| &board.cells[row])
+            .filter(|&&cell| cell == CellType::Empty)
+            .count();
+        let sequences = self.sequences(empty_cells / 4);
+        if sequences.is_empty() {
+            return Some(0.0);
+        }
+
+        let finished = sequences
+            .iter()
+            .filter(|sequence| {
+                let mut next = game.clone();
+                next.set_fixed_queue(sequence);
+                next.start();
+                self.can_finish(&next)
+            })
+            .count();
+        (!self.is_stopped()).then(|| finished as f64 / sequences.len() as f64)
+    }
+
+    fn can_finish(&mut self, game: &Game) -> bool {
+        let key = SearchKey::new(game);
+        if let Some(&can_finish) = self.finishable.get(&key) {
+            return can_finish;
+        }
+        let can_finish = self
+            .moves(game)
+            .iter()
+            .any(|placement| is_cleared(&placement.result.board) || self.can_finish(&placement.result));
+        self.finishable.insert(key, can_finish);
+        can_finish
+    }
+
+    /// The sequences of `length` unknown pieces that can come, every one equally likely.
+    fn sequences(&self, length: usize) -> Vec<Vec<PieceType>> {
+        let bag = match &self.unknown {
+            UnknownPieces::None => return Vec::new(),
+            UnknownPieces::Bag(bag) => Some(bag.as_slice()),
+            UnknownPieces::Random => None,
+        };
+
+        let mut count: usize = 1;
+        let mut left = bag.map_or(0, <[_]>::len);
+        for _ in 0..length {
+            if bag.is_none() || left == 0 {
+                left = self.piece_types.len();
+            }
+            count = count.saturating_mul(left);
+            if bag.is_some() {
+                left -= 1;
+            }
+        }
+        if count <= MAX_SEQUENCES {
+            return all_sequences(bag, &self.piece_types, length);
+        }
+
+        // The same draws every time, so solving a position twice gives the same estimate
+        let mut rng = StdRng::seed_from_u64(0);
+        (0..MAX_SEQUENCES)
+            .map(|_| {
+                let mut bag = bag.map(<[_]>::to_vec);
+                (0..length)
+                    .map(|_| match &mut bag {
+                        Some(bag) => {
+                            if bag.is_empty() {
+                                bag.clone_from(&self.piece_types);
+                            }
+                            bag.swap_remove(rng.random_range(0..bag.len()))
+                        }
+                        None => self.piece_types[rng.random_range(0..self.piece_types.len())].clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Every sequence of `length` pieces dealt from the rest of `bag` and then from whole bags of
+/// `piece_types`, or from any of `piece_types` each time without a bag.
+fn all_sequences(bag: Option<&[PieceType]>, piece_types: &[PieceType], length: usize) -> Vec<Vec<PieceType>> {
+    if length == 0 {
+        return vec![Vec::new()];
+    }
+    let choices = match bag {
+        Some([]) | None => piece_types,
+        Some(bag) => bag,
+    };
+
+    let mut sequences = Vec::new();
+    for (index, piece_type) in choices.iter().enumerate() {
+        let rest: Option<Vec<PieceType>> = bag.map(|_| {
+            let mut rest = choices.to_vec();
+            rest.remove(index);
+            rest
+        });
+        for mut sequence in all_sequences(rest.as_deref(), piece_types, length - 1) {
+            sequence.insert(0, piece_type.clone());
+            sequences.push(sequence);
+        }
+    }
+    sequences
+}
+
+/// Identifies a position of the search: the board, the pieces to play and the lines cleared,
+/// which tell how many rows are left.
+#[derive(PartialEq, Eq, Hash)]
+struct SearchKey {
+    board: Vec<Vec<usize>>,
+    pieces: Vec<Option<PieceType>>,
+    remaining: Vec<PieceType>,
+    lines_cleared: usize,
+}
+
+impl SearchKey {
+    fn new(game: &Game) -> Self {
+        Self {
+            board: game.board.get_board_representation(),
+            pieces: vec![
+                piece_type_of(&game.current_piece),
+                piece_type_of(&game.next_piece),
+                piece_type_of(&game.hold_piece),
+            ],
+            remaining: game.remaining_queue(),
+            lines_cleared: game.lines_cleared,
+        }
+    }
+}
+
+fn piece_type_of(piece: &Option<crate::core::piece::Piece>) -> Option<PieceType> {
+    piece.as_ref().map(|piece| piece.piece_type.clone())
+}
+
+fn is_cleared(board: &Board) -> bool {
+    board.cells.iter().flatten().all(|&cell| cell == CellType::Empty)
+}
+
+/// Whether the stack of `board` lies in its bottom `lines` rows, leaving room in them that
+/// pieces can fill: every part of those rows between columns filled to the top of them has
+/// a number of empty cells that is a multiple of 4. Filled columns stay filled as lines
+/// clear, so pieces never cross them.
+fn fits_within(board: &Board, lines: usize) -> bool {
+    let top = board.rows.saturating_sub(lines);
+    if board.cells[..top].iter().flatten().any(|&cell| cell != CellType::Empty) {
+        return false;
+    }
+
+    let mut empty_cells = 0;
+    for col in 0..board.cols {
+        let column_empty = board.cells[top..].iter().filter(|row| row[col] == CellType::Empty).count();
+        if column_empty == 0 && empty_cells % 4 != 0 {
+            return false;
+        }
+        empty_cells = if column_empty == 0 { 0 } else { empty_cells + column_empty };
+    }
+    empty_cells % 4 == 0
+}
+
+/// Runs a [`PerfectClearSolver`] on a worker thread while the game goes on, for a training
+/// overlay. It solves again for every new piece, giving up on the previous solve.
+pub struct BackgroundSolver {
+    pub solver: PerfectClearSolver,
+    /// Solutions for the piece to play, once found.
+    pub solutions: Option<Vec<Solution>>,
+    /// Pieces placed, current and held piece the solutions are for.
+    position: Option<(usize, Option<PieceType>, Option<PieceType>)>,
+    receiver: Option<Receiver<Vec<Solution>>>,
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl BackgroundSolver {
+    pub fn new(solver: PerfectClearSolver) -> Self {
+        Self {
+            solver,
+            solutions: None,
+            position: None,
+            receiver: None,
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether a solve is still running.
+    pub fn is_solving(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Starts solving when the piece to play changed since the last call, and picks up the
+    /// solutions of a finished solve.
+    pub fn update(&mut self, game: &Game) {
+        let position = (
+            game.pieces_placed,
+            piece_type_of(&game.current_piece),
+            piece_type_of(&game.hold_piece),
+        );
+        if self.position.as_ref() != Some(&position) {
+            self.is_cancelled.store(true, Ordering::Relaxed);
+            self.solutions = None;
+            self.receiver = None;
+            self.position = Some(position);
+            if game.current_piece.is_some() {
+                let (sender, receiver) = mpsc::channel();
+                let (solver, game) = (self.solver, game.clone());
+                let is_cancelled = Arc::new(AtomicBool::new(false));
+                self.is_cancelled = Arc::clone(&is_cancelled);
+                thread::spawn(move || {
+                    // Nobody is waiting any more when a newer solve replaced this one
+                    let _ = sender.send(solver.solve_until(&game, &is_cancelled));
+                });
+                self.receiver = Some(receiver);
+            }
+        }
+
+        if let Some(receiver) = &self.receiver
+            && let Ok(solutions) = receiver.try_recv()
+        {
+            self.solutions = Some(solutions);
+            self.receiver = None;
+        }
+    }
+}
+
+impl Drop for BackgroundSolver {
+    fn drop(&mut self) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::core::{
+        constants::{COLS, ROWS},
+        randomizer::Randomizer,
+        rules::RuleSet,
+    };
+
+    /// Fills the bottom `lines` rows except the given columns.
+    fn fill_bottom(game: &mut Game, lines: usize, empty_cols: &[usize]) {
+        for row in ROWS - lines..ROWS {
+            for col in (0..COLS).filter(|col| !empty_cols.contains(col)) {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+    }
+
+    fn game_with_queue(queue: &[PieceType], lines: usize, empty_cols: &[usize]) -> Game {
+        let mut game = Game::new();
+        fill_bottom(&mut game, lines, empty_cols);
+        game.set_fixed_queue(queue);
+        game.start();
+        game
+    }
+
+    #[test]
+    fn known_pieces_that_clear_the_board_are_a_certain_solution() {
+        let game = game_with_queue(&[PieceType::I], 4, &[9]);
+
+        let solutions = PerfectClearSolver::default().solve(&game);
+
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        assert!(solution.is_complete());
+        assert_eq!((solution.lines, solution.probability), (4, 1.0));
+
+        let mut played = game.clone();
+        for &action in &solution.placements[0].inputs {
+            played.apply_placement_step(action);
+        }
+        assert_eq!(played.perfect_clears, 1);
+    }
+
+    #[test]
+    fn the_same_placements_are_found_once_for_the_fewest_lines() {
+        // The O fits the hole of a 2-line clear, and of every taller one too
+        let game = game_with_queue(&[PieceType::O], 2, &[4, 5]);
+
+        let solutions = PerfectClearSolver::default().solve(&game);
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].lines, 2);
+    }
+
+    #[test]
+    fn solutions_use_the_hold_when_the_current_piece_does_not_fit() {
+        let game = game_with_queue(&[PieceType::O, PieceType::I], 4, &[0]);
+
+        let solutions = PerfectClearSolver::default().solve(&game);
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].placements[0].inputs.first(), Some(&GameAction::Hold));
+        assert!(PerfectClearSolver::default().solve(&game_with_queue(&[PieceType::O], 4, &[0])).is_empty());
+    }
+
+    #[test]
+    fn probabilities_count_the_unknown_pieces_that_finish_the_clear() {
+        // With no previews only the current I is known, and an I must follow it
+        let mut rules = RuleSet::guideline();
+        rules.previews = 0;
+        rules.randomizer = Randomizer::Random;
+        let mut game = Game::new();
+        game.apply_rules(rules);
+        fill_bottom(&mut game, 4, &[8, 9]);
+        game.start();
+        game.current_piece = game.spawn_piece_of_type(&PieceType::I);
+
+        let solutions = PerfectClearSolver::default().solve(&game);
+
+        // The I goes down either column
+        assert_eq!(solutions.len(), 2);
+        for solution in &solutions {
+            assert!(!solution.is_complete());
+            assert!((solution.probability - 1.0 / 7.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn background_solver_finds_the_solutions_of_the_piece_to_play() {
+        let game = game_with_queue(&[PieceType::I], 4, &[9]);
+        let mut background = BackgroundSolver::new(PerfectClearSolver::default());
+
+        let started = Instant::now();
+        while background.solutions.is_none() && started.elapsed() < Duration::from_secs(30) {
+            background.update(&game);
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert!(!background.is_solving());
+        assert_eq!(background.solutions.as_ref().map(Vec::len), Some(1));
+    }
+}
@@ -0,0 +1,562 @@
+#[cfg(test)]
+mod stub;
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    core::{constants::COLS, game::Game, placement::Placement, point_2d::Point2D},
+    enums::{CellType, GameAction, PieceType},
+};
+
+/// Height of the boards sent to bots, the bottom row first. Rows above ours are sent empty.
+pub(crate) const TBP_BOARD_ROWS: usize = 40;
+
+/// Orientations of a TBP move location, clockwise from spawn.
+const TBP_ORIENTATIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+/// How long the bot may take to answer when the frontend waits for it.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a bot told to quit has to exit before its process is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A bot speaking the Tetris Bot Protocol (<https://github.com/tetris-bot-protocol/tbp-spec>),
+/// one JSON message per line. The bot is told the board and queue with `start`, asked for a
+/// move with `suggest`, and kept up to date with `play` and `new_piece` as it plays a [`Game`].
+/// Messages are read and written on worker threads, so a slow bot never holds up the game.
+pub struct TbpBot {
+    /// Name the bot gave in its `info` message.
+    pub name: String,
+    pieces_per_second: f64,
+    /// Messages for the worker thread writing to the bot.
+    outgoing: Sender<Value>,
+    /// Messages read from the bot by a worker thread, or why reading or writing failed.
+    incoming: Receiver<Result<Value, String>>,
+    /// The bot's process, when it was launched by [`TbpBot::launch`].
+    child: Option<Child>,
+    /// The current piece and queue as the bot knows them, `None` until `start` is sent.
+    known_queue: Option<VecDeque<PieceType>>,
+    /// Seconds since the last piece was played.
+    waited_seconds: f64,
+    /// Pieces placed when `suggest` was sent, while the suggestion has not arrived.
+    pending_suggestion: Option<usize>,
+}
+
+impl TbpBot {
+    /// Starts the bot program `command` (the program followed by its arguments, separated by
+    /// spaces) and talks to it over its standard input and output.
+    pub fn launch(command: &str, pieces_per_second: f64) -> Result<Self, String> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("no bot command given")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("could not start `{}`: {}", program, error))?;
+        let reader = BufReader::new(child.stdout.take().ok_or("the bot has no output")?);
+        let writer = child.stdin.take().ok_or("the bot has no input")?;
+
+        // A bot that does not get ready is not left running
+        let mut bot = Self::connect(reader, writer, pieces_per_second).inspect_err(|_| {
+            let _ = child.kill();
+            let _ = child.wait();
+        })?;
+        bot.child = Some(child);
+        Ok(bot)
+    }
+
+    /// Talks to a bot over `reader` and `writer`: waits for its `info`, then sends the
+    /// `rules` and waits until it is `ready`.
+    pub fn connect(
+        reader: impl BufRead + Send + 'static,
+        writer: impl Write + Send + 'static,
+        pieces_per_second: f64,
+    ) -> Result<Self, String> {
+        let (outgoing, messages) = mpsc::channel();
+        let (sender, incoming) = mpsc::channel();
+        let errors = sender.clone();
+        thread::spawn(move || read_messages(reader, sender));
+        thread::spawn(move || write_messages(writer, messages, errors));
+
+        let mut bot = Self {
+            name: String::new(),
+            pieces_per_second,
+            outgoing,
+            incoming,
+            child: None,
+            known_queue: None,
+            waited_seconds: 0.0,
+            pending_suggestion: None,
+        };
+        let info = bot.receive("info")?;
+        bot.name = info.get("name").and_then(Value::as_str).unwrap_or("TBP bot").to_string();
+        bot.send(json!({ "type": "rules" }))?;
+        bot.receive("ready")?;
+        Ok(bot)
+    }
+
+    /// Asks the bot for a move once the time it is given per piece has passed, and makes the
+    /// move once the bot's answer has arrived, without waiting for it. Call before
+    /// [`Game::update`] every frame.
+    pub fn update(&mut self, game: &mut Game, delta_seconds: f64) -> Result<(), String> {
+        if let Some(pieces_placed) = self.pending_suggestion {
+            let Some(suggestion) = self.try_receive("suggestion")? else {
+                return Ok(());
+            };
+            self.pending_suggestion = None;
+            // The piece locked by itself while the bot was thinking, the bot starts over from the new board
+            if game.pieces_placed != pieces_placed || game.current_piece.is_none() {
+                return self.stop();
+            }
+            return self.make_move(game, &suggestion);
+        }
+
+        if game.is_game_over || game.current_piece.is_none() {
+            return Ok(());
+        }
+        self.waited_seconds += delta_seconds;
+        if self.waited_seconds < 1.0 / self.pieces_per_second {
+            return Ok(());
+        }
+        self.waited_seconds = 0.0;
+        self.suggest(game)
+    }
+
+    /// Asks the bot for a move, waiting for it, makes it in `game` and tells the bot about it,
+    /// and about the pieces that came into the queue.
+    pub fn play(&mut self, game: &mut Game) -> Result<(), String> {
+        self.suggest(game)?;
+        let suggestion = self.receive("suggestion")?;
+        self.pending_suggestion = None;
+        self.make_move(game, &suggestion)
+    }
+
+    /// Sends `suggest`, and the state of `game` first when the bot does not know it.
+    fn suggest(&mut self, game: &Game) -> Result<(), String> {
+        if self.known_queue.is_none() {
+            self.start(game)?;
+        }
+        self.send(json!({ "type": "suggest" }))?;
+        self.pending_suggestion = Some(game.pieces_placed);
+        Ok(())
+    }
+
+    /// Makes the first move of `suggestion` that can be made and tells the bot about it.
+    fn make_move(&mut self, game: &mut Game, suggestion: &Value) -> Result<(), String> {
+        let chosen = array(field(suggestion, "moves")?)?
+            .iter()
+            .find_map(|tbp_move| Some((tbp_move, find_placement(game, tbp_move).ok()?)))
+            .ok_or("none of the suggested moves can be made")?;
+        let (tbp_move, (placement, is_hold)) = chosen;
+
+        let was_hold_empty = game.hold_piece.is_none();
+        let garbage_before = game.incoming_garbage.total();
+        let lines_before = game.lines_cleared;
+        if is_hold {
+            game.press(GameAction::Hold);
+            game.release(GameAction::Hold);
+        }
+        for &action in placement.inputs.iter().filter(|&&action| action != GameAction::Hold) {
+            game.apply_placement_step(action);
+        }
+        self.send(json!({ "type": "play", "move": tbp_move }))?;
+
+        // Garbage that rose into the board is news to the bot, which starts over from the new board
+        if game.lines_cleared == lines_before && game.incoming_garbage.total() < garbage_before {
+            return self.stop();
+        }
+
+        let known_queue = self.known_queue.get_or_insert_default();
+        known_queue.pop_front();
+        if is_hold && was_hold_empty {
+            known_queue.pop_front();
+        }
+        let new_pieces: Vec<PieceType> = queue(game).into_iter().skip(known_queue.len()).collect();
+        for piece_type in new_pieces {
+            let letter = piece_letter(&piece_type)?;
+            self.known_queue.get_or_insert_default().push_back(piece_type);
+            self.send(json!({ "type": "new_piece", "piece": letter }))?;
+        }
+        Ok(())
+    }
+
+    /// Sends the whole state of `game`, the bot forgetting anything it knew before.
+    fn start(&mut self, game: &Game) -> Result<(), String> {
+        if game.board.cols != COLS {
+            return Err(format!("TBP boards are {} columns wide", COLS));
+        }
+        let pieces = queue(game);
+        let hold = game.hold_piece.as_ref().map(|piece| piece_letter(&piece.piece_type)).transpose()?;
+        let letters = pieces.iter().map(piece_letter).collect::<Result<Vec<_>, _>>()?;
+        // TBP counts the clears of the combo, the game the clears after the first
+        let combo = game.combo.map_or(0, |combo| combo + 1);
+
+        self.send(json!({
+            "type": "start",
+            "hold": hold,
+            "queue": letters,
+            "combo": combo,
+            "back_to_back": game.is_back_to_back,
+            "board": board_rows(game),
+        }))?;
+        self.known_queue = Some(pieces.into());
+        Ok(())
+    }
+
+    /// Has the bot forget the game, to be sent `start` again before the next move.
+    fn stop(&mut self) -> Result<(), String> {
+        self.send(json!({ "type": "stop" }))?;
+        self.known_queue = None;
+        Ok(())
+    }
+
+    fn send(&self, message: Value) -> Result<(), String> {
+        self.outgoing.send(message).map_err(|_| "the bot no longer reads its input".to_string())
+    }
+
+    /// Waits for a message of the `expected` type, at most [`REPLY_TIMEOUT`].
+    fn receive(&self, expected: &str) -> Result<Value, String> {
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+        loop {
+            let message = self
+                .incoming
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|error| match error {
+                    RecvTimeoutError::Timeout => format!("the bot did not send `{}` in time", expected),
+                    RecvTimeoutError::Disconnected => format!("the bot stopped while waiting for `{}`", expected),
+                })?;
+            if let Some(message) = accept(message, expected)? {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// The message of the `expected` type if it has arrived, without waiting.
+    fn try_receive(&self, expected: &str) -> Result<Option<Value>, String> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(message) => {
+                    if let Some(message) = accept(message, expected)? {
+                        return Ok(Some(message));
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(format!("the bot stopped while waiting for `{}`", expected));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TbpBot {
+    fn drop(&mut self) {
+        // The bot may be gone already, there is nothing left to do about errors
+        let _ = self.send(json!({ "type": "quit" }));
+        if let Some(child) = &mut self.child {
+            // A bot that does not quit in time is killed rather than waited for
+            let deadline = Instant::now() + QUIT_TIMEOUT;
+            while let Ok(None) = child.try_wait() {
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+/// Sends every message the bot writes to `sender`, until its output ends or a line is not
+/// a message.
+fn read_messages(mut reader: impl BufRead, sender: Sender<Result<Value, String>>) {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let message = match reader.read_line(&mut line) {
+            Ok(0) => Err("the bot stopped".to_string()),
+            Ok(_) if line.trim().is_empty() => continue,
+            Ok(_) => serde_json::from_str(&line).map_err(|error| format!("invalid message from the bot: {}", error)),
+            Err(error) => Err(format!("could not read from the bot: {}", error)),
+        };
+        let is_last = message.is_err();
+        // Nobody is listening any more once the bot was dropped
+        if sender.send(message).is_err() || is_last {
+            return;
+        }
+    }
+}
+
+/// Writes the `messages` to the bot one per line, reporting a failed write to `errors`.
+fn write_messages(mut writer: impl Write, messages: Receiver<Value>, errors: Sender<Result<Value, String>>) {
+    for message in messages {
+        if let Err(error) = writeln!(writer, "{}", message).and_then(|_| writer.flush()) {
+            let _ = errors.send(Err(format!("could not write to the bot: {}", error)));
+            return;
+        }
+    }
+}
+
+/// `message` if it is of the `expected` type, `None` for the other ones, which the protocol
+/// allows the frontend to ignore. An `error` message from the bot is returned as an error.
+fn accept(message: Result<Value, String>, expected: &str) -> Result<Option<Value>, String> {
+    let message = message?;
+    let kind = string(field(&message, "type")?)?;
+    if kind == "error" && expected != "error" {
+        let reason = message.get("reason").and_then(Value::as_str).unwrap_or("unknown");
+        return Err(format!("the bot reported an error: {}", reason));
+    }
+    Ok((kind == expected).then_some(message))
+}
+
+/// The current piece followed by the previews, the queue as TBP sees it.
+fn queue(game: &Game) -> Vec<PieceType> {
+    game.current_piece
+        .iter()
+        .chain(game.previews())
+        .map(|piece| piece.piece_type.clone())
+        .collect()
+}
+
+/// The board as TBP rows, the bottom row first, filled cells sent as garbage.
+fn board_rows(game: &Game) -> Value {
+    let board = &game.board;
+    let rows = (0..TBP_BOARD_ROWS)
+        .map(|height| {
+            let cells = (0..board.cols)
+                .map(|col| match board.rows.checked_sub(height + 1).map(|row| board.cells[row][col]) {
+                    Some(CellType::Filled(_) | CellType::Garbage) => json!("G"),
+                    _ => Value::Null,
+                })
+                .collect();
+            Value::Array(cells)
+        })
+        .collect();
+    Value::Array(rows)
+}
+
+/// The `key` member of a message, which the protocol requires.
+fn field<'a>(message: &'a Value, key: &str) -> Result<&'a Value, String> {
+    message.get(key).ok_or_else(|| format!("missing `{}`", key))
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value.as_str().ok_or_else(|| format!("expected a string, found {}", value))
+}
+
+fn integer(value: &Value) -> Result<i64, String> {
+    value.as_i64().ok_or_else(|| format!("expected a whole number, found {}", value))
+}
+
+fn array(value: &Value) -> Result<&[Value], String> {
+    value.as_array().map(Vec::as_slice).ok_or_else(|| format!("expected an array, found {}", value))
+}
+
+pub(crate) fn piece_letter(piece_type: &PieceType) -> Result<&'static str, String> {
+    match piece_type {
+        PieceType::I => Ok("I"),
+        PieceType::J => Ok("J"),
+        PieceType::L => Ok("L"),
+        PieceType::O => Ok("O"),
+        PieceType::S => Ok("S"),
+        PieceType::T => Ok("T"),
+        PieceType::Z => Ok("Z"),
+        PieceType::Custom(name) => Err(format!("TBP only knows tetrominoes, not {}", name)),
+    }
+}
+
+pub(crate) fn piece_from_letter(letter: &str) -> Result<PieceType, String> {
+    match letter {
+        "I" => Ok(PieceType::I),
+        "J" => Ok(PieceType::J),
+        "L" => Ok(PieceType::L),
+        "O" => Ok(PieceType::O),
+        "S" => Ok(PieceType::S),
+        "T" => Ok(PieceType::T),
+        "Z" => Ok(PieceType::Z),
+        other => Err(format!("unknown piece `{}`", other)),
+    }
+}
+
+/// Cells covered by a piece of a TBP move location, as `(x, y)` from the bottom left corner.
+/// Locations give the rotation center of the piece in SRS.
+pub(crate) fn location_cells(piece_type: &PieceType, orientation: &str, x: i64, y: i64) -> Result<Vec<(i64, i64)>, String> {
+    let north: [(i64, i64); 4] = match piece_type {
+        PieceType::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        PieceType::J => [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        PieceType::L => [(1, 1), (-1, 0), (0, 0), (1, 0)],
+        PieceType::O => [(0, 1), (1, 1), (0, 0), (1, 0)],
+        PieceType::S => [(0, 1), (1, 1), (-1, 0), (0, 0)],
+        PieceType::T => [(0, 1), (-1, 0), (0, 0), (1, 0)],
+        PieceType::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        PieceType::Custom(name) => return Err(format!("TBP only knows tetrominoes, not {}", name)),
+    };
+    let turns = TBP_ORIENTATIONS
+        .iter()
+        .position(|&name| name == orientation)
+        .ok_or_else(|| format!("unknown orientation `{}`", orientation))?;
+    Ok(north
+        .iter()
+        .map(|&(dx, dy)| (0..turns).fold((dx, dy), |(dx, dy), _| (dy, -dx)))
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .collect())
+}
+
+/// Finds the placement of `game` a TBP move stands for, and whether it starts with a hold.
+/// Moves asking for a spin are made the highest scoring way, which is the spin when there is one.
+fn find_placement(game: &Game, tbp_move: &Value) -> Result<(Placement, bool), String> {
+    let location = field(tbp_move, "location")?;
+    let piece_type = piece_from_letter(string(field(location, "type")?)?)?;
+    let cells = location_cells(
+        &piece_type,
+        string(field(location, "orientation")?)?,
+        integer(field(location, "x")?)?,
+        integer(field(location, "y")?)?,
+    )?;
+    let rows = game.board.rows as i64;
+    let mut cells: Vec<Point2D> = cells.into_iter().map(|(x, y)| Point2D::new((rows - 1 - y) as isize, x as isize)).collect();
+    cells.sort_by_key(|block| (block.y, block.x));
+
+    let is_current = game.current_piece.as_ref().is_some_and(|piece| piece.piece_type == piece_type);
+    let mut held = game.clone();
+    if !is_current {
+        held.press(GameAction::Hold);
+    }
+    if held.current_piece.as_ref().is_none_or(|piece| piece.piece_type != piece_type) {
+        return Err(format!("{:?} is neither the current piece nor the one to hold", piece_type));
+    }
+
+    let is_spin = tbp_move.get("spin").and_then(Value::as_str).is_some_and(|spin| spin != "none");
+    let mut matching = held.placements().into_iter().filter(|placement| placement.cells == cells);
+    let placement = if is_spin {
+        matching.max_by_key(|placement| placement.result.score)
+    } else {
+        matching.next()
+    };
+    placement.map(|placement| (placement, !is_current)).ok_or_else(|| "the move cannot be reached".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, thread};
+
+    use super::*;
+
+    /// Connects to the stub bot running on a thread.
+    fn connect_stub() -> TbpBot {
+        let (bot_reader, frontend_writer) = io::pipe().unwrap();
+        let (frontend_reader, bot_writer) = io::pipe().unwrap();
+        thread::spawn(move || stub::run(BufReader::new(bot_reader), bot_writer));
+        TbpBot::connect(BufReader::new(frontend_reader), frontend_writer, 60.0).unwrap()
+    }
+
+    #[test]
+    fn locations_cover_the_srs_cells_of_each_orientation() {
+        assert_eq!(location_cells(&PieceType::T, "north", 4, 0), Ok(vec![(4, 1), (3, 0), (4, 0), (5, 0)]));
+        assert_eq!(location_cells(&PieceType::I, "east", 0, 2), Ok(vec![(0, 3), (0, 2), (0, 1), (0, 0)]));
+        assert!(location_cells(&PieceType::T, "up", 4, 0).is_err());
+    }
+
+    #[test]
+    fn the_start_message_sends_the_board_bottom_row_first() {
+        let mut game = Game::new();
+        game.start();
+        game.board.set_cell(game.board.rows - 1, 0, CellType::Garbage);
+
+        let rows = board_rows(&game);
+        let rows = rows.as_array().unwrap();
+
+        assert_eq!(rows.len(), TBP_BOARD_ROWS);
+        assert_eq!(rows[0][0], "G");
+        assert_eq!(rows[0][1], Value::Null);
+        assert!(rows[1].as_array().unwrap().iter().all(Value::is_null));
+    }
+
+    #[test]
+    fn a_tbp_bot_plays_the_game_through_the_protocol() {
+        let mut game = Game::new();
+        game.set_seed(4);
+        game.start();
+        let mut bot = connect_stub();
+        assert_eq!(bot.name, "Stub");
+
+        for _ in 0..20 {
+            bot.play(&mut game).unwrap();
+            while game.current_piece.is_none() {
+                game.step_frame();
+            }
+        }
+
+        assert_eq!(game.pieces_placed, 20);
+        assert!(!game.is_game_over);
+        // The bot's queue follows the game's, so every piece it was told of is the one it played
+        assert_eq!(bot.known_queue.clone().map(Vec::from), Some(queue(&game)));
+    }
+
+    #[test]
+    fn updates_make_the_move_once_the_bot_has_answered() {
+        let mut game = Game::new();
+        game.set_seed(2);
+        game.start();
+        let mut bot = connect_stub();
+
+        for _ in 0..1000 {
+            if game.pieces_placed == 5 {
+                break;
+            }
+            bot.update(&mut game, 1.0).unwrap();
+            while game.current_piece.is_none() {
+                game.step_frame();
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(game.pieces_placed, 5);
+    }
+
+    #[test]
+    fn a_bot_that_does_not_answer_never_holds_up_the_game() {
+        let (bot_reader, frontend_writer) = io::pipe().unwrap();
+        let (frontend_reader, mut bot_writer) = io::pipe().unwrap();
+        // Gets ready, then reads everything without ever suggesting a move
+        thread::spawn(move || {
+            writeln!(bot_writer, "{}", json!({ "type": "info", "name": "Silent" })).unwrap();
+            writeln!(bot_writer, "{}", json!({ "type": "ready" })).unwrap();
+            for _ in BufReader::new(bot_reader).lines() {}
+        });
+        let mut bot = TbpBot::connect(BufReader::new(frontend_reader), frontend_writer, 60.0).unwrap();
+        let mut game = Game::new();
+        game.start();
+
+        let started = Instant::now();
+        for _ in 0..10 {
+            bot.update(&mut game, 1.0).unwrap();
+        }
+
+        assert!(started.elapsed() < REPLY_TIMEOUT);
+        assert_eq!(game.pieces_placed, 0);
+        assert_eq!(bot.pending_suggestion, Some(0));
+    }
+
+    #[test]
+    fn a_bot_process_that_does_not_quit_is_killed() {
+        let mut bot = connect_stub();
+        bot.child = Some(Command::new("sleep").arg("30").spawn().unwrap());
+
+        let started = Instant::now();
+        drop(bot);
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
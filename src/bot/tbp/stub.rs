@@ -0,0 +1,125 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Write},
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    bot::tbp::{TBP_BOARD_ROWS, location_cells, piece_from_letter},
+    core::constants::COLS,
+};
+
+/// A TBP bot for tests. It never holds and drops each piece, in any orientation, wherever its
+/// highest cell ends up lowest, leftmost first.
+pub fn run(reader: impl BufRead, mut writer: impl Write) {
+    let mut send = |message: Value| {
+        writeln!(writer, "{}", message).and_then(|_| writer.flush()).is_ok()
+    };
+    send(json!({ "type": "info", "name": "Stub", "version": "1", "author": "Tests", "features": [] }));
+
+    let mut board = vec![[false; COLS]; TBP_BOARD_ROWS];
+    let mut queue: VecDeque<String> = VecDeque::new();
+    for line in reader.lines() {
+        let Some(message) = line.ok().and_then(|line| serde_json::from_str::<Value>(&line).ok()) else {
+            return;
+        };
+        let kind = message.get("type").and_then(Value::as_str).unwrap_or("");
+        let is_sent = match kind {
+            "rules" => send(json!({ "type": "ready" })),
+            "start" => {
+                board = read_board(&message);
+                queue = read_queue(&message);
+                true
+            }
+            "suggest" => {
+                let moves: Vec<Value> = queue.front().and_then(|piece| best_move(&board, piece)).into_iter().collect();
+                send(json!({ "type": "suggestion", "moves": moves }))
+            }
+            "play" => {
+                if let Some(cells) = message.get("move").and_then(move_cells)
+                    && fits(&board, &cells)
+                {
+                    place(&mut board, &cells);
+                }
+                queue.pop_front();
+                true
+            }
+            "new_piece" => {
+                if let Some(piece) = message.get("piece").and_then(Value::as_str) {
+                    queue.push_back(piece.to_string());
+                }
+                true
+            }
+            "quit" => return,
+            _ => true,
+        };
+        if !is_sent {
+            return;
+        }
+    }
+}
+
+fn read_board(message: &Value) -> Vec<[bool; COLS]> {
+    let mut board = vec![[false; COLS]; TBP_BOARD_ROWS];
+    let rows = message["board"].as_array().map(Vec::as_slice).unwrap_or_default();
+    for (row, cells) in board.iter_mut().zip(rows) {
+        for (cell, value) in row.iter_mut().zip(cells.as_array().map(Vec::as_slice).unwrap_or_default()) {
+            *cell = !value.is_null();
+        }
+    }
+    board
+}
+
+fn read_queue(message: &Value) -> VecDeque<String> {
+    let pieces = message["queue"].as_array().map(Vec::as_slice).unwrap_or_default();
+    pieces.iter().filter_map(Value::as_str).map(str::to_string).collect()
+}
+
+fn fits(board: &[[bool; COLS]], cells: &[(i64, i64)]) -> bool {
+    cells.iter().all(|&(x, y)| {
+        (0..COLS as i64).contains(&x) && (0..TBP_BOARD_ROWS as i64).contains(&y) && !board[y as usize][x as usize]
+    })
+}
+
+fn move_cells(tbp_move: &Value) -> Option<Vec<(i64, i64)>> {
+    let location = tbp_move.get("location")?;
+    let piece_type = piece_from_letter(location.get("type")?.as_str()?).ok()?;
+    let orientation = location.get("orientation")?.as_str()?;
+    let x = location.get("x")?.as_i64()?;
+    let y = location.get("y")?.as_i64()?;
+    location_cells(&piece_type, orientation, x, y).ok()
+}
+
+fn best_move(board: &[[bool; COLS]], piece: &str) -> Option<Value> {
+    let piece_type = piece_from_letter(piece).ok()?;
+    let mut best: Option<(i64, Value)> = None;
+    for orientation in ["north", "east", "south", "west"] {
+        for x in -2..COLS as i64 + 2 {
+            // Dropped from the top of the 20 rows the game shows
+            let mut y = 18;
+            let cells_at = |y| location_cells(&piece_type, orientation, x, y).unwrap_or_default();
+            if !fits(board, &cells_at(y)) {
+                continue;
+            }
+            while fits(board, &cells_at(y - 1)) {
+                y -= 1;
+            }
+            let top = cells_at(y).iter().map(|&(_, y)| y).max().unwrap_or(0);
+            if best.as_ref().is_none_or(|(best_top, _)| top < *best_top) {
+                let location = json!({ "type": piece, "orientation": orientation, "x": x, "y": y });
+                let tbp_move = json!({ "location": location, "spin": "none" });
+                best = Some((top, tbp_move));
+            }
+        }
+    }
+    best.map(|(_, tbp_move)| tbp_move)
+}
+
+fn place(board: &mut Vec<[bool; COLS]>, cells: &[(i64, i64)]) {
+    for &(x, y) in cells {
+        board[y as usize][x as usize] = true;
+    }
+    board.retain(|row| !row.iter().all(|&cell| cell));
+    board.resize(TBP_BOARD_ROWS, [false; COLS]);
+}
@@ -0,0 +1,343 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    bot::Bot,
+    core::constants::FRAMES_PER_SECOND,
+    enums::{BotDifficulty, MatchStatus},
+    modes::{pieces_per_second, versus::Versus},
+};
+
+/// One headless game to play: bots against each other in a [`Versus`] match, or a single bot
+/// alone as a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pairing {
+    pub bots: Vec<BotDifficulty>,
+    /// Seeds the piece sequence, shared by every player, and the bots' mistakes.
+    pub seed: u64,
+}
+
+impl Pairing {
+    /// Every pairing of `games` games each: every bot alone when there is only one, otherwise
+    /// every two different bots against each other. Seeds count up from `first_seed`, so each
+    /// pairing plays the same piece sequences.
+    pub fn round_robin(bots: &[BotDifficulty], games: usize, first_seed: u64) -> Vec<Self> {
+        let sides: Vec<Vec<BotDifficulty>> = if bots.len() == 1 {
+            vec![bots.to_vec()]
+        } else {
+            (0..bots.len())
+                .flat_map(|first| (first + 1..bots.len()).map(move |second| vec![bots[first], bots[second]]))
+                .collect()
+        };
+        sides
+            .iter()
+            .flat_map(|bots| {
+                (0..games as u64).map(move |game| Self {
+                    bots: bots.clone(),
+                    seed: first_seed + game,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How one bot did in one game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerResult {
+    pub bot: BotDifficulty,
+    pub pieces: usize,
+    pub lines: usize,
+    /// Garbage lines sent to the opponent.
+    pub attack: usize,
+    /// Seconds until the bot topped out, or the whole game when it did not.
+    pub seconds: f64,
+    /// Set for the last bot standing, and for a benchmark bot that lasted until the time limit.
+    pub is_winner: bool,
+}
+
+impl PlayerResult {
+    pub fn pieces_per_second(&self) -> f64 {
+        pieces_per_second(self.pieces, self.seconds)
+    }
+
+    pub fn attack_per_piece(&self) -> f64 {
+        if self.pieces == 0 { 0.0 } else { self.attack as f64 / self.pieces as f64 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    pub seed: u64,
+    /// Seconds the game lasted, at most the time limit.
+    pub seconds: f64,
+    pub players: Vec<PlayerResult>,
+}
+
+/// Totals of one bot over every game it played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotSummary {
+    pub bot: BotDifficulty,
+    pub games: usize,
+    pub wins: usize,
+    pub pieces: usize,
+    pub lines: usize,
+    pub attack: usize,
+    pub seconds: f64,
+}
+
+impl BotSummary {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.wins as f64 / self.games as f64 }
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        pieces_per_second(self.pieces, self.seconds)
+    }
+
+    pub fn attack_per_piece(&self) -> f64 {
+        if self.pieces == 0 { 0.0 } else { self.attack as f64 / self.pieces as f64 }
+    }
+
+    /// Lines cleared before topping out, on average per game.
+    pub fn lines_survived(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.lines as f64 / self.games as f64 }
+    }
+}
+
+/// Plays `pairing` frame by frame without a window until one bot is left standing, every bot
+/// has topped out, or `time_limit_seconds` of game time have passed.
+pub fn play(pairing: &Pairing, time_limit_seconds: f64) -> GameResult {
+    let mut versus = Versus::new(pairing.bots.len(), pairing.seed, |_| {});
+    for (index, &difficulty) in pairing.bots.iter().enumerate() {
+        versus.bots[index] = Some(Bot::new(difficulty, pairing.seed + index as u64));
+    }
+
+    let mut topped_out_at: Vec<Option<f64>> = vec![None; pairing.bots.len()];
+    while versus.status() == MatchStatus::Playing && versus.elapsed_seconds < time_limit_seconds {
+        versus.update(1.0 / FRAMES_PER_SECOND);
+        for (seconds, game) in topped_out_at.iter_mut().zip(&versus.players) {
+            if game.is_game_over && seconds.is_none() {
+                *seconds = Some(versus.elapsed_seconds);
+            }
+        }
+    }
+
+    let status = versus.status();
+    let players = versus
+        .players
+        .iter()
+        .zip(&pairing.bots)
+        .zip(topped_out_at)
+        .enumerate()
+        .map(|(index, ((game, &bot), topped_out_at))| PlayerResult {
+            bot,
+            pieces: game.pieces_placed,
+            lines: game.lines_cleared,
+            attack: game.lines_sent,
+            seconds: topped_out_at.unwrap_or(versus.elapsed_seconds),
+            is_winner: match status {
+                MatchStatus::Won(winner) => winner == index,
+                MatchStatus::Draw => false,
+                // Out of time: only a benchmark counts as won by lasting
+                MatchStatus::Playing => pairing.bots.len() == 1,
+            },
+        })
+        .collect();
+    GameResult {
+        seed: pairing.seed,
+        seconds: versus.elapsed_seconds,
+        players,
+    }
+}
+
+/// Plays every pairing on `threads` threads. Results are in the order of `pairings`.
+pub fn run(pairings: &[Pairing], time_limit_seconds: f64, threads: usize) -> Vec<GameResult> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<GameResult>>> = Mutex::new(vec![None; pairings.len()]);
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, pairings.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(pairing) = pairings.get(index) else {
+                        break;
+                    };
+                    let result = play(pairing, time_limit_seconds);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+/// Adds up the results of each bot, in the order the bots first appear.
+pub fn summarize(results: &[GameResult]) -> Vec<BotSummary> {
+    let mut summaries: Vec<BotSummary> = Vec::new();
+    for player in results.iter().flat_map(|result| &result.players) {
+        let index = match summaries.iter().position(|summary| summary.bot == player.bot) {
+            Some(index) => index,
+            None => {
+                summaries.push(BotSummary {
+                    bot: player.bot,
+                    games: 0,
+                    wins: 0,
+                    pieces: 0,
+                    lines: 0,
+                    attack: 0,
+                    seconds: 0.0,
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.games += 1;
+        summary.wins += player.is_winner as usize;
+        summary.pieces += player.pieces;
+        summary.lines += player.lines;
+        summary.attack += player.attack;
+        summary.seconds += player.seconds;
+    }
+    summaries
+}
+
+/// One line per bot per game, after a header line.
+pub fn to_csv(results: &[GameResult]) -> String {
+    let mut csv = String::from("seed,player,bot,won,pieces,lines,attack,seconds,pps,app\n");
+    for result in results {
+        for (index, player) in result.players.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{:.3},{:.3},{:.3}\n",
+                result.seed,
+                index + 1,
+                player.bot.name(),
+                player.is_winner,
+                player.pieces,
+                player.lines,
+                player.attack,
+                player.seconds,
+                player.pieces_per_second(),
+                player.attack_per_piece()
+            ));
+        }
+    }
+    csv
+}
+
+/// The games and the summary of each bot as one JSON document.
+pub fn to_json(results: &[GameResult]) -> Value {
+    let games: Vec<Value> = results
+        .iter()
+        .map(|result| {
+            let players: Vec<Value> = result
+                .players
+                .iter()
+                .map(|player| {
+                    json!({
+                        "bot": player.bot.name(),
+                        "won": player.is_winner,
+                        "pieces": player.pieces,
+                        "lines": player.lines,
+                        "attack": player.attack,
+                        "seconds": player.seconds,
+                        "pps": player.pieces_per_second(),
+                        "app": player.attack_per_piece(),
+                    })
+                })
+                .collect();
+            json!({ "seed": result.seed, "seconds": result.seconds, "players": players })
+        })
+        .collect();
+    let summary: Vec<Value> = summarize(results)
+        .iter()
+        .map(|summary| {
+            json!({
+                "bot": summary.bot.name(),
+                "games": summary.games,
+                "win_rate": summary.win_rate(),
+                "pps": summary.pieces_per_second(),
+                "app": summary.attack_per_piece(),
+                "lines_survived": summary.lines_survived(),
+            })
+        })
+        .collect();
+    json!({ "games": games, "summary": summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin_pairs_every_two_bots_or_benchmarks_a_single_one() {
+        let bots = [BotDifficulty::Easy, BotDifficulty::Medium, BotDifficulty::Hard];
+
+        let pairings = Pairing::round_robin(&bots, 2, 10);
+
+        assert_eq!(pairings.len(), 3 * 2);
+        assert_eq!(pairings[1].bots, vec![BotDifficulty::Easy, BotDifficulty::Medium]);
+        assert_eq!(pairings[1].seed, 11);
+        assert_eq!(Pairing::round_robin(&bots[..1], 3, 0)[2].bots, vec![BotDifficulty::Easy]);
+    }
+
+    #[test]
+    fn games_run_headless_in_parallel_and_are_reproducible() {
+        let pairings = Pairing::round_robin(&[BotDifficulty::Easy, BotDifficulty::Easy], 2, 3);
+
+        let results = run(&pairings, 10.0, 2);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].seed, 4);
+        assert_eq!(results[0], play(&pairings[0], 10.0));
+        for player in &results[0].players {
+            assert!(player.pieces > 0);
+            assert!(player.seconds <= 10.0 + 1.0 / FRAMES_PER_SECOND);
+        }
+    }
+
+    #[test]
+    fn summaries_and_reports_add_up_every_game() {
+        let player = |bot, is_winner, pieces, attack| PlayerResult {
+            bot,
+            pieces,
+            lines: 8,
+            attack,
+            seconds: 10.0,
+            is_winner,
+        };
+        let results = vec![
+            GameResult {
+                seed: 1,
+                seconds: 10.0,
+                players: vec![player(BotDifficulty::Hard, true, 30, 6), player(BotDifficulty::Easy, false, 10, 0)],
+            },
+            GameResult {
+                seed: 2,
+                seconds: 10.0,
+                players: vec![player(BotDifficulty::Hard, false, 20, 4), player(BotDifficulty::Easy, true, 10, 1)],
+            },
+        ];
+
+        let summary = &summarize(&results)[0];
+        assert_eq!(summary.bot, BotDifficulty::Hard);
+        assert_eq!(summary.win_rate(), 0.5);
+        assert_eq!(summary.pieces_per_second(), 2.5);
+        assert_eq!(summary.attack_per_piece(), 0.2);
+        assert_eq!(summary.lines_survived(), 8.0);
+
+        let csv = to_csv(&results);
+        assert_eq!(csv.lines().count(), 1 + 4);
+        assert_eq!(csv.lines().nth(1), Some("1,1,Hard,true,30,8,6,10.000,3.000,0.200"));
+
+        let report: Value = serde_json::from_str(&to_json(&results).to_string()).unwrap();
+        assert_eq!(report["games"].as_array().unwrap().len(), 2);
+        assert_eq!(report["summary"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{core::scoring::LineClear, enums::ClearType};
+
+/// Extra garbage lines sent by combos, indexed by the combo count (0 for the first clear).
+/// Longer combos send the last value.
+pub(crate) const COMBO_ATTACK: [usize; 13] = [0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// Extra garbage line sent by a back-to-back difficult clear.
+pub(crate) const BACK_TO_BACK_ATTACK_BONUS: usize = 1;
+
+/// Garbage lines sent by a perfect clear, instead of the usual attack.
+pub(crate) const PERFECT_CLEAR_ATTACK: usize = 10;
+
+/// Default frames incoming garbage waits before it can enter the board.
+pub(crate) const DEFAULT_GARBAGE_DELAY_FRAMES: u32 = 30;
+
+/// Default most garbage rows entering the board after a single lock.
+pub(crate) const DEFAULT_GARBAGE_CAP: usize = 8;
+
+/// Garbage lines sent by a clear type before combo and back-to-back bonuses (guideline table).
+pub fn clear_type_attack(clear_type: ClearType) -> usize {
+    match clear_type {
+        ClearType::Single => 0,
+        ClearType::Double => 1,
+        ClearType::Triple => 2,
+        ClearType::Tetris => 4,
+        ClearType::TSpinMini => 0,
+        ClearType::TSpinMiniSingle => 0,
+        ClearType::TSpinMiniDouble => 1,
+        ClearType::TSpin => 0,
+        ClearType::TSpinSingle => 2,
+        ClearType::TSpinDouble => 4,
+        ClearType::TSpinTriple => 6,
+    }
+}
+
+/// Returns the garbage lines sent by a line clear, including combo and back-to-back bonuses.
+/// A perfect clear sends [`PERFECT_CLEAR_ATTACK`] lines instead.
+pub fn attack_lines(clear: &LineClear) -> usize {
+    if clear.is_perfect_clear {
+        return PERFECT_CLEAR_ATTACK;
+    }
+
+    let combo = COMBO_ATTACK[clear.combo.min(COMBO_ATTACK.len() - 1)];
+    let back_to_back = if clear.is_back_to_back { BACK_TO_BACK_ATTACK_BONUS } else { 0 };
+    clear_type_attack(clear.clear_type) + combo + back_to_back
+}
+
+/// Where the holes of received garbage rows are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HolePattern {
+    /// Every garbage row has its hole in the same column.
+    SameColumn,
+    /// The rows of one attack share a hole, each attack picks a new random column (guideline).
+    PerAttack,
+    /// Every row has its hole in a random column.
+    PerRow,
+}
+
+impl HolePattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HolePattern::SameColumn => "Same column",
+            HolePattern::PerAttack => "Per attack",
+            HolePattern::PerRow => "Per row",
+        }
+    }
+
+    /// Name used in rule files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            HolePattern::SameColumn => "same_column",
+            HolePattern::PerAttack => "per_attack",
+            HolePattern::PerRow => "per_row",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [HolePattern::SameColumn, HolePattern::PerAttack, HolePattern::PerRow]
+            .into_iter()
+            .find(|pattern| pattern.key() == key)
+    }
+
+    /// The next pattern in the cycle Per attack, Per row, Same column.
+    pub fn next(&self) -> Self {
+        match self {
+            HolePattern::PerAttack => HolePattern::PerRow,
+            HolePattern::PerRow => HolePattern::SameColumn,
+            HolePattern::SameColumn => HolePattern::PerAttack,
+        }
+    }
+}
+
+/// How garbage is received by a [`crate::core::game::Game`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GarbageSettings {
+    /// Frames an attack waits in the incoming meter before it can enter the board.
+    pub delay_frames: u32,
+    /// Most garbage rows entering the board after a single lock, the rest keep waiting.
+    pub cap_per_lock: usize,
+    pub hole_pattern: HolePattern,
+}
+
+impl Default for GarbageSettings {
+    fn default() -> Self {
+        Self {
+            delay_frames: DEFAULT_GARBAGE_DELAY_FRAMES,
+            cap_per_lock: DEFAULT_GARBAGE_CAP,
+            hole_pattern: HolePattern::PerAttack,
+        }
+    }
+}
+
+/// One attack waiting in the incoming meter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncomingGarbage {
+    pub lines: usize,
+    /// Frames left before the attack can enter the board.
+    pub frames_left: u32,
+}
+
+/// Garbage sent by opponents and not received yet, oldest attack first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GarbageMeter {
+    pub attacks: Vec<IncomingGarbage>,
+}
+
+impl GarbageMeter {
+    /// Queues an attack of `lines` lines that can enter the board after `delay_frames` frames.
+    pub fn push(&mut self, lines: usize, delay_frames: u32) {
+        if lines > 0 {
+            self.attacks.push(IncomingGarbage {
+                lines,
+                frames_left: delay_frames,
+            });
+        }
+    }
+
+    /// Total lines waiting.
+    pub fn total(&self) -> usize {
+        self.attacks.iter().map(|attack| attack.lines).sum()
+    }
+
+    /// Lines whose delay has run out.
+    pub fn ready(&self) -> usize {
+        self.attacks
+            .iter()
+            .filter(|attack| attack.frames_left == 0)
+            .map(|attack| attack.lines)
+            .sum()
+    }
+
+    /// Counts one frame down on every waiting attack.
+    pub fn tick(&mut self) {
+        for attack in &mut self.attacks {
+            attack.frames_left = attack.frames_left.saturating_sub(1);
+        }
+    }
+
+    /// Cancels waiting garbage with an outgoing attack of `lines` lines, oldest first.
+    /// Returns the lines left to send to opponents.
+    pub fn offset(&mut self, mut lines: usize) -> usize {
+        while lines > 0 && !self.attacks.is_empty() {
+            let cancelled = lines.min(self.attacks[0].lines);
+            self.attacks[0].lines -= cancelled;
+            lines -= cancelled;
+            if self.attacks[0].lines == 0 {
+                self.attacks.remove(0);
+            }
+        }
+        lines
+    }
+
+    /// Removes up to `cap` ready lines and returns them as attacks, oldest first.
+    pub fn take_ready(&mut self, cap: usize) -> Vec<usize> {
+        let mut taken = Vec::new();
+        let mut remaining = cap;
+        while remaining > 0 && self.attacks.first().is_some_and(|attack| attack.frames_left == 0) {
+            let lines = remaining.min(self.attacks[0].lines);
+            self.attacks[0].lines -= lines;
+            remaining -= lines;
+            taken.push(lines);
+            if self.attacks[0].lines == 0 {
+                self.attacks.remove(0);
+            }
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(clear_type: ClearType, combo: usize, is_back_to_back: bool) -> LineClear {
+        LineClear {
+            clear_type,
+            combo,
+            is_back_to_back,
+            is_perfect_clear: false,
+            points: 0,
+        }
+    }
+
+    #[test]
+    fn attack_adds_combo_and_back_to_back_bonuses() {
+        assert_eq!(attack_lines(&clear(ClearType::Single, 0, false)), 0);
+        assert_eq!(attack_lines(&clear(ClearType::Tetris, 0, false)), 4);
+        assert_eq!(attack_lines(&clear(ClearType::Tetris, 0, true)), 5);
+        assert_eq!(attack_lines(&clear(ClearType::TSpinDouble, 2, true)), 6);
+        assert_eq!(attack_lines(&clear(ClearType::Single, 30, false)), 5);
+
+        let perfect_clear = LineClear {
+            is_perfect_clear: true,
+            ..clear(ClearType::Single, 0, false)
+        };
+        assert_eq!(attack_lines(&perfect_clear), PERFECT_CLEAR_ATTACK);
+    }
+
+    #[test]
+    fn offset_cancels_the_oldest_garbage_first() {
+        let mut meter = GarbageMeter::default();
+        meter.push(2, 10);
+        meter.push(3, 20);
+
+        assert_eq!(meter.offset(3), 0);
+        assert_eq!(meter.attacks, vec![IncomingGarbage { lines: 2, frames_left: 20 }]);
+        assert_eq!(meter.offset(5), 3);
+        assert_eq!(meter.total(), 0);
+    }
+
+    #[test]
+    fn only_garbage_past_its_delay_is_taken_up_to_the_cap() {
+        let mut meter = GarbageMeter::default();
+        meter.push(4, 1);
+        meter.push(6, 1);
+        meter.push(1, 5);
+        assert!(meter.take_ready(8).is_empty());
+
+        meter.tick();
+        assert_eq!(meter.ready(), 10);
+        assert_eq!(meter.take_ready(8), vec![4, 4]);
+        assert_eq!(meter.total(), 3);
+    }
+}
@@ -1,29 +1,165 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::LazyLock};
+
+use macroquad::color::Color;
+use rand::Rng;
 
 use crate::{
     core::{
         constants::{COLS, ROWS},
         piece::Piece,
+        piece_definition::PieceSet,
+        point_2d::Point2D,
     },
-    enums::cell_type::CellType,
+    enums::{cell_type::CellType, piece_type::PieceType},
 };
 
+/// Character of an empty cell in the plain-text notation of [`Board::from_ascii`].
+pub const ASCII_EMPTY: char = '.';
+
+/// Character of a garbage cell, and of cells left by pieces without a one-letter name.
+pub const ASCII_GARBAGE: char = 'G';
+
+/// Character marking the cells of the active piece, which are empty on the board itself.
+pub const ASCII_PIECE: char = '@';
+
+/// Letter and color of each piece with a one-letter name, tetrominoes first, shared by reading
+/// and writing the notation.
+static PIECE_LETTERS: LazyLock<Vec<(char, Color)>> = LazyLock::new(|| {
+    PieceSet::mixed()
+        .definitions
+        .iter()
+        .filter_map(|definition| {
+            let letter = match &definition.piece_type {
+                PieceType::Custom(name) if name.chars().count() == 1 => name.chars().next(),
+                PieceType::Custom(_) => None,
+                tetromino => format!("{:?}", tetromino).chars().next(),
+            };
+            letter.map(|letter| (letter, definition.color))
+        })
+        .collect()
+});
+
+#[derive(Clone)]
 pub struct Board {
     pub rows: usize,
     pub cols: usize,
     pub cells: Vec<Vec<CellType>>,
+    /// Value of `clock_seconds` when each filled cell was set, `None` for empty cells.
+    pub lock_times: Vec<Vec<Option<f64>>>,
+    /// Cells taken by the active pieces of other players sharing the board.
+    /// They block movement like filled cells but are not part of the stack.
+    pub blocked_cells: Vec<Point2D>,
+    /// Seconds of play so far, advanced by the game and used to stamp locked cells.
+    pub clock_seconds: f64,
+    /// Value of `clock_seconds` when the last piece was placed.
+    pub last_lock_seconds: Option<f64>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
-        let filled_cells = vec![vec![CellType::Empty; COLS]; ROWS];
+        Self::with_size(ROWS, COLS)
+    }
+
+    /// Creates an empty board of the given size, e.g. a wide board shared by two players.
+    pub fn with_size(rows: usize, cols: usize) -> Self {
         Self {
-            rows: ROWS,
-            cols: COLS,
-            cells: filled_cells,
+            rows,
+            cols,
+            cells: vec![vec![CellType::Empty; cols]; rows],
+            lock_times: vec![vec![None; cols]; rows],
+            blocked_cells: Vec::new(),
+            clock_seconds: 0.0,
+            last_lock_seconds: None,
+        }
+    }
+
+    /// Reads a board written one row per line from the top, e.g. `..TTT.....`: `.` is an empty
+    /// cell, `G` garbage and a piece letter (`I`, `T`, `X`...) a cell filled with that piece's
+    /// color. Blank lines and indentation are ignored, and boards of fewer rows than the
+    /// standard height get empty rows on top, so only the stack needs to be written.
+    pub fn from_ascii(text: &str) -> Result<Self, String> {
+        Self::from_ascii_with_piece(text).map(|(board, _)| board)
+    }
+
+    /// Like [`Board::from_ascii`], also returning the cells marked `@` as the active piece.
+    pub fn from_ascii_with_piece(text: &str) -> Result<(Self, Vec<Point2D>), String> {
+        let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let cols = lines.first().map_or(COLS, |line| line.chars().count());
+        let rows = lines.len().max(ROWS);
+        let top = rows - lines.len();
+
+        let mut board = Self::with_size(rows, cols);
+        let mut piece_cells = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if line.chars().count() != cols {
+                return Err(format!("row {}: rows must all be {} cells wide", index + 1, cols));
+            }
+            for (col, character) in line.chars().enumerate() {
+                let row = top + index;
+                if character == ASCII_PIECE {
+                    piece_cells.push(Point2D::new(row as isize, col as isize));
+                    continue;
+                }
+                let cell = Self::cell_from_ascii(character)
+                    .ok_or_else(|| format!("row {}: unknown cell `{}`", index + 1, character))?;
+                board.set_cell(row, col, cell);
+            }
+        }
+        Ok((board, piece_cells))
+    }
+
+    /// The cell written as `character`, if it is one of the notation's cells.
+    pub fn cell_from_ascii(character: char) -> Option<CellType> {
+        match character {
+            ASCII_EMPTY => Some(CellType::Empty),
+            ASCII_GARBAGE => Some(CellType::Garbage),
+            letter => PIECE_LETTERS
+                .iter()
+                .find(|(piece_letter, _)| *piece_letter == letter)
+                .map(|(_, color)| CellType::Filled(*color)),
         }
     }
 
+    /// Writes the board in the notation of [`Board::from_ascii`], one line per row. Empty rows
+    /// above the stack are left out, as reading the text back adds them again.
+    pub fn to_ascii(&self) -> String {
+        self.to_ascii_with_piece(None)
+    }
+
+    /// Like [`Board::to_ascii`], with the cells of `piece` marked `@`.
+    pub fn to_ascii_with_piece(&self, piece: Option<&Piece>) -> String {
+        let letter_of = |cell: &CellType| match cell {
+            CellType::Empty => ASCII_EMPTY,
+            CellType::Garbage => ASCII_GARBAGE,
+            CellType::Filled(color) => PIECE_LETTERS
+                .iter()
+                .find(|(_, piece_color)| piece_color == color)
+                .map_or(ASCII_GARBAGE, |(letter, _)| *letter),
+        };
+
+        let mut lines: Vec<Vec<char>> = self.cells.iter().map(|row| row.iter().map(letter_of).collect()).collect();
+        for block in piece.map(Piece::get_blocks_position).unwrap_or_default() {
+            // Blocks above the board are not shown
+            if block.y >= 0
+                && let Some(cell) = lines.get_mut(block.y as usize).and_then(|row| row.get_mut(block.x as usize))
+            {
+                *cell = ASCII_PIECE;
+            }
+        }
+
+        lines
+            .iter()
+            .skip_while(|line| line.iter().all(|&cell| cell == ASCII_EMPTY))
+            .map(|line| line.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
     pub fn get_filled_cells(&self) -> HashMap<(usize, usize), CellType> {
         self.cells
             .iter()
@@ -41,23 +177,129 @@ impl Board {
     }
 
     pub fn set_cell(&mut self, row: usize, col: usize, cell_type: CellType) {
-        if Piece::is_in_bounds(row, col) {
+        if row < self.rows && col < self.cols {
             self.cells[row][col] = cell_type;
+            self.lock_times[row][col] = (cell_type != CellType::Empty).then_some(self.clock_seconds);
         }
     }
+
+    /// Seconds since the cell was filled, `None` for empty cells.
+    pub fn lock_age(&self, row: usize, col: usize) -> Option<f64> {
+        let lock_time = (*self.lock_times.get(row)?.get(col)?)?;
+        Some(self.clock_seconds - lock_time)
+    }
     
+    /// Checks that every block fits inside the board columns, above the floor and on an empty cell.
+    /// Blocks above the top row are allowed so pieces can rotate and spawn partially hidden.
+    pub fn is_valid_position(&self, blocks: &[Point2D]) -> bool {
+        blocks.iter().all(|block| !self.is_occupied(block.y, block.x))
+    }
+
+    /// Checks whether the cell is a wall, the floor or a filled cell. Cells above the board are free.
+    pub fn is_occupied(&self, row: isize, col: isize) -> bool {
+        if col < 0 || col >= self.cols as isize || row >= self.rows as isize {
+            return true;
+        }
+        (row >= 0 && self.cells[row as usize][col as usize] != CellType::Empty)
+            || self.blocked_cells.contains(&Point2D::new(row, col))
+    }
+
+    /// Moves the piece up until it no longer overlaps the stack or is entirely above the board,
+    /// e.g. after garbage rows were pushed in under it.
+    pub fn lift_out_of_stack(&self, piece: &mut Piece) {
+        while !self.is_valid_position(&piece.get_blocks_position())
+            && piece.get_blocks_position().iter().any(|block| block.y >= 0)
+        {
+            piece.position.y -= 1;
+        }
+    }
+
     pub fn place_piece(&mut self, piece: &Piece) {
         for block in piece.get_blocks_position() {
             self.set_cell(block.y as usize, block.x as usize, CellType::Filled(piece.color));
         }
+        self.last_lock_seconds = Some(self.clock_seconds);
+    }
+
+    /// Removes every full row, moving the rows above it down, and returns the removed rows
+    /// from the top of the board to the bottom.
+    pub fn clear_full_rows(&mut self) -> Vec<Vec<CellType>> {
+        let is_full = |row: &Vec<CellType>| row.iter().all(|cell| *cell != CellType::Empty);
+        let full_rows: Vec<usize> = (0..self.rows).filter(|&row| is_full(&self.cells[row])).collect();
+
+        let mut removed = Vec::with_capacity(full_rows.len());
+        for &row in full_rows.iter().rev() {
+            removed.push(self.cells.remove(row));
+            self.lock_times.remove(row);
+        }
+        for _ in 0..full_rows.len() {
+            self.cells.insert(0, vec![CellType::Empty; self.cols]);
+            self.lock_times.insert(0, vec![None; self.cols]);
+        }
+
+        removed.reverse();
+        removed
+    }
+
+    /// Pushes the whole stack up and fills the bottom rows with garbage, one row per entry
+    /// of `hole_columns` with an empty cell at that column. The last entry ends up at the bottom.
+    /// Returns `true` if filled cells were pushed off the top of the board.
+    pub fn add_garbage_rows(&mut self, hole_columns: &[usize]) -> bool {
+        let count = hole_columns.len().min(self.rows);
+        let overflowed = self.cells[..count]
+            .iter()
+            .any(|row| row.iter().any(|cell| *cell != CellType::Empty));
+
+        self.cells.drain(..count);
+        self.lock_times.drain(..count);
+        for &hole in &hole_columns[hole_columns.len() - count..] {
+            let mut row = vec![CellType::Garbage; self.cols];
+            let mut lock_times = vec![Some(self.clock_seconds); self.cols];
+            if hole < self.cols {
+                row[hole] = CellType::Empty;
+                lock_times[hole] = None;
+            }
+            self.cells.push(row);
+            self.lock_times.push(lock_times);
+        }
+
+        overflowed
+    }
+
+    /// Generates the hole columns of `count` garbage rows. Each row keeps the hole of the row
+    /// before it, except with probability `messiness` where it moves to another random column.
+    /// `previous_hole` continues the pattern of garbage already on the board.
+    pub fn generate_garbage_holes<R: Rng>(
+        &self,
+        count: usize,
+        messiness: f64,
+        previous_hole: Option<usize>,
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let mut holes = Vec::with_capacity(count);
+        let mut hole = previous_hole;
+        for _ in 0..count {
+            let next_hole = match hole {
+                Some(column) if !rng.random_bool(messiness.clamp(0.0, 1.0)) => column,
+                Some(column) if self.cols > 1 => {
+                    // Move the hole to any other column
+                    let offset = rng.random_range(1..self.cols);
+                    (column + offset) % self.cols
+                }
+                _ => rng.random_range(0..self.cols),
+            };
+            holes.push(next_hole);
+            hole = Some(next_hole);
+        }
+        holes
     }
 
     pub fn get_board_representation(&self) -> Vec<Vec<usize>> {
-        let mut matrix = vec![vec![0 as usize; self.cols]; self.rows];
+        let mut matrix = vec![vec![0; self.cols]; self.rows];
         self.cells.iter().enumerate().for_each(|(row, cells)| {
             cells.iter().enumerate().for_each(|(col, cell)| {
-                if let CellType::Filled(_color) = cell {
-                    matrix[row][col] = 1 as usize;
+                if *cell != CellType::Empty {
+                    matrix[row][col] = 1;
                 }
             });
         });
@@ -74,6 +316,67 @@ impl Board {
     }
 }
 
+#[test]
+fn test_add_garbage_rows_pushes_the_stack_up() {
+    use macroquad::color::BLUE;
+
+    let mut board = Board::new();
+    board.set_cell(19, 3, CellType::Filled(BLUE));
+
+    let overflowed = board.add_garbage_rows(&[0, 5]);
+
+    assert!(!overflowed);
+    assert_eq!(board.cells[17][3], CellType::Filled(BLUE));
+    assert_eq!(board.cells[18][0], CellType::Empty);
+    assert_eq!(board.cells[18][1], CellType::Garbage);
+    assert_eq!(board.cells[19][5], CellType::Empty);
+    assert_eq!(board.cells[19].iter().filter(|&&cell| cell == CellType::Garbage).count(), COLS - 1);
+}
+
+#[test]
+fn test_add_garbage_rows_reports_overflow() {
+    use macroquad::color::BLUE;
+
+    let mut board = Board::new();
+    board.set_cell(0, 3, CellType::Filled(BLUE));
+    assert!(board.add_garbage_rows(&[0]));
+}
+
+#[test]
+fn test_lock_times_follow_cells_when_rows_are_cleared() {
+    use macroquad::color::BLUE;
+
+    let mut board = Board::new();
+    board.set_cell(18, 2, CellType::Filled(BLUE));
+    board.clock_seconds = 3.0;
+    for col in 0..COLS {
+        board.set_cell(19, col, CellType::Filled(BLUE));
+    }
+    board.clock_seconds = 5.0;
+
+    let removed = board.clear_full_rows();
+
+    assert_eq!(removed.len(), 1);
+    assert_eq!(board.cells[19][2], CellType::Filled(BLUE));
+    assert_eq!(board.lock_age(19, 2), Some(5.0));
+    assert_eq!(board.lock_age(19, 3), None);
+    assert_eq!(board.lock_age(0, 0), None);
+}
+
+#[test]
+fn test_generate_garbage_holes_respects_messiness() {
+    let board = Board::new();
+    let mut rng = rand::rng();
+
+    let clean = board.generate_garbage_holes(20, 0.0, Some(4), &mut rng);
+    assert!(clean.iter().all(|&hole| hole == 4));
+
+    let messy = board.generate_garbage_holes(20, 1.0, Some(4), &mut rng);
+    assert!(messy.windows(2).all(|pair| pair[0] != pair[1]));
+    assert_ne!(messy[0], 4);
+    assert!(messy.iter().all(|&hole| hole < COLS));
+}
+
 #[test]
 fn test_board_initialization() {
     let board = Board::new();
@@ -82,7 +385,7 @@ fn test_board_initialization() {
     assert_eq!(board.cells.len(), ROWS);
     for row in &board.cells {
         assert_eq!(row.len(), COLS);
-        assert_eq!(row.iter().all(|&cell| cell == CellType::Empty), true);
+        assert!(row.iter().all(|&cell| cell == CellType::Empty));
     }
 }
 
@@ -92,3 +395,36 @@ fn test_board_initialization() {
 //     board.set_cell(0, 0, CellType::Filled(BLUE));
 //     assert_eq!(board.cells[0][0], CellType::Filled(BLUE));
 // }
+
+#[test]
+fn test_ascii_boards_read_back_the_same() {
+    use crate::enums::RotationDirection;
+
+    let text = "
+        ....@@@...
+        T....@..X.
+        TTIIIIG.XX
+    ";
+
+    let (board, piece_cells) = Board::from_ascii_with_piece(text).unwrap();
+
+    assert_eq!(board.rows, ROWS);
+    assert_eq!(board.cells[19][6], CellType::Garbage);
+    assert_eq!(board.cells[17][4], CellType::Empty);
+    assert_eq!(piece_cells, vec![Point2D::new(17, 4), Point2D::new(17, 5), Point2D::new(17, 6), Point2D::new(18, 5)]);
+
+    let mut piece = Piece::new(&PieceType::T, Point2D::new(17, 5)).unwrap();
+    piece.rotate(RotationDirection::Clockwise);
+    piece.rotate(RotationDirection::Clockwise);
+    assert_eq!(
+        board.to_ascii_with_piece(Some(&piece)),
+        "....@@@...\nT....@..X.\nTTIIIIG.XX\n"
+    );
+    assert_eq!(board.to_ascii(), "T.......X.\nTTIIIIG.XX\n");
+}
+
+#[test]
+fn test_ascii_boards_report_bad_rows() {
+    assert!(Board::from_ascii("..........\n.........").is_err());
+    assert!(Board::from_ascii("....?.....").is_err());
+}
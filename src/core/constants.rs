@@ -1,13 +1,16 @@
 // Board dimensions
-pub(crate) const ROWS: usize = 20;
-pub(crate) const COLS: usize = 10;
-pub(crate) const CELL_SIZE: f32 = 27.0; // size of each cell in pixels
-pub(crate) const BORDER_THICKNESS: f32 = 4.0;
+pub const ROWS: usize = 20;
+pub const COLS: usize = 10;
+pub const CELL_SIZE: f32 = 27.0; // size of each cell in pixels
+pub const BORDER_THICKNESS: f32 = 4.0;
 
 // Calculate the board's pixel size
-pub(crate) const BOARD_WIDTH: f32 = CELL_SIZE * COLS as f32;
-pub(crate) const BOARD_HEIGHT: f32 = CELL_SIZE * ROWS as f32;
+pub const BOARD_WIDTH: f32 = CELL_SIZE * COLS as f32;
+pub const BOARD_HEIGHT: f32 = CELL_SIZE * ROWS as f32;
 
 // Board top-left corner position
-pub(crate) const BOARD_X: f32 = 50.0;
-pub(crate) const BOARD_Y: f32 = 30.0;
\ No newline at end of file
+pub const BOARD_X: f32 = 50.0;
+pub const BOARD_Y: f32 = 30.0;
+
+// Game logic runs at a fixed frame rate, timings are expressed in frames
+pub const FRAMES_PER_SECOND: f64 = 60.0;
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use crate::{
+    core::{game::Game, piece::Piece, placement::sorted_blocks, point_2d::Point2D},
+    enums::{FinesseInput, RotationDirection},
+};
+
+/// Key presses explored to find a finesse path, soft drop last so paths without it are
+/// preferred among the shortest.
+const FINESSE_INPUTS: [FinesseInput; 7] = [
+    FinesseInput::MoveLeft,
+    FinesseInput::MoveRight,
+    FinesseInput::DasLeft,
+    FinesseInput::DasRight,
+    FinesseInput::RotateClockwise,
+    FinesseInput::RotateCounterClockwise,
+    FinesseInput::SoftDrop,
+];
+
+/// A position of the piece found by the search, with the state and press it was reached from.
+struct FinesseState {
+    piece: Piece,
+    parent: Option<(usize, FinesseInput)>,
+}
+
+impl Game {
+    /// Returns the fewest key presses taking the current piece from where it is to the
+    /// placement covering the `target` cells once hard dropped, or `None` when it cannot get there.
+    /// A held move key counts as one press, going as far as auto-shift takes it, and so does
+    /// a soft drop to the stack. The hard drop ending the path is not part of it.
+    pub fn finesse_path(&self, target: &[Point2D]) -> Option<Vec<FinesseInput>> {
+        let start = self.current_piece.clone()?;
+        let mut target = target.to_vec();
+        target.sort_by_key(|block| (block.y, block.x));
+
+        // Breadth-first search over the positions of the piece, each state recording the state
+        // and press it was reached from
+        let mut states = vec![FinesseState {
+            piece: start.clone(),
+            parent: None,
+        }];
+        let mut seen: HashSet<Vec<Point2D>> = HashSet::from([sorted_blocks(&start)]);
+        let mut scratch = self.clone();
+        let mut index = 0;
+        while index < states.len() {
+            let piece = states[index].piece.clone();
+            scratch.current_piece = Some(piece.clone());
+            while !scratch.detect_collision() {
+                scratch.move_piece_down();
+            }
+            if scratch.current_piece.as_ref().map(sorted_blocks).as_ref() == Some(&target) {
+                return Some(path_to(&states, index));
+            }
+
+            for input in FINESSE_INPUTS {
+                scratch.current_piece = Some(piece.clone());
+                scratch.apply_finesse_input(input);
+                let Some(moved) = scratch.current_piece.take() else {
+                    continue;
+                };
+                if seen.insert(sorted_blocks(&moved)) {
+                    states.push(FinesseState {
+                        piece: moved,
+                        parent: Some((index, input)),
+                    });
+                }
+            }
+            index += 1;
+        }
+        None
+    }
+
+    /// Moves the current piece as one key press of a finesse path would.
+    fn apply_finesse_input(&mut self, input: FinesseInput) {
+        match input {
+            FinesseInput::MoveLeft => self.move_piece_left(),
+            FinesseInput::MoveRight => self.move_piece_right(),
+            FinesseInput::DasLeft => self.shift_to_the_end(Game::move_piece_left),
+            FinesseInput::DasRight => self.shift_to_the_end(Game::move_piece_right),
+            FinesseInput::RotateClockwise => self.rotate_piece(RotationDirection::Clockwise),
+            FinesseInput::RotateCounterClockwise => self.rotate_piece(RotationDirection::CounterClockwise),
+            FinesseInput::SoftDrop => {
+                while !self.detect_collision() {
+                    self.move_piece_down();
+                }
+            }
+        }
+    }
+
+    /// Repeats `shift` until the current piece stops moving.
+    fn shift_to_the_end(&mut self, shift: fn(&mut Game)) {
+        loop {
+            let before = self.current_piece.as_ref().map(|piece| piece.position);
+            shift(self);
+            if self.current_piece.as_ref().map(|piece| piece.position) == before {
+                return;
+            }
+        }
+    }
+}
+
+/// Rebuilds the presses leading from the first state to `state`.
+fn path_to(states: &[FinesseState], mut state: usize) -> Vec<FinesseInput> {
+    let mut path = Vec::new();
+    while let Some((parent, input)) = states[state].parent {
+        path.push(input);
+        state = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::constants::{COLS, ROWS},
+        enums::{CellType, GameAction, PieceType},
+    };
+
+    fn game_with_piece(piece_type: PieceType) -> Game {
+        let mut game = Game::new();
+        game.start();
+        game.current_piece = game.spawn_piece_of_type(&piece_type);
+        game
+    }
+
+    /// Cells the current piece covers after `inputs`, hard dropped.
+    fn landing(game: &Game, inputs: &[FinesseInput]) -> Vec<Point2D> {
+        let mut played = game.clone();
+        for &input in inputs {
+            played.apply_finesse_input(input);
+        }
+        played.apply_finesse_input(FinesseInput::SoftDrop);
+        played.current_piece.unwrap().get_blocks_position()
+    }
+
+    #[test]
+    fn shifting_next_to_the_wall_uses_das_and_a_tap_back() {
+        let game = game_with_piece(PieceType::O);
+        let target = landing(&game, &[FinesseInput::DasLeft, FinesseInput::MoveRight]);
+
+        assert_eq!(game.finesse_path(&landing(&game, &[])), Some(vec![]));
+        assert_eq!(
+            game.finesse_path(&landing(&game, &[FinesseInput::MoveLeft; COLS])),
+            Some(vec![FinesseInput::DasLeft])
+        );
+        assert_eq!(
+            game.finesse_path(&target),
+            Some(vec![FinesseInput::DasLeft, FinesseInput::MoveRight])
+        );
+    }
+
+    #[test]
+    fn tucks_under_an_overhang_need_a_soft_drop() {
+        let mut game = game_with_piece(PieceType::O);
+        // A roof over columns 0 to 2, two rows above the floor
+        for col in 0..3 {
+            game.board.set_cell(ROWS - 3, col, CellType::Filled(BLUE));
+        }
+        let tuck = [Point2D::new(ROWS as isize - 2, 0), Point2D::new(ROWS as isize - 2, 1)]
+            .into_iter()
+            .flat_map(|block| [block, Point2D::new(block.y + 1, block.x)])
+            .collect::<Vec<_>>();
+
+        let path = game.finesse_path(&tuck).expect("the O piece can slide under the roof");
+
+        assert!(path.contains(&FinesseInput::SoftDrop));
+        assert_eq!(landing(&game, &path).len(), 4);
+    }
+
+    #[test]
+    fn extra_presses_count_as_a_finesse_fault() {
+        let mut game = game_with_piece(PieceType::T);
+        game.tracks_finesse = true;
+
+        // Straight down needs no press
+        game.press(GameAction::HardDrop);
+        assert_eq!(game.finesse_faults, 0);
+
+        // One column left takes one tap, not three
+        game.current_piece = game.spawn_piece_of_type(&PieceType::T);
+        for action in [GameAction::MoveLeft, GameAction::MoveLeft, GameAction::MoveRight] {
+            game.press(action);
+            game.release(action);
+        }
+        game.press(GameAction::HardDrop);
+        assert_eq!(game.finesse_faults, 1);
+
+        // Rotating twice the shorter way round is fine
+        game.current_piece = game.spawn_piece_of_type(&PieceType::T);
+        for action in [GameAction::RotateClockwise, GameAction::RotateClockwise] {
+            game.press(action);
+        }
+        game.press(GameAction::HardDrop);
+        assert_eq!(game.finesse_faults, 1);
+    }
+}
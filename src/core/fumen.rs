@@ -0,0 +1,704 @@
+use crate::{
+    core::{board::Board, game::Game, piece::Piece, piece_definition::PieceDefinition, point_2d::Point2D},
+    enums::{CellType, PieceType, RotationDirection},
+};
+
+/// Marks the start of fumen data in version 1.15, the only one written.
+const FUMEN_PREFIX: &str = "v115@";
+
+/// Prefixes of the same data as shown by the different fumen pages (view, mobile, editor).
+const FUMEN_PREFIXES: [&str; 3] = ["v115@", "m115@", "d115@"];
+
+/// Digits of the encoded data, worth 0 to 63.
+const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Characters a comment is written with once escaped, the printable ASCII characters.
+const COMMENT_CHARACTERS: &[u8; 95] =
+    b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+/// Escaped comment characters kept, longer comments are cut.
+const MAX_COMMENT_LENGTH: usize = 4095;
+
+/// Rows of a fumen field, above the garbage row rising from below.
+const FIELD_ROWS: usize = 23;
+const FIELD_COLS: usize = 10;
+
+/// Cells of a fumen field, the garbage row included.
+const FIELD_CELLS: usize = (FIELD_ROWS + 1) * FIELD_COLS;
+
+/// Field value of a gray cell: 0 is empty and 1 to 7 the pieces of [`FUMEN_PIECES`].
+const GRAY: u8 = 8;
+
+/// The pieces in the order of their fumen values, from 1.
+const FUMEN_PIECES: [PieceType; 7] = [
+    PieceType::I,
+    PieceType::L,
+    PieceType::O,
+    PieceType::Z,
+    PieceType::T,
+    PieceType::J,
+    PieceType::S,
+];
+
+/// Fumen rotation values by number of clockwise turns from the spawn orientation,
+/// which is also how to turn them back into turns.
+const ROTATION_VALUES: [usize; 4] = [2, 1, 0, 3];
+
+/// Characters a fumen viewer breaks the data with: after the first ones, then every so many.
+const FIRST_LINE_LENGTH: usize = 42;
+const LINE_LENGTH: usize = 47;
+
+/// One page of a fumen: a board, the piece being placed on it and a comment.
+#[derive(Clone)]
+pub struct FumenPage {
+    pub board: Board,
+    pub piece: Option<Piece>,
+    pub comment: String,
+    /// Whether the piece locks once the page is left, so the next page starts from the board
+    /// with the piece placed and full lines cleared.
+    pub locks: bool,
+}
+
+impl FumenPage {
+    pub fn new(board: Board) -> Self {
+        Self {
+            board,
+            piece: None,
+            comment: String::new(),
+            locks: true,
+        }
+    }
+
+    /// The board of `game` with its current piece, as the game shows it right now.
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            piece: game.current_piece.clone(),
+            ..Self::new(game.board.clone())
+        }
+    }
+}
+
+/// Cells of a fumen field, row by row from the top one to the garbage row.
+type Field = Vec<u8>;
+
+/// A piece as fumen places it: its value, clockwise turns from its spawn orientation and
+/// the cell of its center, `y` counting rows up from the bottom one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FumenPiece {
+    value: u8,
+    turns: usize,
+    x: isize,
+    y: isize,
+}
+
+impl FumenPiece {
+    /// Block offsets from the center, `y` pointing up.
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let spawn: [(isize, isize); 4] = match self.value {
+            1 => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+            2 => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+            3 => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            4 => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+            5 => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+            6 => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+            _ => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        };
+        spawn
+            .iter()
+            .map(|&offset| (0..self.turns).fold(offset, |(x, y), _| (y, -x)))
+            .collect()
+    }
+
+    /// Cells as `(x, y)`.
+    fn cells(&self) -> Vec<(isize, isize)> {
+        self.offsets().iter().map(|(dx, dy)| (self.x + dx, self.y + dy)).collect()
+    }
+
+    /// Fumen places I, O, S and Z pieces from another cell than their center in some
+    /// orientations: the offset from the center to that cell.
+    fn anchor_offset(&self) -> (isize, isize) {
+        match (self.value, self.turns) {
+            (1, 2) => (-1, 0),
+            (1, 3) => (0, 1),
+            (3, 0) => (0, 1),
+            (3, 2) => (-1, 0),
+            (3, 3) => (-1, 1),
+            (4, 0) => (0, 1),
+            (4, 3) => (-1, 0),
+            (7, 0) => (0, 1),
+            (7, 1) => (1, 0),
+            _ => (0, 0),
+        }
+    }
+
+    /// Index of the field cell the piece is placed from.
+    fn location(&self) -> usize {
+        let (dx, dy) = self.anchor_offset();
+        let (x, y) = (self.x + dx, self.y + dy);
+        ((FIELD_ROWS as isize - y - 1) * FIELD_COLS as isize + x) as usize
+    }
+
+    fn at_location(value: u8, turns: usize, location: usize) -> Self {
+        let mut piece = Self {
+            value,
+            turns,
+            x: (location % FIELD_COLS) as isize,
+            y: FIELD_ROWS as isize - (location / FIELD_COLS) as isize - 1,
+        };
+        let (dx, dy) = piece.anchor_offset();
+        piece.x -= dx;
+        piece.y -= dy;
+        piece
+    }
+
+    /// Finds how fumen places the tetromino covering `cells` of a board of `rows` rows.
+    fn of(piece: &Piece, rows: usize) -> Result<Self, String> {
+        let value = piece_value(&piece.piece_type)
+            .ok_or_else(|| format!("{:?} pieces cannot be written in a fumen", piece.piece_type))?;
+        let mut cells: Vec<(isize, isize)> = piece
+            .get_blocks_position()
+            .iter()
+            .map(|cell| (cell.x, rows as isize - 1 - cell.y))
+            .collect();
+        cells.sort();
+
+        (0..4)
+            .flat_map(|turns| cells.iter().map(move |&(x, y)| FumenPiece { value, turns, x, y }))
+            .find(|candidate| {
+                let mut candidate_cells = candidate.cells();
+                candidate_cells.sort();
+                candidate_cells == cells
+            })
+            .ok_or_else(|| "the piece does not have the shape of a tetromino".to_string())
+    }
+
+    /// The piece of the game covering the same cells on a board of `rows` rows, turned with
+    /// the game's rotation so it can go on being played.
+    fn to_piece(self, rows: usize) -> Option<Piece> {
+        let piece_type = FUMEN_PIECES[self.value as usize - 1].clone();
+        let mut piece = Piece::new(&piece_type, Point2D::default())?;
+        for _ in 0..self.turns {
+            piece.rotate(RotationDirection::Clockwise);
+        }
+
+        let top_left = |cells: Vec<Point2D>| cells.into_iter().min_by_key(|cell| (cell.y, cell.x)).unwrap_or_default();
+        let target = top_left(
+            self.cells()
+                .iter()
+                .map(|&(x, y)| Point2D::new(rows as isize - 1 - y, x))
+                .collect(),
+        );
+        let current = top_left(piece.get_blocks_position());
+        piece.position = Point2D::new(target.y - current.y, target.x - current.x);
+        Some(piece)
+    }
+}
+
+/// What a page does besides showing its field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Action {
+    piece: Option<FumenPiece>,
+    rises: bool,
+    is_mirrored: bool,
+    uses_guideline_colors: bool,
+    has_comment: bool,
+    locks: bool,
+}
+
+impl Action {
+    fn to_value(self) -> usize {
+        let (value, rotation, location) = match self.piece {
+            Some(piece) => (piece.value as usize, ROTATION_VALUES[piece.turns], piece.location()),
+            None => (0, 0, 0),
+        };
+        let flags = [!self.locks, self.has_comment, self.uses_guideline_colors, self.is_mirrored, self.rises];
+        let flags = flags.iter().fold(0, |value, &flag| value * 2 + flag as usize);
+        ((flags * FIELD_CELLS + location) * 4 + rotation) * 8 + value
+    }
+
+    fn from_value(mut value: usize) -> Result<Self, String> {
+        let mut take = |count: usize| {
+            let part = value % count;
+            value /= count;
+            part
+        };
+        let piece_value = take(8) as u8;
+        let turns = ROTATION_VALUES[take(4)];
+        let location = take(FIELD_CELLS);
+        let rises = take(2) == 1;
+        let is_mirrored = take(2) == 1;
+        let uses_guideline_colors = take(2) == 1;
+        let has_comment = take(2) == 1;
+        let locks = take(2) == 0;
+        let piece = match piece_value {
+            0 => None,
+            GRAY => return Err("a gray piece cannot be placed".to_string()),
+            _ => Some(FumenPiece::at_location(piece_value, turns, location)),
+        };
+        Ok(Self {
+            piece,
+            rises,
+            is_mirrored,
+            uses_guideline_colors,
+            has_comment,
+            locks,
+        })
+    }
+}
+
+/// Encodes `pages` as fumen data, to be shared or opened in a fumen viewer. Boards must be
+/// 10 columns wide and at most 23 rows high, and pieces tetrominoes of the standard size.
+pub fn encode(pages: &[FumenPage]) -> Result<String, String> {
+    let mut digits: Vec<u8> = Vec::new();
+    let mut previous_field: Field = vec![0; FIELD_CELLS];
+    let mut previous_comment = "";
+    // Digit counting the following pages that keep the field unchanged
+    let mut repeat_digit: Option<usize> = None;
+    for (index, page) in pages.iter().enumerate() {
+        let field = field_of(&page.board).map_err(|error| format!("page {}: {}", index + 1, error))?;
+        let piece = page
+            .piece
+            .as_ref()
+            .map(|piece| FumenPiece::of(piece, page.board.rows))
+            .transpose()
+            .map_err(|error| format!("page {}: {}", index + 1, error))?;
+
+        let is_unchanged = field == previous_field;
+        match repeat_digit {
+            Some(digit) if is_unchanged && digits[digit] < 63 => digits[digit] += 1,
+            _ => {
+                write_field(&mut digits, &previous_field, &field);
+                if is_unchanged {
+                    repeat_digit = Some(digits.len());
+                    push(&mut digits, 0, 1);
+                } else {
+                    repeat_digit = None;
+                }
+            }
+        }
+
+        let action = Action {
+            piece,
+            rises: false,
+            is_mirrored: false,
+            uses_guideline_colors: index == 0,
+            has_comment: page.comment != previous_comment,
+            locks: page.locks,
+        };
+        push(&mut digits, action.to_value(), 3);
+        if action.has_comment {
+            write_comment(&mut digits, &page.comment);
+        }
+
+        previous_field = after_page(field, &action);
+        previous_comment = &page.comment;
+    }
+
+    let data: Vec<char> = digits.iter().map(|&digit| DIGITS[digit as usize] as char).collect();
+    let mut text = String::from(FUMEN_PREFIX);
+    for (index, chunk) in data[..FIRST_LINE_LENGTH.min(data.len())]
+        .chunks(FIRST_LINE_LENGTH)
+        .chain(data[FIRST_LINE_LENGTH.min(data.len())..].chunks(LINE_LENGTH))
+        .enumerate()
+    {
+        if index > 0 {
+            text.push('?');
+        }
+        text.extend(chunk);
+    }
+    Ok(text)
+}
+
+/// Decodes fumen data, on its own or in a fumen URL, into its pages. Boards have the standard
+/// 20 rows: fields filled above them are refused.
+pub fn decode(text: &str) -> Result<Vec<FumenPage>, String> {
+    let data = FUMEN_PREFIXES
+        .iter()
+        .find_map(|prefix| text.find(prefix).map(|start| &text[start + prefix.len()..]))
+        .ok_or_else(|| format!("fumen data starts with `{}`", FUMEN_PREFIX))?;
+    let digits = data
+        .chars()
+        .filter(|character| *character != '?' && !character.is_whitespace())
+        .map(|character| {
+            DIGITS
+                .iter()
+                .position(|&digit| digit as char == character)
+                .map(|digit| digit as u8)
+                .ok_or_else(|| format!("unexpected character `{}`", character))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    let mut reader = Reader { digits, index: 0 };
+    let mut pages = Vec::new();
+    let mut previous_field: Field = vec![0; FIELD_CELLS];
+    let mut previous_comment = String::new();
+    let mut unchanged_pages = 0;
+    while !reader.is_at_end() {
+        let page_error = |error: String| format!("page {}: {}", pages.len() + 1, error);
+        let field = if unchanged_pages > 0 {
+            unchanged_pages -= 1;
+            previous_field.clone()
+        } else {
+            let (field, is_changed) = read_field(&mut reader, &previous_field).map_err(page_error)?;
+            if !is_changed {
+                unchanged_pages = reader.take(1).map_err(page_error)?;
+            }
+            field
+        };
+
+        let action = Action::from_value(reader.take(3).map_err(page_error)?).map_err(page_error)?;
+        let comment = if action.has_comment {
+            read_comment(&mut reader).map_err(page_error)?
+        } else {
+            previous_comment.clone()
+        };
+
+        let board = board_of(&field).map_err(page_error)?;
+        let piece = action.piece.and_then(|piece| piece.to_piece(board.rows));
+        pages.push(FumenPage {
+            board,
+            piece,
+            comment: comment.clone(),
+            locks: action.locks,
+        });
+        previous_field = after_page(field, &action);
+        previous_comment = comment;
+    }
+    Ok(pages)
+}
+
+/// Reads the digits of decoded data in order.
+struct Reader {
+    digits: Vec<u8>,
+    index: usize,
+}
+
+impl Reader {
+    fn is_at_end(&self) -> bool {
+        self.index >= self.digits.len()
+    }
+
+    /// Reads a value written with `count` digits, the lowest first.
+    fn take(&mut self, count: usize) -> Result<usize, String> {
+        let digits = self
+            .digits
+            .get(self.index..self.index + count)
+            .ok_or_else(|| "the data ends early".to_string())?;
+        self.index += count;
+        Ok(digits.iter().rev().fold(0, |value, &digit| value * 64 + digit as usize))
+    }
+}
+
+/// Writes `value` with `count` digits, the lowest first.
+fn push(digits: &mut Vec<u8>, mut value: usize, count: usize) {
+    for _ in 0..count {
+        digits.push((value % 64) as u8);
+        value /= 64;
+    }
+}
+
+/// Writes `field` as runs of cells changed by the same amount from `previous`.
+fn write_field(digits: &mut Vec<u8>, previous: &Field, field: &Field) {
+    let changes: Vec<usize> = field
+        .iter()
+        .zip(previous)
+        .map(|(&cell, &previous)| cell as usize + GRAY as usize - previous as usize)
+        .collect();
+    let mut start = 0;
+    while start < changes.len() {
+        let length = changes[start..].iter().take_while(|&&change| change == changes[start]).count();
+        push(digits, changes[start] * FIELD_CELLS + length - 1, 2);
+        start += length;
+    }
+}
+
+/// Reads a field written by [`write_field`], and whether it differs from `previous`.
+fn read_field(reader: &mut Reader, previous: &Field) -> Result<(Field, bool), String> {
+    let mut field = Vec::with_capacity(FIELD_CELLS);
+    let mut is_changed = true;
+    while field.len() < FIELD_CELLS {
+        let value = reader.take(2)?;
+        let (change, length) = (value / FIELD_CELLS, value % FIELD_CELLS + 1);
+        if change == GRAY as usize && length == FIELD_CELLS {
+            is_changed = false;
+        }
+        if field.len() + length > FIELD_CELLS {
+            return Err("the field has too many cells".to_string());
+        }
+        for &block in previous[field.len()..field.len() + length].iter() {
+            let cell = (block as usize + change)
+                .checked_sub(GRAY as usize)
+                .filter(|&cell| cell <= GRAY as usize)
+                .ok_or_else(|| "the field has an unknown block".to_string())?;
+            field.push(cell as u8);
+        }
+    }
+    Ok((field, is_changed))
+}
+
+fn write_comment(digits: &mut Vec<u8>, comment: &str) {
+    let escaped: Vec<u8> = escape(comment).bytes().take(MAX_COMMENT_LENGTH).collect();
+    push(digits, escaped.len(), 2);
+    for chunk in escaped.chunks(4) {
+        let value = chunk.iter().rev().fold(0, |value, &character| {
+            let index = COMMENT_CHARACTERS.iter().position(|&known| known == character).unwrap_or(0);
+            value * (COMMENT_CHARACTERS.len() + 1) + index
+        });
+        push(digits, value, 5);
+    }
+}
+
+fn read_comment(reader: &mut Reader) -> Result<String, String> {
+    let length = reader.take(2)?;
+    let mut escaped = String::with_capacity(length);
+    while escaped.len() < length {
+        let mut value = reader.take(5)?;
+        for _ in 0..(length - escaped.len()).min(4) {
+            let index = value % (COMMENT_CHARACTERS.len() + 1);
+            value /= COMMENT_CHARACTERS.len() + 1;
+            let character = COMMENT_CHARACTERS
+                .get(index)
+                .ok_or_else(|| "the comment has an unknown character".to_string())?;
+            escaped.push(*character as char);
+        }
+    }
+    unescape(&escaped)
+}
+
+/// Escapes a comment as JavaScript's `escape` does, which fumen viewers undo: ASCII letters,
+/// digits and `@*_+-./` are kept, other characters become `%XX` or `%uXXXX`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for unit in text.encode_utf16() {
+        match char::from_u32(unit as u32) {
+            Some(character) if character.is_ascii_alphanumeric() || "@*_+-./".contains(character) => {
+                escaped.push(character)
+            }
+            _ if unit < 256 => escaped.push_str(&format!("%{:02X}", unit)),
+            _ => escaped.push_str(&format!("%u{:04X}", unit)),
+        }
+    }
+    escaped
+}
+
+fn unescape(escaped: &str) -> Result<String, String> {
+    let mut units: Vec<u16> = Vec::new();
+    let mut rest = escaped;
+    while let Some(character) = rest.chars().next() {
+        let (hex, length) = match rest.strip_prefix("%u") {
+            Some(after) => (after.get(..4), 6),
+            None if character == '%' => (rest.get(1..3), 3),
+            None => {
+                units.push(character as u16);
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let unit = hex
+            .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| format!("the comment has a broken escape at `{}`", rest))?;
+        units.push(unit);
+        rest = &rest[length..];
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// The field of a page after it is left: the piece placed if it locks, full rows cleared,
+/// then the garbage row risen and the field mirrored when asked.
+fn after_page(mut field: Field, action: &Action) -> Field {
+    if !action.locks {
+        return field;
+    }
+    if let Some(piece) = action.piece {
+        for (x, y) in piece.cells() {
+            if (0..FIELD_COLS as isize).contains(&x) && (0..FIELD_ROWS as isize).contains(&y) {
+                field[(FIELD_ROWS - 1 - y as usize) * FIELD_COLS + x as usize] = piece.value;
+            }
+        }
+    }
+
+    let garbage_row = field.split_off(FIELD_ROWS * FIELD_COLS);
+    let mut rows: Vec<Vec<u8>> = field
+        .chunks(FIELD_COLS)
+        .filter(|row| row.contains(&0))
+        .map(<[u8]>::to_vec)
+        .collect();
+    while rows.len() < FIELD_ROWS {
+        rows.insert(0, vec![0; FIELD_COLS]);
+    }
+    let mut garbage_row = garbage_row;
+    if action.rises {
+        rows.remove(0);
+        rows.push(garbage_row);
+        garbage_row = vec![0; FIELD_COLS];
+    }
+    if action.is_mirrored {
+        rows.iter_mut().for_each(|row| row.reverse());
+    }
+    rows.concat().into_iter().chain(garbage_row).collect()
+}
+
+fn piece_value(piece_type: &PieceType) -> Option<u8> {
+    FUMEN_PIECES.iter().position(|known| known == piece_type).map(|index| index as u8 + 1)
+}
+
+fn cell_value(cell: &CellType) -> u8 {
+    match cell {
+        CellType::Empty => 0,
+        CellType::Garbage => GRAY,
+        CellType::Filled(color) => FUMEN_PIECES
+            .iter()
+            .filter_map(PieceDefinition::from_type)
+            .position(|definition| definition.color == *color)
+            .map_or(GRAY, |index| index as u8 + 1),
+    }
+}
+
+fn cell_of(value: u8) -> CellType {
+    match value {
+        0 => CellType::Empty,
+        GRAY => CellType::Garbage,
+        _ => PieceDefinition::from_type(&FUMEN_PIECES[value as usize - 1])
+            .map_or(CellType::Garbage, |definition| CellType::Filled(definition.color)),
+    }
+}
+
+/// The field showing `board` at its bottom, the colors of the tetrominoes kept and other
+/// cells turned gray.
+fn field_of(board: &Board) -> Result<Field, String> {
+    if board.cols != FIELD_COLS || board.rows > FIELD_ROWS {
+        return Err(format!(
+            "a {}x{} board does not fit the {}x{} field",
+            board.cols, board.rows, FIELD_COLS, FIELD_ROWS
+        ));
+    }
+    let mut field = vec![0; FIELD_CELLS];
+    let top = FIELD_ROWS - board.rows;
+    for (row, cells) in board.cells.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            field[(top + row) * FIELD_COLS + col] = cell_value(cell);
+        }
+    }
+    Ok(field)
+}
+
+fn board_of(field: &Field) -> Result<Board, String> {
+    let mut board = Board::new();
+    let top = FIELD_ROWS - board.rows;
+    if field[..top * FIELD_COLS].iter().any(|&cell| cell != 0) {
+        return Err(format!("the field is filled above the {} rows of the board", board.rows));
+    }
+    for row in 0..board.rows {
+        for col in 0..board.cols {
+            board.set_cell(row, col, cell_of(field[(top + row) * FIELD_COLS + col]));
+        }
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::constants::{COLS, ROWS};
+
+    fn sorted_cells(piece: &Piece) -> Vec<Point2D> {
+        let mut cells = piece.get_blocks_position();
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells
+    }
+
+    #[test]
+    fn an_empty_page_matches_fumen() {
+        let text = encode(&[FumenPage::new(Board::new())]).unwrap();
+
+        assert_eq!(text, "v115@vhAAgH");
+        let pages = decode(&format!("https://fumen.zui.jp/?{}", text)).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].board.get_filled_cells().is_empty());
+        assert!(pages[0].piece.is_none() && pages[0].locks);
+    }
+
+    #[test]
+    fn pages_keep_their_board_colors_pieces_and_comments() {
+        let mut board = Board::new();
+        board.set_cell(ROWS - 1, 0, CellType::Garbage);
+        board.set_cell(ROWS - 1, 1, CellType::Filled(PieceDefinition::from_type(&PieceType::T).unwrap().color));
+        board.set_cell(ROWS - 2, 1, CellType::Filled(PieceDefinition::from_type(&PieceType::L).unwrap().color));
+        let mut pages = Vec::new();
+        // Every tetromino in every orientation, each on its own page
+        for piece_type in FUMEN_PIECES {
+            for turns in 0..4 {
+                let mut piece = Piece::new(&piece_type, Point2D::new(10, 4)).unwrap();
+                for _ in 0..turns {
+                    piece.rotate(RotationDirection::Clockwise);
+                }
+                pages.push(FumenPage {
+                    piece: Some(piece),
+                    comment: format!("{:?} after {} turns: 40% of T-spins → ok", piece_type, turns),
+                    locks: false,
+                    ..FumenPage::new(board.clone())
+                });
+            }
+        }
+
+        let text = encode(&pages).unwrap();
+        let decoded = decode(&text).unwrap();
+
+        assert!(text.contains('?'));
+        assert_eq!(decoded.len(), pages.len());
+        for (page, decoded) in pages.iter().zip(&decoded) {
+            assert_eq!(decoded.comment, page.comment);
+            assert_eq!(decoded.board.cells, board.cells);
+            let (piece, decoded_piece) = (page.piece.as_ref().unwrap(), decoded.piece.as_ref().unwrap());
+            assert_eq!(decoded_piece.piece_type, piece.piece_type);
+            assert_eq!(sorted_cells(decoded_piece), sorted_cells(piece));
+        }
+    }
+
+    #[test]
+    fn locked_pieces_carry_over_to_the_next_page() {
+        // An I in the last column of a nearly full row clears it
+        let mut board = Board::new();
+        for col in 0..COLS - 1 {
+            board.set_cell(ROWS - 1, col, CellType::Garbage);
+        }
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(ROWS as isize - 2, COLS as isize - 1)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        let mut after = Board::new();
+        for cell in piece.get_blocks_position().iter().filter(|cell| cell.y < ROWS as isize - 1) {
+            after.set_cell(cell.y as usize + 1, cell.x as usize, cell_of(1));
+        }
+        let pages = [
+            FumenPage {
+                piece: Some(piece),
+                ..FumenPage::new(board)
+            },
+            FumenPage::new(after.clone()),
+            FumenPage::new(after.clone()),
+        ];
+
+        let text = encode(&pages).unwrap();
+        let decoded = decode(&text).unwrap();
+
+        // The later pages only count as repeats of the field the lock left
+        assert!(text.len() < FUMEN_PREFIX.len() + 3 * 5 + 12);
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1].board.cells, after.cells);
+        assert_eq!(decoded[2].board.cells, after.cells);
+    }
+
+    #[test]
+    fn broken_data_is_refused() {
+        assert!(matches!(decode("vhAAgH"), Err(error) if error.contains("v115@")));
+        assert!(matches!(decode("v115@vhAA"), Err(error) if error.contains("ends early")));
+        assert!(matches!(decode("v115@vh!AgH"), Err(error) if error.contains('!')));
+
+        let mut piece = Piece::new(&PieceType::T, Point2D::new(5, 5)).unwrap().with_scale(2);
+        piece.position = Point2D::new(5, 5);
+        let page = FumenPage {
+            piece: Some(piece),
+            ..FumenPage::new(Board::new())
+        };
+        assert!(encode(&[page]).is_err());
+    }
+}
@@ -1,43 +1,365 @@
+use std::collections::{HashMap, VecDeque};
 
-use std::collections::HashSet;
+use rand::{SeedableRng, rngs::StdRng};
 
-use crate::{core::{board::Board, constants::{COLS, ROWS}, piece::Piece}, enums::{CellType, RotationDirection}};
+use crate::{core::{attack::{self, GarbageMeter, GarbageSettings, HolePattern}, board::Board, constants::{COLS, FRAMES_PER_SECOND}, piece::Piece, piece_definition::{PieceDefinition, PieceSet}, point_2d::Point2D, randomizer::{PieceGenerator, Randomizer}, rules::RuleSet, scoring::{self, ClearStats, LineClear}, timings::Timings}, enums::{CellType, ClearType, GameAction, PieceType, RotationDirection, TSpinType}};
 
+/// Most frames simulated by a single call to [`Game::update`], so a long stall
+/// (e.g. the window being dragged) does not fast-forward the game.
+pub(crate) const MAX_FRAMES_PER_UPDATE: u32 = 10;
+
+/// A copy of the full state of a [`Game`] (board, queue, hold, random generator, score
+/// and timers), taken with [`Game::snapshot`] and brought back with [`Game::restore`].
+#[derive(Clone)]
+pub struct GameSnapshot(Game);
+
+#[derive(Clone)]
 pub struct Game {
     pub board: Board,
     pub current_piece: Option<Piece>,
     pub next_piece: Option<Piece>,
-    pub fall_speed_seconds_per_line: f64,
-    default_fall_speed: f64,
-    fall_speed_soft_drop: f64, // Speed at which the piece falls
+    /// Piece put aside with [`Game::hold`], at its spawn position.
+    pub hold_piece: Option<Piece>,
+    /// Gravity, spawn delays, auto-shift and lock delay.
+    pub timings: Timings,
+    /// The pieces new pieces are drawn from.
+    pub piece_set: PieceSet,
+    pub score: u64,
+    pub level: usize,
+    pub lines_cleared: usize,
+    /// Number of pieces locked on the board so far.
+    pub pieces_placed: usize,
+    /// Set when a new piece cannot spawn because the stack reached the top of the board.
+    pub is_game_over: bool,
+    /// When `false` the level (and gravity) stays at the start level whatever the lines cleared.
+    pub has_level_progression: bool,
+    /// Number of consecutive locks that cleared lines, `None` when the last lock cleared nothing.
+    pub combo: Option<usize>,
+    /// Set while the last line clear was a difficult one (tetris or T-spin).
+    pub is_back_to_back: bool,
+    /// The most recent line clear or T-spin, if any.
+    pub last_clear: Option<LineClear>,
+    /// Count and points of every kind of clear made so far.
+    pub clear_stats: HashMap<ClearType, ClearStats>,
+    pub perfect_clears: usize,
+    /// Number of cleared lines that were garbage rows.
+    pub garbage_lines_cleared: usize,
+    /// Side of the square each block of a piece covers, 2 for Big mode (see [`Piece::scale`]).
+    /// Set before [`Game::start`].
+    pub piece_scale: isize,
+    /// Columns added to the spawn position of every piece, so a co-op player's pieces
+    /// appear over their own half of a wide board.
+    pub spawn_column_offset: isize,
+    /// Delay, cap and hole pattern of received garbage.
+    pub garbage_settings: GarbageSettings,
+    /// Garbage sent by opponents that has not entered the board yet.
+    pub incoming_garbage: GarbageMeter,
+    /// Garbage lines sent to opponents after cancelling incoming garbage.
+    pub lines_sent: usize,
+    /// Rotation system, randomizer, previews, hold, gravity curve and scoring in use,
+    /// set with [`Game::apply_rules`].
+    pub rules: RuleSet,
+    /// When set, every lock checks the key presses of the piece against its finesse path
+    /// (see [`Game::finesse_path`]). Off by default, the search is wasted on simulated games.
+    pub tracks_finesse: bool,
+    /// Pieces locked with more key presses than the fewest that reach their placement.
+    pub finesse_faults: usize,
+    start_level: usize,
+    /// State of the randomizer of the rules, e.g. the bag being dealt.
+    generator: PieceGenerator,
+    /// Pieces after the next one, enough to fill the previews of the rules.
+    upcoming_pieces: VecDeque<Piece>,
+    /// Generates the piece sequence, seeded so a game can be replayed or restored.
+    rng: StdRng,
+    /// Generates the holes of received garbage, apart from `rng` so garbage does not change the pieces.
+    garbage_rng: StdRng,
+    /// Hole column of the last garbage row received.
+    last_garbage_hole: Option<usize>,
+    /// Attack lines not collected with [`Game::take_outgoing_attack`] yet.
+    outgoing_attack: usize,
+    /// Pieces still to come when the sequence is fixed instead of random.
+    fixed_queue: Option<VecDeque<PieceType>>,
+    /// Set once the current piece has been swapped with the hold, until it locks.
+    has_held: bool,
+    /// Move, rotation and soft drop presses since the current piece spawned.
+    piece_presses: usize,
+    is_soft_dropping: bool,
+    last_move_was_rotation: bool,
+    /// Seconds not yet simulated because they do not add up to a whole frame.
+    unsimulated_seconds: f64,
+    /// Fraction of a row the piece has fallen but not moved yet.
+    gravity_progress: f64,
+    /// Frames the current piece has been resting on the stack.
+    lock_frames: u32,
+    /// Frames left before the next piece appears.
+    spawn_delay_frames: u32,
+    /// Direction of the held move key (-1 left, 1 right, 0 none).
+    shift_direction: isize,
+    /// Frames the move key has been held, used for DAS.
+    shift_held_frames: u32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
+        Self::with_piece_set(PieceSet::tetrominoes())
+    }
+
+    /// Creates a new [`Game`] that draws its pieces from the given `piece_set`.
+    pub fn with_piece_set(piece_set: PieceSet) -> Self {
         Game {
             board: Board::new(),
             current_piece: None,
             next_piece: None,
-            fall_speed_seconds_per_line: 1.0, // 1 Second per line
-            default_fall_speed: 1.0,
-            fall_speed_soft_drop: 1.0 / 20.0
+            hold_piece: None,
+            timings: Timings::default(),
+            piece_set,
+            score: 0,
+            level: 1,
+            lines_cleared: 0,
+            pieces_placed: 0,
+            is_game_over: false,
+            has_level_progression: true,
+            combo: None,
+            is_back_to_back: false,
+            last_clear: None,
+            clear_stats: HashMap::new(),
+            perfect_clears: 0,
+            garbage_lines_cleared: 0,
+            piece_scale: 1,
+            spawn_column_offset: 0,
+            garbage_settings: GarbageSettings::default(),
+            incoming_garbage: GarbageMeter::default(),
+            lines_sent: 0,
+            rules: RuleSet::guideline(),
+            tracks_finesse: false,
+            finesse_faults: 0,
+            start_level: 1,
+            generator: PieceGenerator::new(RuleSet::guideline().randomizer),
+            upcoming_pieces: VecDeque::new(),
+            rng: StdRng::from_os_rng(),
+            garbage_rng: StdRng::from_os_rng(),
+            last_garbage_hole: None,
+            outgoing_attack: 0,
+            fixed_queue: None,
+            has_held: false,
+            piece_presses: 0,
+            is_soft_dropping: false,
+            last_move_was_rotation: false,
+            unsimulated_seconds: 0.0,
+            gravity_progress: 0.0,
+            lock_frames: 0,
+            spawn_delay_frames: 0,
+            shift_direction: 0,
+            shift_held_frames: 0,
         }
     }
+
+    /// Sets the level the game starts at and the gravity that goes with it, no lower than
+    /// the first level of the rules.
+    pub fn set_start_level(&mut self, level: usize) {
+        self.start_level = level.max(self.rules.first_level);
+        self.set_level(self.start_level);
+    }
+
+    fn set_level(&mut self, level: usize) {
+        self.level = level;
+        self.timings.gravity = self.rules.gravity_curve.gravity(level);
+    }
+
+    /// Switches to the given rules: board size, timings, garbage, randomizer and gravity.
+    /// The game starts at the first level of the rules. Call before [`Game::start`].
+    pub fn apply_rules(&mut self, rules: RuleSet) {
+        let clock_seconds = self.board.clock_seconds;
+        self.board = Board::with_size(rules.rows, rules.cols);
+        self.board.clock_seconds = clock_seconds;
+        self.timings = rules.timings;
+        self.garbage_settings = rules.garbage;
+        // Pieces spawn over the middle of boards wider or narrower than the standard one
+        self.spawn_column_offset = (rules.cols as isize - COLS as isize) / 2;
+        self.generator = PieceGenerator::new(rules.randomizer);
+        self.start_level = rules.first_level;
+        self.rules = rules;
+        self.set_level(self.start_level);
+    }
     
+    /// Seeds the piece and garbage hole generators, so games with the same seed get the same
+    /// pieces and garbage. Call before [`Game::start`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.garbage_rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+    }
+
+    /// Replaces the random piece sequence with the given pieces, in order. Once they have all
+    /// been dealt no new piece appears. Call before [`Game::start`].
+    pub fn set_fixed_queue(&mut self, pieces: &[PieceType]) {
+        self.fixed_queue = Some(pieces.iter().cloned().collect());
+    }
+
+    /// Pieces of a fixed queue that have not been dealt yet, after the next piece.
+    pub fn remaining_queue(&self) -> Vec<PieceType> {
+        self.upcoming_pieces
+            .iter()
+            .map(|piece| piece.piece_type.clone())
+            .chain(self.fixed_queue.iter().flatten().cloned())
+            .collect()
+    }
+
+    /// Whether the piece sequence was replaced with [`Game::set_fixed_queue`].
+    pub fn has_fixed_queue(&self) -> bool {
+        self.fixed_queue.is_some()
+    }
+
+    /// Pieces of the bag being dealt that the player has not seen yet: drawn into the queue
+    /// past the previews the rules show, or still in the bag. The player can tell which they
+    /// are from the pieces seen so far. `None` when pieces are not dealt from bags.
+    pub fn unseen_bag_pieces(&self) -> Option<Vec<PieceType>> {
+        if self.fixed_queue.is_some() || self.generator.randomizer != Randomizer::Bag {
+            return None;
+        }
+        let definitions = &self.piece_set.definitions;
+        let hidden = self.next_piece.iter().chain(&self.upcoming_pieces).skip(self.rules.previews);
+        let in_bag = self.generator.bag().iter().map(|&index| &definitions[index]);
+        Some(
+            hidden
+                .map(|piece| piece.piece_type.clone())
+                .chain(in_bag.map(|definition| definition.piece_type.clone()))
+                .collect(),
+        )
+    }
+
+    /// The pieces shown in the next queue, the next piece first, as many as the rules allow.
+    pub fn previews(&self) -> Vec<&Piece> {
+        self.next_piece
+            .iter()
+            .chain(&self.upcoming_pieces)
+            .take(self.rules.previews)
+            .collect()
+    }
+
+    /// Whether the current piece was last moved by a rotation, which makes a lock a T-spin candidate.
+    pub fn last_move_was_rotation(&self) -> bool {
+        self.last_move_was_rotation
+    }
+
+    /// Whether the current piece came out of the hold, or was put there, so it cannot be held again.
+    pub fn has_held(&self) -> bool {
+        self.has_held
+    }
+
+    /// Returns a copy of the whole game state.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot(self.clone())
+    }
+
+    /// Puts the game back in the state it was in when `snapshot` was taken.
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        *self = snapshot.0.clone();
+    }
+
     pub fn start(&mut self) {
-        self.current_piece = Some(Piece::generate_random_piece());
-        self.next_piece = Some(Piece::generate_random_piece());
+        self.current_piece = self.generate_piece();
+        self.next_piece = self.generate_piece();
+        self.fill_upcoming_pieces();
     }
 
-    pub fn move_piece_right(&mut self) {
-        if let Some(piece) = &mut self.current_piece {
-            piece.move_right();
+    /// Draws pieces until the queue after the next piece fills the previews of the rules.
+    fn fill_upcoming_pieces(&mut self) {
+        while self.upcoming_pieces.len() + 1 < self.rules.previews {
+            let Some(piece) = self.generate_piece() else {
+                break;
+            };
+            self.upcoming_pieces.push_back(piece);
+        }
+    }
+
+    /// Draws the next piece of the sequence, scaled for Big mode if needed.
+    /// Returns `None` once a fixed queue is exhausted, or at a queued piece nothing defines.
+    fn generate_piece(&mut self) -> Option<Piece> {
+        let piece = match &mut self.fixed_queue {
+            Some(queue) => {
+                let piece_type = queue.pop_front()?;
+                self.spawn_piece_of_type(&piece_type)?
+            }
+            None => self.piece_set.generate_random_piece(&mut self.generator, &mut self.rng),
+        };
+        Some(self.placed_for_spawn(piece))
+    }
+
+    /// Creates a piece of the given type at its spawn position, `None` when neither the
+    /// game's piece set nor the built-in ones define it.
+    pub fn spawn_piece_of_type(&self, piece_type: &PieceType) -> Option<Piece> {
+        let definition = self
+            .piece_set
+            .get(piece_type)
+            .cloned()
+            .or_else(|| PieceDefinition::from_type(piece_type))?;
+        Some(Piece::from_definition(&definition, definition.spawn_position))
+    }
+
+    /// Scales a freshly spawned piece for Big mode and moves it over the player's spawn columns.
+    fn placed_for_spawn(&self, piece: Piece) -> Piece {
+        let mut piece = if self.piece_scale > 1 { piece.with_scale(self.piece_scale) } else { piece };
+        piece.position.x += self.spawn_column_offset;
+        piece
+    }
+
+    /// Swaps the current piece with the held one, or with the next piece when nothing is held.
+    /// A piece can only be held once until it locks, and not at all when the rules have no hold.
+    pub fn hold(&mut self) {
+        // Without a next piece there would be nothing to play after holding
+        if !self.rules.has_hold || self.is_game_over || self.has_held || (self.hold_piece.is_none() && self.next_piece.is_none()) {
+            return;
         }
+        // The held piece goes back to its spawn orientation and position
+        let Some(held) = self
+            .current_piece
+            .as_ref()
+            .and_then(|piece| self.spawn_piece_of_type(&piece.piece_type))
+        else {
+            return;
+        };
+        let held = self.placed_for_spawn(held);
+        self.current_piece = None;
+
+        self.has_held = true;
+        self.piece_presses = 0;
+        self.lock_frames = 0;
+        self.gravity_progress = 0.0;
+        self.last_move_was_rotation = false;
+        match self.hold_piece.replace(held) {
+            Some(previous) => {
+                if !self.board.is_valid_position(&previous.get_blocks_position()) {
+                    self.is_game_over = true;
+                }
+                self.current_piece = Some(previous);
+            }
+            None => self.spawn_next_piece(),
+        }
+    }
+
+    pub fn move_piece_right(&mut self) {
+        self.try_shift_piece(1);
     }
 
     pub fn move_piece_left(&mut self) {
+        self.try_shift_piece(-1);
+    }
+
+    /// Moves the current piece horizontally by `dx` blocks if the new position is free.
+    fn try_shift_piece(&mut self, dx: isize) {
         if let Some(piece) = &mut self.current_piece {
-            piece.move_left();
+            let mut moved = piece.clone();
+            moved.position.x += dx * piece.scale;
+            if self.board.is_valid_position(&moved.get_blocks_position()) {
+                *piece = moved;
+                self.last_move_was_rotation = false;
+            }
         }
     }
     
@@ -47,21 +369,171 @@ impl Game {
         }
     }
     
+    /// Rotates the current piece, trying the kick offsets of the rotation system in order when
+    /// the rotated piece does not fit in place. The rotation is discarded if no offset fits.
     pub fn rotate_piece(&mut self, direction: RotationDirection) {
         if let Some(piece) = &mut self.current_piece {
-            piece.rotate(direction);
+            let mut rotated = piece.clone();
+            rotated.rotate(direction);
+
+            let offsets = std::iter::once(Point2D::default()).chain(self.rules.rotation_system.kicks(&rotated.kicks));
+            for offset in offsets {
+                let mut kicked = rotated.clone();
+                kicked.position.y += offset.y * kicked.scale;
+                kicked.position.x += offset.x * kicked.scale;
+                if self.board.is_valid_position(&kicked.get_blocks_position()) {
+                    *piece = kicked;
+                    self.last_move_was_rotation = true;
+                    return;
+                }
+            }
         }
     }
     
     pub fn start_soft_drop(&mut self) {
-        self.fall_speed_seconds_per_line = self.fall_speed_soft_drop;
+        self.is_soft_dropping = true;
     }
     
     pub fn stop_soft_drop(&mut self) {
-        self.fall_speed_seconds_per_line = self.default_fall_speed;
+        self.is_soft_dropping = false;
+    }
+
+    /// Handles an input being pressed. Moves act once immediately and then auto-shift
+    /// while held (see [`Timings::das`]), soft drop lasts until the input is released.
+    pub fn press(&mut self, action: GameAction) {
+        if self.is_game_over {
+            return;
+        }
+
+        let is_finesse_press = !matches!(action, GameAction::HardDrop | GameAction::Hold);
+        if is_finesse_press && self.current_piece.is_some() {
+            self.piece_presses += 1;
+        }
+
+        match action {
+            GameAction::MoveLeft | GameAction::MoveRight => {
+                self.shift_direction = if action == GameAction::MoveLeft { -1 } else { 1 };
+                self.shift_held_frames = 0;
+                self.try_shift_piece(self.shift_direction);
+            }
+            GameAction::RotateClockwise => self.rotate_piece(RotationDirection::Clockwise),
+            GameAction::RotateCounterClockwise => {
+                self.rotate_piece(RotationDirection::CounterClockwise)
+            }
+            GameAction::SoftDrop => self.start_soft_drop(),
+            GameAction::HardDrop if self.rules.has_hard_drop => self.hard_drop(),
+            GameAction::HardDrop => {}
+            GameAction::Hold => self.hold(),
+        }
+    }
+
+    /// Handles an input being released, ending auto-shift or soft drop.
+    pub fn release(&mut self, action: GameAction) {
+        match action {
+            GameAction::MoveLeft if self.shift_direction == -1 => self.shift_direction = 0,
+            GameAction::MoveRight if self.shift_direction == 1 => self.shift_direction = 0,
+            GameAction::SoftDrop => self.stop_soft_drop(),
+            _ => {}
+        }
+    }
+
+    /// Advances the game by `delta_seconds`, simulating whole frames at [`FRAMES_PER_SECOND`].
+    pub fn update(&mut self, delta_seconds: f64) {
+        self.unsimulated_seconds += delta_seconds;
+        let frame_seconds = 1.0 / FRAMES_PER_SECOND;
+        let mut frames = 0;
+        while self.unsimulated_seconds >= frame_seconds {
+            self.unsimulated_seconds -= frame_seconds;
+            frames += 1;
+            if frames > MAX_FRAMES_PER_UPDATE {
+                self.unsimulated_seconds = 0.0;
+                break;
+            }
+            self.step_frame();
+        }
+    }
+
+    /// Simulates one frame: spawn delay, auto-shift, gravity and lock delay.
+    pub fn step_frame(&mut self) {
+        if self.is_game_over {
+            return;
+        }
+        self.board.clock_seconds += 1.0 / FRAMES_PER_SECOND;
+        self.incoming_garbage.tick();
+
+        if self.current_piece.is_none() {
+            if self.spawn_delay_frames > 0 {
+                self.spawn_delay_frames -= 1;
+            }
+            if self.spawn_delay_frames > 0 || self.next_piece.is_none() {
+                return;
+            }
+            self.spawn_next_piece();
+            if self.is_game_over {
+                return;
+            }
+        }
+
+        self.apply_auto_shift();
+
+        let gravity = if self.is_soft_dropping {
+            self.timings.gravity.max(self.timings.soft_drop_gravity)
+        } else {
+            self.timings.gravity
+        };
+        self.gravity_progress += gravity;
+        while self.gravity_progress >= 1.0 {
+            if self.detect_collision() {
+                self.gravity_progress = 0.0;
+                if self.timings.locks_on_failed_drop {
+                    self.do_after_collision();
+                    return;
+                }
+                break;
+            }
+            self.gravity_progress -= 1.0;
+            self.move_piece_down();
+            self.last_move_was_rotation = false;
+            // Step reset: moving down a row gives the piece a fresh lock delay
+            self.lock_frames = 0;
+            if self.is_soft_dropping {
+                self.score += self.rules.scoring.soft_drop_points;
+            }
+        }
+
+        if self.detect_collision() && !self.timings.locks_on_failed_drop {
+            self.lock_frames += 1;
+            if self.lock_frames >= self.timings.lock_delay {
+                self.do_after_collision();
+            }
+        }
+    }
+
+    fn apply_auto_shift(&mut self) {
+        if self.shift_direction == 0 {
+            return;
+        }
+
+        self.shift_held_frames += 1;
+        if self.shift_held_frames < self.timings.das {
+            return;
+        }
+
+        let frames_since_charged = self.shift_held_frames - self.timings.das;
+        if self.timings.arr == 0 {
+            for _ in 0..self.board.cols {
+                self.try_shift_piece(self.shift_direction);
+            }
+        } else if frames_since_charged.is_multiple_of(self.timings.arr) {
+            self.try_shift_piece(self.shift_direction);
+        }
     }
     
     pub fn hard_drop(&mut self) {
+        if self.is_game_over || self.current_piece.is_none() {
+            return;
+        }
+
         loop {
             let collision = self.detect_collision();
             if collision {
@@ -71,83 +543,256 @@ impl Game {
 
             if let Some(piece) = &mut self.current_piece {
                 piece.move_down();
+                self.score += self.rules.scoring.hard_drop_points;
+                self.last_move_was_rotation = false;
             }
         }
     }
     
+    /// Checks whether the current piece would collide with the floor or a filled cell
+    /// if it moved one row down.
     pub fn detect_collision(&self) -> bool {
         if let Some(piece) = &self.current_piece {
-            for block in &piece.blocks {
-                let row = piece.position.y + block.y as isize;
-                if row == (ROWS - 1) as isize {
-                    return true; // Collision with bottom
-                }
-
-                let col = piece.position.x + block.x as isize;
-                // Check if cell bellow the current block is filled
-                if self.board.cells[(row + 1) as usize][col as usize] != CellType::Empty {
-                    return true; // Collision detected
-                }
-            }
+            let mut below = piece.clone();
+            below.move_down();
+            return !self.board.is_valid_position(&below.get_blocks_position());
         }
 
         false // No collision
     }
     
-    pub fn detect_filled_rows(&mut self) {
-        // let mut down_most_filled_row: Option<usize> = None;
-        let mut filled_rows: HashSet<usize> = HashSet::new();
-
-        self.board.cells = self.board.cells
-            .clone()
-            .into_iter()
-            .enumerate()
-            .rev()
-            .filter_map(|(row_index, row)| {
-                if row.iter().all(|cell| *cell != CellType::Empty) {
-                    // Row is filled, replace with empty row
-                    filled_rows.insert(row_index);
-                    // if down_most_filled_row.is_none() || row_index > down_most_filled_row.unwrap() {
-                    //     down_most_filled_row = Some(row_index);
-                    // }
-                    Some(vec![CellType::Empty; COLS])
-                } else {
-                    // Keep the row as is
-                    Some(row)
-                }
-            })
-            .rev()
-            .collect();
+    /// Removes the filled rows, shifting the rows above them down.
+    /// Returns the number of rows removed.
+    pub fn detect_filled_rows(&mut self) -> usize {
+        let removed = self.board.clear_full_rows();
+        self.garbage_lines_cleared += removed
+            .iter()
+            .filter(|row| row.contains(&CellType::Garbage))
+            .count();
+        removed.len()
+    }
+    
+    /// Pushes garbage rows in from the bottom of the board (see [`Board::add_garbage_rows`]).
+    /// The current piece is pushed up with the stack if it would overlap it, and the game is
+    /// over when filled cells are pushed off the top.
+    pub fn add_garbage_rows(&mut self, hole_columns: &[usize]) {
+        if self.board.add_garbage_rows(hole_columns) {
+            self.is_game_over = true;
+        }
 
-        let mut filled_rows_vec: Vec<_> = filled_rows.iter().cloned().collect();
-        filled_rows_vec.sort();
-        for row_index in filled_rows_vec {
-            // Shift all rows above the filled row down
-            for r in (1..=row_index).rev() {
-                if self.board.cells[r].iter().all(|cell| *cell != CellType::Empty) {
-                    break; //stop loop when finding an empty row
-                }
+        if let Some(piece) = &mut self.current_piece {
+            self.board.lift_out_of_stack(piece);
+        }
+    }
 
-                self.board.cells[r] = self.board.cells[r - 1].clone();
-            }
-            // Set the top row to empty
-            self.board.cells[0] = vec![CellType::Empty; COLS];
+    /// Queues `lines` garbage lines sent by an opponent. They wait in [`Game::incoming_garbage`]
+    /// for the garbage delay, and enter the board when a piece locks without clearing lines.
+    pub fn receive_garbage(&mut self, lines: usize) {
+        self.incoming_garbage.push(lines, self.garbage_settings.delay_frames);
+    }
+
+    /// Returns the attack lines sent since the last call, to be passed to opponents
+    /// with [`Game::receive_garbage`].
+    pub fn take_outgoing_attack(&mut self) -> usize {
+        std::mem::take(&mut self.outgoing_attack)
+    }
+
+    /// Cancels incoming garbage with the attack of the clear that just happened,
+    /// what remains is sent to opponents.
+    fn send_attack(&mut self) {
+        let Some(clear) = &self.last_clear else {
+            return;
+        };
+        let remainder = self.incoming_garbage.offset(attack::attack_lines(clear));
+        self.outgoing_attack += remainder;
+        self.lines_sent += remainder;
+    }
+
+    /// Pushes the incoming garbage past its delay into the board, up to the cap per lock.
+    fn apply_incoming_garbage(&mut self) {
+        for lines in self.incoming_garbage.take_ready(self.garbage_settings.cap_per_lock) {
+            let holes = match self.garbage_settings.hole_pattern {
+                HolePattern::SameColumn => {
+                    self.board.generate_garbage_holes(lines, 0.0, self.last_garbage_hole, &mut self.garbage_rng)
+                }
+                HolePattern::PerAttack => self.board.generate_garbage_holes(lines, 0.0, None, &mut self.garbage_rng),
+                HolePattern::PerRow => {
+                    self.board.generate_garbage_holes(lines, 1.0, self.last_garbage_hole, &mut self.garbage_rng)
+                }
+            };
+            self.last_garbage_hole = holes.last().copied();
+            self.add_garbage_rows(&holes);
         }
     }
-    
+
+    /// Applies one row of gravity, ignoring lock delay: a piece resting on the stack is locked,
+    /// otherwise it moves one row down. [`Game::update`] drives the game frame by frame instead.
     pub fn do_on_each_loop(&mut self) {
-        self.move_piece_down();
-        //TODO: implement game over conditons
+        if self.is_game_over || self.current_piece.is_none() {
+            return;
+        }
+
         if self.detect_collision() {
             self.do_after_collision();
+            return;
+        }
+
+        self.move_piece_down();
+        self.last_move_was_rotation = false;
+        if self.is_soft_dropping {
+            self.score += self.rules.scoring.soft_drop_points;
         }
     }
 
     fn do_after_collision(&mut self) {
-        self.board.place_piece(&self.current_piece.as_ref().unwrap().clone());
-        self.detect_filled_rows();
-        self.current_piece = self.next_piece.clone();
-        self.next_piece = Some(Piece::generate_random_piece());
+        let piece = self.current_piece.as_ref().unwrap().clone();
+        if self.tracks_finesse {
+            self.count_finesse_fault(&piece);
+        }
+        self.piece_presses = 0;
+        let t_spin = self.detect_t_spin(&piece);
+        self.board.place_piece(&piece);
+        self.pieces_placed += 1;
+        self.last_move_was_rotation = false;
+        self.has_held = false;
+
+        // Big pieces clear rows in pairs, each pair counts as one line
+        let lines = self.detect_filled_rows().div_ceil(piece.scale as usize);
+        self.register_clear(lines, t_spin);
+        self.lines_cleared += lines;
+        // Clearing lines holds incoming garbage back, otherwise it rises into the board
+        if lines > 0 {
+            self.send_attack();
+        } else {
+            self.apply_incoming_garbage();
+        }
+        let level = self.rules.level_progression.level(
+            self.rules.first_level,
+            self.start_level,
+            self.lines_cleared,
+            self.rules.lines_per_level,
+        );
+        if self.has_level_progression && level != self.level {
+            self.set_level(level);
+        }
+
+        self.current_piece = None;
+        self.lock_frames = 0;
+        self.gravity_progress = 0.0;
+        self.spawn_delay_frames = self.timings.are;
+        if lines > 0 {
+            self.spawn_delay_frames += self.timings.line_clear_delay;
+        }
+        if self.spawn_delay_frames == 0 {
+            self.spawn_next_piece();
+        }
+    }
+
+    /// Counts a finesse fault when `piece`, about to lock, took more key presses than
+    /// the fewest taking a piece of its type from its spawn position to the same cells.
+    fn count_finesse_fault(&mut self, piece: &Piece) {
+        let mut from_spawn = self.clone();
+        from_spawn.current_piece = self
+            .spawn_piece_of_type(&piece.piece_type)
+            .map(|spawned| self.placed_for_spawn(spawned));
+        if let Some(path) = from_spawn.finesse_path(&piece.get_blocks_position())
+            && self.piece_presses > path.len()
+        {
+            self.finesse_faults += 1;
+        }
+    }
+
+    /// Makes the next piece the current one and draws a new next piece.
+    fn spawn_next_piece(&mut self) {
+        self.current_piece = self.next_piece.take();
+        self.next_piece = self.upcoming_pieces.pop_front().or_else(|| self.generate_piece());
+        self.fill_upcoming_pieces();
+
+        // Block out: the new piece overlaps the stack as soon as it spawns
+        if let Some(piece) = &self.current_piece
+            && !self.board.is_valid_position(&piece.get_blocks_position())
+        {
+            self.is_game_over = true;
+        }
+    }
+
+    /// Scores the lock that just removed `lines` rows and updates combo, back-to-back
+    /// and clear statistics.
+    fn register_clear(&mut self, lines: usize, t_spin: TSpinType) {
+        self.combo = if lines > 0 { Some(self.combo.map_or(0, |combo| combo + 1)) } else { None };
+
+        let Some(clear_type) = scoring::classify_clear(lines, t_spin) else {
+            return;
+        };
+
+        // Only difficult clears that remove lines continue (or start) a back-to-back chain;
+        // T-spins without lines neither break nor extend it
+        let is_back_to_back = clear_type.is_difficult() && self.is_back_to_back;
+        if lines > 0 {
+            self.is_back_to_back = clear_type.is_difficult();
+        }
+
+        let combo = self.combo.unwrap_or(0);
+        let is_perfect_clear = lines > 0 && self.board.get_filled_cells().is_empty();
+        let mut points = self.rules.scoring.clear_points(clear_type, self.level, is_back_to_back)
+            + self.rules.scoring.combo_points(combo, self.level);
+        if is_perfect_clear {
+            if self.rules.scoring.has_perfect_clear_bonus {
+                points += scoring::perfect_clear_points(lines, self.level, is_back_to_back);
+            }
+            self.perfect_clears += 1;
+        }
+
+        self.score += points;
+        let stats = self.clear_stats.entry(clear_type).or_default();
+        stats.count += 1;
+        stats.points += points;
+        self.last_clear = Some(LineClear {
+            clear_type,
+            combo,
+            is_back_to_back,
+            is_perfect_clear,
+            points,
+        });
+    }
+
+    /// Detects a T-spin with the three-corner rule: the T piece was last moved by a rotation
+    /// and at least three of the four cells diagonal to its center are walls or filled.
+    /// It is a full T-spin when both corners on the side the T points to are filled,
+    /// otherwise a mini.
+    fn detect_t_spin(&self, piece: &Piece) -> TSpinType {
+        if piece.piece_type != PieceType::T || !self.last_move_was_rotation {
+            return TSpinType::None;
+        }
+
+        let center = piece.position;
+        let scale = piece.scale;
+        let is_occupied =
+            |dy: isize, dx: isize| self.board.is_occupied(center.y + dy * scale, center.x + dx * scale);
+        let corners = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+        let filled_corners = corners.iter().filter(|(dy, dx)| is_occupied(*dy, *dx)).count();
+        if filled_corners < 3 {
+            return TSpinType::None;
+        }
+
+        // The nub is the only block whose opposite block is missing
+        let Some(nub) = piece.blocks.iter().find(|block| {
+            (block.x != 0 || block.y != 0)
+                && !piece.blocks.contains(&Point2D::new(-block.y, -block.x))
+        }) else {
+            return TSpinType::None;
+        };
+        let front_corners = if nub.y != 0 {
+            [(nub.y, -1), (nub.y, 1)]
+        } else {
+            [(-1, nub.x), (1, nub.x)]
+        };
+
+        if front_corners.iter().all(|(dy, dx)| is_occupied(*dy, *dx)) {
+            TSpinType::Full
+        } else {
+            TSpinType::Mini
+        }
     }
     
     pub fn print_board_with_current_piece(&self) {
@@ -174,7 +819,7 @@ impl Game {
 mod tests {
     use macroquad::color::BLUE;
 
-    use crate::{core::{constants::COLS, point_2d::Point2D}, enums::PieceType};
+    use crate::core::{constants::{COLS, ROWS}, timings::{ONE_ROW_PER_SECOND, TWENTY_G}};
 
     use super::*;
 
@@ -190,36 +835,35 @@ mod tests {
     fn board_with_filled_cells() {
         let mut game = Game::new();
         initialize_test_board(&mut game);
-        let expected_board = get_expected_board_representation_on_initialization();
-        assert_eq!(game.board.get_board_representation(), expected_board);
+        assert_eq!(game.board.rows, ROWS);
+        assert_eq!(game.board.to_ascii(), "J.........\nJ...J.....\n");
     }
     
     #[test]
     fn detect_collision_with_bottom_row() {
         let mut game = Game::new();
         initialize_test_board(&mut game);
-        game.current_piece = Some(Piece::new(&PieceType::I, Point2D::new(18, 7)));
-        assert_eq!(game.detect_collision(), false);
-        // this Board will look like this:
-        // row 0 to 17: 0 0 0 0 0 0 0 0 0 0
-        // row 18:      1 0 0 0 0 0 1 1 1 1 // I piece is here at the right side
-        // row 19:      1 0 0 0 1 0 0 0 0 0
-        
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 7));
+        assert!(!game.detect_collision());
+        assert_eq!(
+            game.board.to_ascii_with_piece(game.current_piece.as_ref()),
+            "J.....@@@@\nJ...J.....\n"
+        );
+
         game.move_piece_down();
         assert!(game.detect_collision()); // Collision with bottom
-        // After moving down, the piece should collide with the bottom row.
-        // After moving down, the board will look like this:
-        // row 0 to 17: 0 0 0 0 0 0 0 0 0 0 
-        // row 18:      1 0 0 0 0 0 0 0 0 0 // I piece moved down
-        // row 19:      1 0 0 0 1 0 1 1 1 1 // I piece is here at the bottom
+        assert_eq!(
+            game.board.to_ascii_with_piece(game.current_piece.as_ref()),
+            "J.........\nJ...J.@@@@\n"
+        );
     }
     
     #[test]
     fn detect_collision_with_filled_cells() {
         let mut game = Game::new();
         initialize_test_board(&mut game);
-        game.current_piece = Some(Piece::new(&PieceType::I, Point2D::new(16, 1)));
-        assert_eq!(game.detect_collision(), false);
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(16, 1));
+        assert!(!game.detect_collision());
         // this Board will look like this:
         // row 0 to 15: 0 0 0 0 0 0 0 0 0 0
         // row 16:      1 1 1 1 0 0 0 0 0 0 // I piece is here
@@ -243,8 +887,8 @@ mod tests {
         let mut game = Game::new();
         initialize_test_board(&mut game);
         // Place Z piece just above the bottom row
-        game.current_piece = Some(Piece::new(&PieceType::Z, Point2D::new(18, 2)));
-        assert_eq!(game.detect_collision(), false);
+        game.current_piece = Piece::new(&PieceType::Z, Point2D::new(18, 2));
+        assert!(!game.detect_collision());
 
         // this Board will look like this before moving down:
         // row 0 to 17: 0 1 1 0 0 0 0 0 0 0 // Z piece is here 
@@ -267,8 +911,8 @@ mod tests {
         let mut game = Game::new();
         initialize_test_board(&mut game);
         // Place Z piece so that its lower block will collide after moving down
-        game.current_piece = Some(Piece::new(&PieceType::Z, Point2D::new(17, 1)));
-        assert_eq!(game.detect_collision(), false);
+        game.current_piece = Piece::new(&PieceType::Z, Point2D::new(17, 1));
+        assert!(!game.detect_collision());
         game.board.print_board();
 
         // this Board will look like this before moving down:
@@ -306,164 +950,647 @@ mod tests {
     #[test]
     fn detect_filled_row() {
         let mut game = Game::new();
-        // Fill the last row
+        // The last row is full, the cells above it are for control
+        game.board = Board::from_ascii(
+            "
+            GG........
+            GGGGGGGGGG
+            ",
+        )
+        .unwrap();
+
+        game.detect_filled_rows();
+
+        // The full row is removed and the cells above it move down
+        assert_eq!(game.board.to_ascii(), "GG........\n");
+    }
+
+    #[test]
+    fn detect_filled_row_when_there_are_multiple_filled_rows() {
+        let mut game = Game::new();
+        game.board = Board::from_ascii(
+            "
+            GG........
+            GGGGGGGGGG
+            GGGGGGGGGG
+            ",
+        )
+        .unwrap();
+
+        game.detect_filled_rows();
+
+        assert_eq!(game.board.to_ascii(), "GG........\n");
+    }
+
+    #[test]
+    fn detect_filled_row_when_there_are_multiple_filled_rows_and_some_empty_rows() {
+        let mut game = Game::new();
+        // The row in between the full rows is not full
+        game.board = Board::from_ascii(
+            "
+            GG.....G..
+            GGGGGGGGGG
+            GGGGGGGG..
+            GGGGGGGGGG
+            ",
+        )
+        .unwrap();
+
+        game.detect_filled_rows();
+
+        // Both full rows are removed, the rows above them move down past the gap
+        assert_eq!(game.board.to_ascii(), "GG.....G..\nGGGGGGGG..\n");
+    }
+
+    #[test]
+    fn rotate_piece_kicks_away_from_the_wall() {
+        let mut game = Game::new();
+        // Vertical I piece against the left wall
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 0)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
+
+        game.rotate_piece(RotationDirection::CounterClockwise);
+
+        let piece = game.current_piece.as_ref().unwrap();
+        assert!(game.board.is_valid_position(&piece.get_blocks_position()));
+        assert!(piece.get_blocks_position().iter().all(|block| block.y == 5));
+    }
+
+    #[test]
+    fn rotate_piece_is_discarded_when_no_kick_fits() {
+        let mut game = Game::new();
+        // Fill everything around a vertical I piece so it can never lie flat
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                if col != 4 {
+                    game.board.set_cell(row, col, CellType::Filled(BLUE));
+                }
+            }
+        }
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(10, 4)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        let blocks_before = piece.get_blocks_position();
+        game.current_piece = Some(piece);
+
+        game.rotate_piece(RotationDirection::Clockwise);
+
+        assert_eq!(game.current_piece.as_ref().unwrap().get_blocks_position(), blocks_before);
+    }
+
+    #[test]
+    fn big_pieces_move_in_two_cell_steps_and_clear_rows_in_pairs() {
+        let mut game = Game::new();
+        game.piece_scale = 2;
+        game.start();
+        for row in ROWS - 2..ROWS {
+            for col in 0..COLS - 4 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        let mut piece = Piece::new(&PieceType::O, Point2D::default()).unwrap().with_scale(2);
+        piece.position = Point2D::new(2, 4);
+        game.current_piece = Some(piece);
+
+        game.move_piece_right();
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 6);
+        game.hard_drop();
+
+        assert_eq!(game.lines_cleared, 1);
+        assert_eq!(game.last_clear.as_ref().unwrap().clear_type, ClearType::Single);
+        // The top half of the big O stays on the board
+        assert_eq!(game.board.get_filled_cells().len(), 8);
+        assert!(game.next_piece.as_ref().is_some_and(|piece| piece.scale == 2));
+    }
+
+    #[test]
+    fn move_piece_is_blocked_by_filled_cells() {
+        let mut game = Game::new();
+        game.board.set_cell(10, 2, CellType::Filled(BLUE));
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(10, 4));
+
+        game.move_piece_left();
+
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 4);
+    }
+
+    #[test]
+    fn pentomino_game_drops_and_locks_pieces() {
+        let mut game = Game::with_piece_set(PieceSet::pentominoes());
+        game.start();
+        game.hard_drop();
+
+        let filled = game.board.get_filled_cells().len();
+        assert_eq!(filled, 5);
+        assert_eq!(game.current_piece.as_ref().unwrap().blocks.len(), 5);
+    }
+
+    #[test]
+    fn locking_a_piece_that_clears_lines_updates_score_and_stats() {
+        let mut game = Game::new();
+        game.start();
+        // Leave a hole for a horizontal I piece in the bottom row
         for col in 0..COLS {
-            game.board.set_cell(19, col, CellType::Filled(BLUE));
+            if !(3..=6).contains(&col) {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
         }
-        // Fill some other cells for control
         game.board.set_cell(18, 0, CellType::Filled(BLUE));
-        game.board.set_cell(18, 1, CellType::Filled(BLUE));
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 4));
 
-        let before = game.board.get_board_representation();
-        game.print_board_with_current_piece();
-        game.detect_filled_rows();
-        println!("After detecting filled row:");
-        let after = game.board.get_board_representation();
-        game.print_board_with_current_piece();
+        // One step to land on the stack, one more to lock
+        game.do_on_each_loop();
+        game.do_on_each_loop();
 
-        // The row where before it had some filled cells should now be empty
-        assert_eq!(after[18], vec![0; COLS]);
+        assert_eq!(game.lines_cleared, 1);
+        assert_eq!(game.pieces_placed, 1);
+        assert_eq!(game.score, 100);
+        assert_eq!(game.board.get_filled_cells().len(), 1);
+        assert_eq!(game.last_clear.as_ref().unwrap().clear_type, ClearType::Single);
+        assert_eq!(game.clear_stats[&ClearType::Single], ClearStats { count: 1, points: 100 });
+    }
+
+    #[test]
+    fn emptying_the_board_scores_a_perfect_clear() {
+        let mut game = Game::new();
+        game.start();
+        for col in 0..COLS {
+            if !(3..=6).contains(&col) {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
+        }
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 4));
 
-        // The filled cell of the row above should be moved down
-        assert_eq!(after[19][0], 1);
-        assert_eq!(after[19][1], 1);
+        game.hard_drop();
 
-        // The rest of the board should be unchanged because they are all empty cells
-        for row in 0..18 { // remember the for is exclusive of the last row
-            assert_eq!(before[row], after[row]);
+        assert!(game.last_clear.as_ref().unwrap().is_perfect_clear);
+        assert_eq!(game.perfect_clears, 1);
+        // Single + perfect clear single + one cell of hard drop
+        assert_eq!(game.score, 100 + 800 + 2);
+    }
+
+    #[test]
+    fn rotating_a_t_piece_into_a_slot_scores_a_t_spin_double() {
+        let mut game = Game::new();
+        game.start();
+        for col in 0..COLS {
+            if col != 4 {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
+            if !(3..=5).contains(&col) {
+                game.board.set_cell(18, col, CellType::Filled(BLUE));
+            }
         }
+        // Overhang above the slot
+        game.board.set_cell(17, 3, CellType::Filled(BLUE));
+        game.current_piece = Piece::new(&PieceType::T, Point2D::new(18, 4));
+        game.rotate_piece(RotationDirection::Clockwise);
+        game.rotate_piece(RotationDirection::Clockwise);
+
+        game.do_on_each_loop();
+
+        let clear = game.last_clear.as_ref().unwrap();
+        assert_eq!(clear.clear_type, ClearType::TSpinDouble);
+        assert_eq!(game.lines_cleared, 2);
+        assert_eq!(game.score, 1200);
+        assert!(game.is_back_to_back);
     }
-    
+
     #[test]
-    fn detect_filled_row_when_there_are_multiple_filled_rows() {
+    fn t_piece_that_was_not_rotated_into_place_is_not_a_t_spin() {
         let mut game = Game::new();
-        // Fill the last two rows
+        game.start();
         for col in 0..COLS {
-            game.board.set_cell(19, col, CellType::Filled(BLUE));
-            game.board.set_cell(18, col, CellType::Filled(BLUE));
+            if col != 4 {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
+            if !(3..=5).contains(&col) {
+                game.board.set_cell(18, col, CellType::Filled(BLUE));
+            }
         }
-        
-        // Fill some other cells for control
-        game.board.set_cell(17, 0, CellType::Filled(BLUE));
-        game.board.set_cell(17, 1, CellType::Filled(BLUE));
+        game.board.set_cell(17, 3, CellType::Filled(BLUE));
+        let mut piece = Piece::new(&PieceType::T, Point2D::new(18, 4)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
 
-        let before = game.board.get_board_representation();
-        game.print_board_with_current_piece();
-        game.detect_filled_rows();
-        println!("After detecting filled row:");
-        let after = game.board.get_board_representation();
-        game.print_board_with_current_piece();
+        game.do_on_each_loop();
 
-        // The last two rows should now be empty
-        assert_eq!(after[17], vec![0; COLS]);
-        assert_eq!(after[18], vec![0; COLS]);
+        assert_eq!(game.last_clear.as_ref().unwrap().clear_type, ClearType::Double);
+        assert!(!game.is_back_to_back);
+    }
 
-        // Filled cells from rrow 17 should be moved down
-        assert_eq!(after[19][0], 1);
-        assert_eq!(after[19][1], 1);
+    #[test]
+    fn consecutive_tetrises_are_back_to_back_and_combo() {
+        let mut game = Game::new();
+        game.start();
+        for row in 12..ROWS {
+            for col in 0..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        // Keep one block so the second tetris is not a perfect clear
+        game.board.set_cell(11, 0, CellType::Filled(BLUE));
+        for _ in 0..2 {
+            let mut piece = Piece::new(&PieceType::I, Point2D::new(3, COLS as isize - 1)).unwrap();
+            piece.rotate(RotationDirection::Clockwise);
+            game.current_piece = Some(piece);
+            game.hard_drop();
+        }
+
+        let clear = game.last_clear.as_ref().unwrap();
+        assert_eq!(clear.clear_type, ClearType::Tetris);
+        assert!(clear.is_back_to_back);
+        assert_eq!(clear.combo, 1);
+        // 800 for the second tetris, 1.5 times for back-to-back, plus a 50 point combo
+        assert_eq!(clear.points, 1200 + 50);
+        assert_eq!(game.clear_stats[&ClearType::Tetris].count, 2);
+    }
 
-        // The rest of the board should be unchanged because they are all empty cells, touched rows were 17, 18 and 19
-        for row in 0..17 { // remember the for is exclusive of the last row
-            assert_eq!(before[row], after[row]);
+    #[test]
+    fn clearing_ten_lines_advances_the_level_and_gravity() {
+        let mut game = Game::new();
+        game.start();
+        game.lines_cleared = 9;
+        for col in 0..COLS {
+            if !(3..=6).contains(&col) {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
         }
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 4));
+
+        game.hard_drop();
+
+        assert_eq!(game.level, 2);
+        assert!(game.timings.gravity > ONE_ROW_PER_SECOND);
     }
-    
+
     #[test]
-    fn detect_filled_row_when_there_are_multiple_filled_rows_and_some_empty_rows() {
+    fn level_stays_fixed_without_level_progression() {
         let mut game = Game::new();
-        // Fill two rows
+        game.start();
+        game.has_level_progression = false;
+        game.lines_cleared = 9;
         for col in 0..COLS {
-            game.board.set_cell(17, col, CellType::Filled(BLUE));
-            game.board.set_cell(18, col, CellType::Filled(BLUE));
-            game.board.set_cell(19, col, CellType::Filled(BLUE));
+            if !(3..=6).contains(&col) {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
         }
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 4));
 
-        // set some cells to empty in the row in between
-        game.board.set_cell(18, 9, CellType::Empty);
-        game.board.set_cell(18, 8, CellType::Empty);
-        
-        // Fill some cells in row 16 for control
-        game.board.set_cell(16, 0, CellType::Filled(BLUE));
-        game.board.set_cell(16, 1, CellType::Filled(BLUE));
-        game.board.set_cell(16, 7, CellType::Filled(BLUE));
-        
-        // Board representation before detecting filled rows
-        // row 0 to 15: 0 0 0 0 0 0 0 0 0 0
-        // row 16:      1 1 0 0 0 0 0 1 0 0
-        // row 17:      1 1 1 1 1 1 1 1 1 1
-        // row 18:      1 1 1 1 1 1 1 1 0 0
-        // row 19:      1 1 1 1 1 1 1 1 1 1
+        game.hard_drop();
 
-        let before = game.board.get_board_representation();
-        game.print_board_with_current_piece();
-        game.detect_filled_rows();
-        println!("After detecting filled row:");
-        let after = game.board.get_board_representation();
-        game.print_board_with_current_piece();
+        assert_eq!(game.level, 1);
+        assert_eq!(game.timings.gravity, ONE_ROW_PER_SECOND);
+    }
+
+    #[test]
+    fn game_is_over_when_the_next_piece_cannot_spawn() {
+        let mut game = Game::new();
+        game.start();
+        // Fill the spawn area, leaving a gap so no line is cleared
+        for row in 0..ROWS {
+            for col in 1..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(0, 0));
+        game.current_piece.as_mut().unwrap().rotate(RotationDirection::Clockwise);
+        game.current_piece.as_mut().unwrap().position = Point2D::new(1, 0);
+
+        game.hard_drop();
+
+        assert!(game.is_game_over);
+    }
+
+    #[test]
+    fn clearing_a_garbage_row_is_counted() {
+        let mut game = Game::new();
+        game.start();
+        game.add_garbage_rows(&[0]);
+        // Fill the hole of the garbage row with a vertical I piece
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 0)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
 
-        // The top two rows should now be empty
-        assert_eq!(after[16], vec![0; COLS]);
-        assert_eq!(after[17], vec![0; COLS]);
+        game.hard_drop();
+
+        assert_eq!(game.lines_cleared, 1);
+        assert_eq!(game.garbage_lines_cleared, 1);
+    }
+
+    #[test]
+    fn garbage_pushed_into_the_current_piece_moves_it_up() {
+        let mut game = Game::new();
+        game.start();
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(19, 4));
 
-        // Cells from row 18 should be moved down
-        assert_eq!(after[19][9], 0);
-        assert_eq!(after[19][8], 0);
-        for col in 0..8 {
-            assert_eq!(after[19][col], 1);
+        game.add_garbage_rows(&[0]);
+
+        assert_eq!(game.current_piece.as_ref().unwrap().position.y, 18);
+        assert!(!game.is_game_over);
+    }
+
+    #[test]
+    fn a_tetris_cancels_incoming_garbage_and_sends_the_rest() {
+        let mut game = Game::new();
+        game.start();
+        game.receive_garbage(3);
+        for row in 16..ROWS {
+            for col in 0..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
         }
-        
-        // Cells from row 16 should be moved down
-        assert_eq!(after[18][0], 1);
-        assert_eq!(after[18][1], 1);
-        assert_eq!(after[18][7], 1);
-        for col in 2..7 {
-            assert_eq!(after[18][col], 0);
+        game.board.set_cell(15, 0, CellType::Filled(BLUE));
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(3, COLS as isize - 1)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
+
+        game.hard_drop();
+
+        assert_eq!(game.incoming_garbage.total(), 0);
+        assert_eq!(game.take_outgoing_attack(), 1);
+        assert_eq!(game.take_outgoing_attack(), 0);
+        assert_eq!(game.lines_sent, 1);
+    }
+
+    #[test]
+    fn incoming_garbage_rises_after_its_delay_when_a_lock_clears_nothing() {
+        let mut game = Game::new();
+        game.set_seed(3);
+        game.garbage_settings.delay_frames = 2;
+        game.garbage_settings.hole_pattern = HolePattern::SameColumn;
+        game.start();
+        game.receive_garbage(2);
+
+        // Still delayed
+        game.hard_drop();
+        assert_eq!(game.incoming_garbage.total(), 2);
+
+        game.step_frame();
+        game.step_frame();
+        game.hard_drop();
+
+        assert_eq!(game.incoming_garbage.total(), 0);
+        let rows = game.board.get_board_representation();
+        let holes: Vec<usize> = rows[ROWS - 2..]
+            .iter()
+            .map(|row| row.iter().position(|cell| *cell == 0).unwrap())
+            .collect();
+        assert_eq!(rows[ROWS - 1].iter().sum::<usize>(), COLS - 1);
+        assert_eq!(holes[0], holes[1]);
+    }
+
+    #[test]
+    fn gravity_of_one_row_per_second_moves_the_piece_every_sixty_frames() {
+        let mut game = Game::new();
+        game.start();
+        let start_y = game.current_piece.as_ref().unwrap().position.y;
+
+        for _ in 0..59 {
+            game.step_frame();
+        }
+        assert_eq!(game.current_piece.as_ref().unwrap().position.y, start_y);
+
+        game.step_frame();
+        assert_eq!(game.current_piece.as_ref().unwrap().position.y, start_y + 1);
+    }
+
+    #[test]
+    fn twenty_g_drops_the_piece_to_the_floor_in_one_frame() {
+        let mut game = Game::new();
+        game.start();
+        game.timings.gravity = TWENTY_G;
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(0, 4));
+
+        game.step_frame();
+
+        assert_eq!(game.current_piece.as_ref().unwrap().position.y, ROWS as isize - 1);
+        assert_eq!(game.pieces_placed, 0);
+    }
+
+    #[test]
+    fn resting_piece_locks_after_the_lock_delay() {
+        let mut game = Game::new();
+        game.start();
+        game.timings.gravity = TWENTY_G;
+        game.timings.lock_delay = 5;
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(0, 4));
+
+        for _ in 0..4 {
+            game.step_frame();
         }
-        assert_eq!(after[18][8], 0);
-        assert_eq!(after[18][9], 0);
+        assert_eq!(game.pieces_placed, 0);
+
+        game.step_frame();
+        assert_eq!(game.pieces_placed, 1);
+    }
+
+    #[test]
+    fn next_piece_appears_after_are_and_line_clear_delay() {
+        let mut game = Game::new();
+        game.start();
+        game.timings.are = 3;
+        game.timings.line_clear_delay = 2;
+        for col in 0..COLS {
+            if !(3..=6).contains(&col) {
+                game.board.set_cell(19, col, CellType::Filled(BLUE));
+            }
+        }
+        game.current_piece = Piece::new(&PieceType::I, Point2D::new(18, 4));
+
+        game.hard_drop();
+        assert!(game.current_piece.is_none());
 
-        // The rest of the board should be unchanged except the filled rows that were removed
-        for row in 0..16 { // remember the for is exclusive of the last row
-            assert_eq!(before[row], after[row]);
+        for _ in 0..4 {
+            game.step_frame();
         }
+        assert!(game.current_piece.is_none());
+
+        game.step_frame();
+        assert!(game.current_piece.is_some());
     }
 
-    fn initialize_test_board(game: &mut Game) {
-        game.board.set_cell(19, 0, CellType::Filled(BLUE));
-        game.board.set_cell(19, 4, CellType::Filled(BLUE));
+    #[test]
+    fn holding_a_move_auto_shifts_after_das() {
+        let mut game = Game::new();
+        game.start();
+        game.timings.das = 3;
+        game.timings.arr = 1;
+        game.current_piece = Piece::new(&PieceType::O, Point2D::new(5, 4));
 
-        game.board.set_cell(18, 0, CellType::Filled(BLUE));
+        game.press(GameAction::MoveRight);
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 5);
 
-        // this Board will look like this:
-        // row 0 to 17: 0 0 0 0 0 0 0 0 0 0
-        // row 18:      1 0 0 0 0 0 0 0 0 0
-        // row 19:      1 0 0 0 1 0 0 0 0 0
+        game.step_frame();
+        game.step_frame();
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 5);
+
+        game.step_frame();
+        game.step_frame();
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 7);
+
+        game.release(GameAction::MoveRight);
+        game.step_frame();
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 7);
     }
-    
-    fn get_expected_board_representation_on_initialization() -> Vec<Vec<usize>> {
-        let expected_board: Vec<Vec<usize>> = vec![
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![0; COLS],
-            vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-            vec![1, 0, 0, 0, 1, 0, 0, 0, 0, 0],
-        ];
-
-        expected_board
+
+    #[test]
+    fn hold_swaps_the_current_piece_once_per_lock() {
+        let mut game = Game::new();
+        game.start();
+        let first = game.current_piece.as_ref().unwrap().piece_type.clone();
+        let next = game.next_piece.as_ref().unwrap().piece_type.clone();
+        game.move_piece_left();
+
+        game.hold();
+        assert_eq!(game.hold_piece.as_ref().unwrap().piece_type, first);
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, next);
+        // Back at its spawn position
+        let definition = game.piece_set.get(&first).unwrap();
+        assert_eq!(game.hold_piece.as_ref().unwrap().position, definition.spawn_position);
+
+        // A second hold before locking does nothing
+        game.hold();
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, next);
+
+        game.hard_drop();
+        game.hold();
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, first);
+    }
+
+    #[test]
+    fn nes_rules_have_one_preview_no_hold_and_no_kicks() {
+        let mut game = Game::new();
+        game.apply_rules(RuleSet::nes());
+        game.start();
+        assert_eq!(game.previews().len(), 1);
+        assert_eq!(game.timings.gravity, 1.0 / 48.0);
+
+        let current = game.current_piece.as_ref().unwrap().piece_type.clone();
+        game.hold();
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, current);
+        assert!(game.hold_piece.is_none());
+
+        // An I piece lying on the floor cannot kick up into a vertical rotation
+        let piece = Piece::new(&PieceType::I, Point2D::new(ROWS as isize - 1, 1)).unwrap();
+        let blocks_before = piece.get_blocks_position();
+        game.current_piece = Some(piece);
+        game.rotate_piece(RotationDirection::Clockwise);
+        assert_eq!(game.current_piece.as_ref().unwrap().get_blocks_position(), blocks_before);
+    }
+
+    #[test]
+    fn guideline_rules_show_five_previews_from_a_bag() {
+        let mut game = Game::new();
+        game.set_seed(9);
+        game.start();
+
+        let mut pieces: Vec<PieceType> = std::iter::once(game.current_piece.as_ref().unwrap())
+            .chain(game.previews())
+            .map(|piece| piece.piece_type.clone())
+            .collect();
+        assert_eq!(pieces.len(), 6);
+        pieces.sort_by_key(|piece_type| format!("{:?}", piece_type));
+        pieces.dedup();
+        assert_eq!(pieces.len(), 6);
+    }
+
+    #[test]
+    fn unseen_bag_pieces_are_the_rest_of_the_bag_and_the_hidden_queue() {
+        let mut game = Game::new();
+        game.set_seed(9);
+        game.start();
+
+        let unseen = game.unseen_bag_pieces().unwrap();
+        let mut bag: Vec<PieceType> = std::iter::once(game.current_piece.as_ref().unwrap())
+            .chain(game.previews())
+            .map(|piece| piece.piece_type.clone())
+            .chain(unseen)
+            .collect();
+        bag.sort_by_key(|piece_type| format!("{:?}", piece_type));
+        bag.dedup();
+        assert_eq!(bag.len(), 7);
+
+        game.rules.previews = 0;
+        assert_eq!(game.unseen_bag_pieces().unwrap().len(), 6);
+        game.set_fixed_queue(&[PieceType::I]);
+        assert_eq!(game.unseen_bag_pieces(), None);
+    }
+
+    #[test]
+    fn fixed_queue_deals_its_pieces_in_order_and_then_runs_out() {
+        let mut game = Game::new();
+        game.set_fixed_queue(&[PieceType::T, PieceType::I, PieceType::O]);
+        game.start();
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, PieceType::T);
+        assert_eq!(game.next_piece.as_ref().unwrap().piece_type, PieceType::I);
+        assert_eq!(game.remaining_queue(), vec![PieceType::O]);
+
+        game.hard_drop();
+        game.hard_drop();
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, PieceType::O);
+        assert!(game.next_piece.is_none());
+
+        // Nothing to swap in when the hold is empty and the queue is exhausted
+        game.hold();
+        assert!(game.hold_piece.is_none());
+
+        game.hard_drop();
+        assert!(game.current_piece.is_none());
+        assert!(!game.is_game_over);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_brings_back_the_board_score_and_piece_sequence() {
+        let mut game = Game::new();
+        game.set_seed(7);
+        game.start();
+        let snapshot = game.snapshot();
+
+        let play = |game: &mut Game| {
+            for _ in 0..5 {
+                game.move_piece_left();
+                game.hard_drop();
+            }
+            (
+                game.board.get_board_representation(),
+                game.score,
+                game.current_piece.as_ref().unwrap().piece_type.clone(),
+                game.next_piece.as_ref().unwrap().piece_type.clone(),
+            )
+        };
+        let first_run = play(&mut game);
+
+        game.restore(&snapshot);
+        assert_eq!(game.pieces_placed, 0);
+        assert_eq!(game.score, 0);
+        assert!(game.board.get_filled_cells().is_empty());
+
+        assert_eq!(play(&mut game), first_run);
+    }
+
+    #[test]
+    fn update_simulates_whole_frames() {
+        let mut game = Game::new();
+        game.start();
+        let start_y = game.current_piece.as_ref().unwrap().position.y;
+
+        // Ten updates of a tenth of a second each add up to 60 frames
+        for _ in 0..10 {
+            game.update(0.1);
+        }
+
+        assert_eq!(game.current_piece.as_ref().unwrap().position.y, start_y + 1);
+    }
+
+    fn initialize_test_board(game: &mut Game) {
+        game.board = Board::from_ascii(
+            "
+            J.........
+            J...J.....
+            ",
+        )
+        .unwrap();
     }
 }
\ No newline at end of file
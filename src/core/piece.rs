@@ -1,9 +1,9 @@
 use macroquad::color::Color;
-use rand::Rng;
 
 use crate::{
     core::{
         constants::{COLS, ROWS},
+        piece_definition::PieceDefinition,
         point_2d::Point2D,
     },
     enums::{direction::Direction, piece_type::PieceType, rotation_direction::RotationDirection},
@@ -26,58 +26,59 @@ pub struct Piece {
     /// The color of the piece.
     /// This color is used to render the piece on the board.
     pub color: Color,
+
+    /// Offsets tried in order when a rotation collides with the board or its walls.
+    pub kicks: Vec<Point2D>,
+
+    /// Whether the piece rotates at all.
+    pub can_rotate: bool,
+
+    /// Side length in cells of the square each block occupies on the board, 2 in Big mode.
+    /// `position` is then the top-left cell of the center block's square.
+    pub scale: isize,
 }
 
 impl Piece {
-    /// Creates a new [`Piece`] based on the given `piece_type` and `position`.
-    pub fn new(piece_type: &PieceType, position: Point2D) -> Self {
+    /// Creates a new [`Piece`] based on the given `piece_type` and `position`, `None` for a
+    /// custom piece that is not built in, see [`PieceDefinition::from_type`].
+    pub fn new(piece_type: &PieceType, position: Point2D) -> Option<Self> {
+        PieceDefinition::from_type(piece_type).map(|definition| Self::from_definition(&definition, position))
+    }
+
+    /// Creates a new [`Piece`] from a data-driven `definition` at the given `position`.
+    pub fn from_definition(definition: &PieceDefinition, position: Point2D) -> Self {
         Self {
-            piece_type: piece_type.clone(),
-            blocks: create_piece(piece_type),
+            piece_type: definition.piece_type.clone(),
+            blocks: definition.blocks.clone(),
             position,
-            color: Self::get_color_by_piece(&piece_type),
+            color: definition.color,
+            kicks: definition.kicks.clone(),
+            can_rotate: definition.can_rotate,
+            scale: 1,
         }
     }
 
-    pub fn generate_random_piece() -> Self {
-        let random_idx = rand::random_range(0..7);
-        let piece_type = match random_idx {
-            0 => PieceType::I,
-            1 => PieceType::J,
-            2 => PieceType::L,
-            3 => PieceType::O,
-            4 => PieceType::S,
-            5 => PieceType::T,
-            6 => PieceType::Z,
-            _ => PieceType::I,
-        };
-
-        let col: isize = 4; // Center the piece in the middle column
-        let mut row: isize = 1; // Start at the top row
-        if piece_type == PieceType::I {
-            row = 0; // I piece starts at row 0
-        }
-
-        Self::new(&piece_type, Point2D::new(row, col))
-    }
+    /// Makes every block occupy a `scale`×`scale` square and moves the piece to the top center
+    /// of the board so it spawns like an unscaled piece would.
+    pub fn with_scale(mut self, scale: isize) -> Self {
+        let min_x = self.blocks.iter().map(|block| block.x).min().unwrap_or(0);
+        let max_x = self.blocks.iter().map(|block| block.x).max().unwrap_or(0);
+        let min_y = self.blocks.iter().map(|block| block.y).min().unwrap_or(0);
+        self.scale = scale.max(1);
+        let width = (max_x - min_x + 1) * self.scale;
 
-    fn get_color_by_piece(piece_type: &PieceType) -> Color {
-        match piece_type {
-            PieceType::I => Color::new(0.0, 1.0, 1.0, 1.0), // Cyan
-            PieceType::J => Color::new(0.0, 0.0, 1.0, 1.0), // Blue
-            PieceType::L => Color::new(1.0, 0.5, 0.0, 1.0), // Orange
-            PieceType::O => Color::new(1.0, 1.0, 0.0, 1.0), // Yellow
-            PieceType::S => Color::new(0.0, 1.0, 0.5, 1.0), // Green
-            PieceType::T => Color::new(1.0, 0.0, 1.0, 1.0), // Purple
-            PieceType::Z => Color::new(1.0, 0.0, 0.5, 1.0), // Red
-        }
+        self.position = Point2D::new(
+            -min_y * self.scale,
+            (COLS as isize - width) / 2 - min_x * self.scale,
+        );
+        self
     }
 
     /// Rotates the piece in the specified direction.
     /// Rotation applies a formula to the piece's blocks based on the direction.
     pub fn rotate(&mut self, direction: RotationDirection) {
-        if self.piece_type == PieceType::O {
-            // O piece does not rotate
+        if !self.can_rotate {
+            // e.g. the O piece does not rotate
             return;
         }
 
@@ -110,101 +111,54 @@ impl Piece {
     }
     pub fn move_left(&mut self) {
         if self.can_move(&Direction::Left) {
-            self.position.x -= 1;
+            self.position.x -= self.scale;
         }
     }
 
     pub fn move_right(&mut self) {
         if self.can_move(&Direction::Right) {
-            self.position.x += 1;
+            self.position.x += self.scale;
         }
     }
 
     pub fn move_down(&mut self) {
-        self.position.y += 1;
+        self.position.y += self.scale;
     }
 
     pub fn can_move(&self, direction: &Direction) -> bool {
-        let new_col = match direction {
-            Direction::Left => self.position.x - 1,
-            Direction::Right => self.position.x + 1,
-            _ => self.position.x,
+        let offset = match direction {
+            Direction::Left => -self.scale,
+            Direction::Right => self.scale,
+            _ => 0,
         };
-        self.blocks.iter().all(|block| {
-            let block_col = new_col + block.x as isize;
-            let block_row = self.position.y + block.y as isize;
-            Piece::is_in_bounds(block_row as usize, block_col as usize)
+        self.get_blocks_position().iter().all(|block| {
+            Piece::is_in_bounds(block.y as usize, (block.x + offset) as usize)
         })
     }
 
     /// Checks if the given row and column are within the bounds of the game area.
     pub fn is_in_bounds(row: usize, col: usize) -> bool {
-        row >= 0 && row < ROWS && col >= 0 && col < COLS
+        row < ROWS && col < COLS
     }
 
-    /// Returns the positions of the blocks in the Board relative to the piece's position.
+    /// Returns the positions of the cells the piece covers in the Board,
+    /// `scale`×`scale` cells for each block.
     pub fn get_blocks_position(&self) -> Vec<Point2D> {
+        let scale = self.scale;
         self.blocks
             .iter()
-            .map(|block| Point2D {
-                x: self.position.x + block.x as isize,
-                y: self.position.y + block.y as isize,
+            .flat_map(|block| {
+                let top = self.position.y + block.y * scale;
+                let left = self.position.x + block.x * scale;
+                (0..scale).flat_map(move |dy| (0..scale).map(move |dx| Point2D::new(top + dy, left + dx)))
             })
             .collect()
     }
 }
 
-fn create_piece(piece_type: &PieceType) -> Vec<Point2D> {
-    let blocks = match piece_type {
-        PieceType::I => vec![
-            Point2D::new(0, -1),
-            Point2D::new(0, 0),
-            Point2D::new(0, 1),
-            Point2D::new(0, 2),
-        ],
-        PieceType::J => vec![
-            Point2D::new(0, -1),
-            Point2D::new(0, 0),
-            Point2D::new(0, 1),
-            Point2D::new(-1, -1),
-        ],
-        PieceType::L => vec![
-            Point2D::new(0, -1),
-            Point2D::new(0, 0),
-            Point2D::new(0, 1),
-            Point2D::new(-1, 1),
-        ],
-        PieceType::O => vec![
-            Point2D::new(0, 0),
-            Point2D::new(-1, 0),
-            Point2D::new(-1, 1),
-            Point2D::new(0, 1),
-        ],
-        PieceType::S => vec![
-            Point2D::new(0, -1),
-            Point2D::new(0, 0),
-            Point2D::new(-1, 0),
-            Point2D::new(-1, 1),
-        ],
-        PieceType::T => vec![
-            Point2D::new(-1, 0),
-            Point2D::new(0, -1),
-            Point2D::new(0, 0),
-            Point2D::new(0, 1),
-        ],
-        PieceType::Z => vec![
-            Point2D::new(-1, -1),
-            Point2D::new(-1, 0),
-            Point2D::new(0, 0),
-            Point2D::new(0, 1),
-        ],
-    };
-    blocks
-}
-
 #[test]
 fn test_move_left_success() {
-    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 5));
+    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 5)).unwrap();
     let original_x = piece.position.x;
     piece.move_left();
     assert_eq!(piece.position.x, original_x - 1);
@@ -212,16 +166,42 @@ fn test_move_left_success() {
 
 #[test]
 fn test_move_right_success() {
-    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 5));
+    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 5)).unwrap();
     let original_x = piece.position.x;
     piece.move_right();
     assert_eq!(piece.position.x, original_x + 1);
 }
 
+#[test]
+fn test_scaled_piece_covers_two_by_two_squares() {
+    let mut piece = Piece::new(&PieceType::O, Point2D::new(0, 0)).unwrap().with_scale(2);
+    let blocks = piece.get_blocks_position();
+    assert_eq!(blocks.len(), 16);
+    // Centered and at the top of the board
+    assert_eq!(blocks.iter().map(|block| block.x).min(), Some(3));
+    assert_eq!(blocks.iter().map(|block| block.x).max(), Some(6));
+    assert_eq!(blocks.iter().map(|block| block.y).min(), Some(0));
+
+    let original_x = piece.position.x;
+    piece.move_left();
+    assert_eq!(piece.position.x, original_x - 2);
+    piece.move_down();
+    assert_eq!(piece.get_blocks_position().iter().map(|block| block.y).max(), Some(5));
+}
+
+#[test]
+fn test_scale_below_one_spawns_like_an_unscaled_piece() {
+    let unscaled = Piece::new(&PieceType::T, Point2D::new(0, 0)).unwrap().with_scale(1);
+    let piece = Piece::new(&PieceType::T, Point2D::new(0, 0)).unwrap().with_scale(0);
+
+    assert_eq!(piece.scale, 1);
+    assert_eq!(piece.get_blocks_position(), unscaled.get_blocks_position());
+}
+
 #[test]
 fn test_move_left_blocked() {
     // Place piece at left edge (x = 0)
-    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 0));
+    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, 0)).unwrap();
     let original_x = piece.position.x;
     piece.move_left();
     // Should not move left
@@ -231,7 +211,7 @@ fn test_move_left_blocked() {
 #[test]
 fn test_move_right_blocked() {
     // Place piece at right edge (x = COLS - 1)
-    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, (COLS - 1) as isize));
+    let mut piece = Piece::new(&PieceType::I, Point2D::new(5, (COLS - 1) as isize)).unwrap();
     let original_x = piece.position.x;
     piece.move_right();
     // Should not move right
@@ -242,7 +222,7 @@ fn test_move_right_blocked() {
 fn test_z_piece_counter_clockwise_rotations() {
     // Initial State of Z Piece for reference: vec![Point2D::new(-1, -1), Point2D::new(-1, 0), Point2D::new(0, 0), Point2D::new(0, 1)]
     use crate::enums::rotation_direction::RotationDirection;
-    let mut piece = Piece::new(&PieceType::Z, Point2D::new(5, 5));
+    let mut piece = Piece::new(&PieceType::Z, Point2D::new(5, 5)).unwrap();
 
     // Initial positions (relative to origin)
     let expected0 = vec![
@@ -292,7 +272,7 @@ fn test_z_piece_counter_clockwise_rotations() {
 fn test_z_piece_clockwise_rotations() {
     // Initial State of Z Piece for reference: vec![Point2D::new(-1, -1), Point2D::new(-1, 0), Point2D::new(0, 0), Point2D::new(0, 1)]
     use crate::enums::rotation_direction::RotationDirection;
-    let mut piece = Piece::new(&PieceType::Z, Point2D::new(5, 5));
+    let mut piece = Piece::new(&PieceType::Z, Point2D::new(5, 5)).unwrap();
     
     // Initial positions (relative to origin)
     let expected0 = vec![
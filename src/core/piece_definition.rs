@@ -0,0 +1,292 @@
+use std::sync::LazyLock;
+
+use macroquad::color::Color;
+use rand::Rng;
+
+use crate::{
+    core::{constants::COLS, piece::Piece, point_2d::Point2D, randomizer::PieceGenerator},
+    enums::piece_type::PieceType,
+};
+
+/// Every built-in piece, built once for looking custom pieces up by name.
+static BUILT_IN_PIECES: LazyLock<PieceSet> = LazyLock::new(PieceSet::mixed);
+
+/// Data-driven description of a piece shape.
+/// A definition holds everything needed to spawn, rotate and draw a piece, so the game
+/// is not limited to the seven tetrominoes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceDefinition {
+    /// The type of the piece this definition describes.
+    pub piece_type: PieceType,
+
+    /// Block offsets relative to the rotation center, represented as (0, 0).
+    pub blocks: Vec<Point2D>,
+
+    /// The color used to render the piece and the cells it leaves on the board.
+    pub color: Color,
+
+    /// Where the piece appears on the board when it is spawned.
+    pub spawn_position: Point2D,
+
+    /// Offsets tried in order when a rotation collides, after the unmoved rotation fails.
+    pub kicks: Vec<Point2D>,
+
+    /// Whether the piece rotates at all (e.g. the O piece does not).
+    pub can_rotate: bool,
+}
+
+impl PieceDefinition {
+    /// Creates a new [`PieceDefinition`] with a spawn position and kick table derived from its blocks.
+    /// The piece spawns with its topmost block on the first row, centered horizontally.
+    pub fn new(piece_type: PieceType, blocks: Vec<Point2D>, color: Color) -> Self {
+        let min_y = blocks.iter().map(|block| block.y).min().unwrap_or(0);
+        let max_extent = blocks
+            .iter()
+            .map(|block| block.x.abs().max(block.y.abs()))
+            .max()
+            .unwrap_or(0);
+
+        let mut kicks = vec![Point2D::new(0, -1), Point2D::new(0, 1), Point2D::new(-1, 0)];
+        if max_extent > 1 {
+            // Long pieces need to be pushed further away from walls to rotate
+            kicks.push(Point2D::new(0, -2));
+            kicks.push(Point2D::new(0, 2));
+        }
+
+        Self {
+            piece_type,
+            blocks,
+            color,
+            spawn_position: Point2D::new(-min_y, (COLS / 2 - 1) as isize),
+            kicks,
+            can_rotate: true,
+        }
+    }
+
+    /// Returns the definition of one of the seven standard tetrominoes, or looks up a custom
+    /// piece among the built-in polyomino sets. `None` for a custom piece of none of them.
+    pub fn from_type(piece_type: &PieceType) -> Option<Self> {
+        if let PieceType::Custom(_) = piece_type {
+            return BUILT_IN_PIECES.get(piece_type).cloned();
+        }
+
+        let mut definition = Self::new(
+            piece_type.clone(),
+            create_tetromino(piece_type),
+            get_tetromino_color(piece_type),
+        );
+        // O piece does not rotate
+        definition.can_rotate = *piece_type != PieceType::O;
+        Some(definition)
+    }
+
+    /// Returns the number of blocks that make up the piece.
+    pub fn size(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+/// A collection of piece definitions the game draws new pieces from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceSet {
+    pub definitions: Vec<PieceDefinition>,
+}
+
+impl PieceSet {
+    /// Creates a new [`PieceSet`] from the given definitions.
+    pub fn new(definitions: Vec<PieceDefinition>) -> Self {
+        Self { definitions }
+    }
+
+    /// The seven standard tetrominoes.
+    pub fn tetrominoes() -> Self {
+        let definitions = [
+            PieceType::I,
+            PieceType::J,
+            PieceType::L,
+            PieceType::O,
+            PieceType::S,
+            PieceType::T,
+            PieceType::Z,
+        ]
+        .iter()
+        .filter_map(PieceDefinition::from_type)
+        .collect();
+        Self::new(definitions)
+    }
+
+    /// The two trominoes: straight and corner.
+    pub fn trominoes() -> Self {
+        Self::new(vec![
+            custom("I3", &[(0, -1), (0, 0), (0, 1)], Color::new(0.5, 1.0, 1.0, 1.0)),
+            custom("L3", &[(-1, 0), (0, 0), (0, 1)], Color::new(1.0, 0.7, 0.3, 1.0)),
+        ])
+    }
+
+    /// The eighteen one-sided pentominoes (the twelve free pentominoes plus the mirror images
+    /// of the chiral ones).
+    pub fn pentominoes() -> Self {
+        Self::new(vec![
+            custom("F", &[(-1, 0), (-1, 1), (0, -1), (0, 0), (1, 0)], Color::new(0.8, 0.2, 0.2, 1.0)),
+            custom("F'", &[(-1, 0), (-1, -1), (0, 1), (0, 0), (1, 0)], Color::new(0.6, 0.1, 0.1, 1.0)),
+            custom("I5", &[(0, -2), (0, -1), (0, 0), (0, 1), (0, 2)], Color::new(0.0, 0.8, 0.8, 1.0)),
+            custom("L5", &[(0, -2), (0, -1), (0, 0), (0, 1), (-1, 1)], Color::new(1.0, 0.6, 0.0, 1.0)),
+            custom("J5", &[(0, -2), (0, -1), (0, 0), (0, 1), (-1, -2)], Color::new(0.2, 0.2, 0.9, 1.0)),
+            custom("N", &[(0, -2), (0, -1), (0, 0), (-1, 0), (-1, 1)], Color::new(0.5, 0.3, 0.1, 1.0)),
+            custom("N'", &[(0, 2), (0, 1), (0, 0), (-1, 0), (-1, -1)], Color::new(0.7, 0.5, 0.3, 1.0)),
+            custom("P", &[(-1, 0), (-1, 1), (0, 0), (0, 1), (1, 0)], Color::new(1.0, 0.4, 0.7, 1.0)),
+            custom("P'", &[(-1, -1), (-1, 0), (0, -1), (0, 0), (1, 0)], Color::new(0.8, 0.3, 0.5, 1.0)),
+            custom("T5", &[(-1, -1), (-1, 0), (-1, 1), (0, 0), (1, 0)], Color::new(0.6, 0.0, 0.8, 1.0)),
+            custom("U", &[(-1, -1), (-1, 1), (0, -1), (0, 0), (0, 1)], Color::new(0.9, 0.9, 0.2, 1.0)),
+            custom("V", &[(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1)], Color::new(0.3, 0.6, 1.0, 1.0)),
+            custom("W", &[(-1, -1), (0, -1), (0, 0), (1, 0), (1, 1)], Color::new(0.4, 0.8, 0.4, 1.0)),
+            custom("X", &[(-1, 0), (0, -1), (0, 0), (0, 1), (1, 0)], Color::new(0.9, 0.1, 0.5, 1.0)),
+            custom("Y", &[(0, -1), (0, 0), (0, 1), (0, 2), (-1, 0)], Color::new(0.5, 0.5, 0.0, 1.0)),
+            custom("Y'", &[(0, -2), (0, -1), (0, 0), (0, 1), (-1, 0)], Color::new(0.7, 0.7, 0.2, 1.0)),
+            custom("Z5", &[(-1, -1), (-1, 0), (0, 0), (1, 0), (1, 1)], Color::new(1.0, 0.2, 0.3, 1.0)),
+            custom("S5", &[(-1, 1), (-1, 0), (0, 0), (1, 0), (1, -1)], Color::new(0.1, 0.7, 0.3, 1.0)),
+        ])
+    }
+
+    /// Trominoes, tetrominoes and pentominoes mixed together.
+    pub fn mixed() -> Self {
+        let mut definitions = Self::trominoes().definitions;
+        definitions.extend(Self::tetrominoes().definitions);
+        definitions.extend(Self::pentominoes().definitions);
+        Self::new(definitions)
+    }
+
+    /// Returns the built-in piece set with the given name, if any.
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tetromino" | "tetrominoes" => Some(Self::tetrominoes()),
+            "tromino" | "trominoes" => Some(Self::trominoes()),
+            "pentomino" | "pentominoes" => Some(Self::pentominoes()),
+            "mixed" | "polyomino" | "polyominoes" => Some(Self::mixed()),
+            _ => None,
+        }
+    }
+
+    /// Returns the definition of the given piece type, if it is part of the set.
+    pub fn get(&self, piece_type: &PieceType) -> Option<&PieceDefinition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.piece_type == *piece_type)
+    }
+
+    /// Picks the next definition from the set with `generator` and spawns a piece from it.
+    pub fn generate_random_piece<R: Rng + ?Sized>(&self, generator: &mut PieceGenerator, rng: &mut R) -> Piece {
+        let definition = &self.definitions[generator.next_index(self.definitions.len(), rng)];
+        Piece::from_definition(definition, definition.spawn_position)
+    }
+}
+
+/// Builds a custom piece definition from `(y, x)` block offsets.
+fn custom(name: &str, blocks: &[(isize, isize)], color: Color) -> PieceDefinition {
+    PieceDefinition::new(
+        PieceType::Custom(name.to_string()),
+        blocks.iter().map(|&(y, x)| Point2D::new(y, x)).collect(),
+        color,
+    )
+}
+
+fn get_tetromino_color(piece_type: &PieceType) -> Color {
+    match piece_type {
+        PieceType::I => Color::new(0.0, 1.0, 1.0, 1.0), // Cyan
+        PieceType::J => Color::new(0.0, 0.0, 1.0, 1.0), // Blue
+        PieceType::L => Color::new(1.0, 0.5, 0.0, 1.0), // Orange
+        PieceType::O => Color::new(1.0, 1.0, 0.0, 1.0), // Yellow
+        PieceType::S => Color::new(0.0, 1.0, 0.5, 1.0), // Green
+        PieceType::T => Color::new(1.0, 0.0, 1.0, 1.0), // Purple
+        PieceType::Z => Color::new(1.0, 0.0, 0.5, 1.0), // Red
+        PieceType::Custom(_) => Color::new(0.5, 0.5, 0.5, 1.0),
+    }
+}
+
+fn create_tetromino(piece_type: &PieceType) -> Vec<Point2D> {
+    match piece_type {
+        PieceType::I => vec![
+            Point2D::new(0, -1),
+            Point2D::new(0, 0),
+            Point2D::new(0, 1),
+            Point2D::new(0, 2),
+        ],
+        PieceType::J => vec![
+            Point2D::new(0, -1),
+            Point2D::new(0, 0),
+            Point2D::new(0, 1),
+            Point2D::new(-1, -1),
+        ],
+        PieceType::L => vec![
+            Point2D::new(0, -1),
+            Point2D::new(0, 0),
+            Point2D::new(0, 1),
+            Point2D::new(-1, 1),
+        ],
+        PieceType::O => vec![
+            Point2D::new(0, 0),
+            Point2D::new(-1, 0),
+            Point2D::new(-1, 1),
+            Point2D::new(0, 1),
+        ],
+        PieceType::S => vec![
+            Point2D::new(0, -1),
+            Point2D::new(0, 0),
+            Point2D::new(-1, 0),
+            Point2D::new(-1, 1),
+        ],
+        PieceType::T => vec![
+            Point2D::new(-1, 0),
+            Point2D::new(0, -1),
+            Point2D::new(0, 0),
+            Point2D::new(0, 1),
+        ],
+        PieceType::Z => vec![
+            Point2D::new(-1, -1),
+            Point2D::new(-1, 0),
+            Point2D::new(0, 0),
+            Point2D::new(0, 1),
+        ],
+        PieceType::Custom(_) => vec![Point2D::new(0, 0)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tetrominoes_keep_their_original_spawn_positions() {
+        let set = PieceSet::tetrominoes();
+        assert_eq!(set.definitions.len(), 7);
+        assert_eq!(set.get(&PieceType::I).unwrap().spawn_position, Point2D::new(0, 4));
+        assert_eq!(set.get(&PieceType::T).unwrap().spawn_position, Point2D::new(1, 4));
+        assert!(!set.get(&PieceType::O).unwrap().can_rotate);
+    }
+
+    #[test]
+    fn built_in_polyomino_sets_have_expected_block_counts() {
+        assert!(PieceSet::trominoes().definitions.iter().all(|d| d.size() == 3));
+        assert!(PieceSet::pentominoes().definitions.iter().all(|d| d.size() == 5));
+        assert_eq!(PieceSet::pentominoes().definitions.len(), 18);
+        assert_eq!(PieceSet::mixed().definitions.len(), 2 + 7 + 18);
+    }
+
+    #[test]
+    fn spawned_pieces_start_inside_the_board() {
+        for definition in PieceSet::mixed().definitions {
+            let piece = Piece::from_definition(&definition, definition.spawn_position);
+            for block in piece.get_blocks_position() {
+                assert!(block.y >= 0, "{:?} spawns above the board", definition.piece_type);
+                assert!(Piece::is_in_bounds(block.y as usize, block.x as usize));
+            }
+        }
+    }
+
+    #[test]
+    fn custom_piece_type_is_looked_up_in_built_in_sets() {
+        let definition = PieceDefinition::from_type(&PieceType::Custom("X".to_string())).unwrap();
+        assert_eq!(definition.size(), 5);
+        assert_eq!(PieceDefinition::from_type(&PieceType::Custom("Q9".to_string())), None);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+
+use crate::{
+    core::{game::Game, piece::Piece, point_2d::Point2D},
+    enums::{GameAction, PieceType, RotationDirection},
+};
+
+/// Steps explored to find where the current piece can go, [`GameAction::SoftDrop`]
+/// standing for a drop of one row.
+const SEARCH_STEPS: [GameAction; 5] = [
+    GameAction::MoveLeft,
+    GameAction::MoveRight,
+    GameAction::RotateClockwise,
+    GameAction::RotateCounterClockwise,
+    GameAction::SoftDrop,
+];
+
+/// A position of the piece found by the search, with the state and step it was reached from.
+struct SearchState {
+    piece: Piece,
+    was_rotation: bool,
+    parent: Option<(usize, GameAction)>,
+}
+
+/// A final position of the current piece and how to get there.
+#[derive(Clone)]
+pub struct Placement {
+    /// Cells the piece covers once locked, in reading order.
+    pub cells: Vec<Point2D>,
+    /// Steps from the current position, applied with [`Game::apply_placement_step`] and
+    /// ending with [`GameAction::HardDrop`].
+    pub inputs: Vec<GameAction>,
+    /// The game right after the piece locks.
+    pub result: Game,
+}
+
+impl Game {
+    /// Applies one step of a placement path: moves and rotations as if pressed and released,
+    /// [`GameAction::SoftDrop`] moving the piece one row down without locking it and
+    /// [`GameAction::HardDrop`] dropping and locking it whatever the rules.
+    pub fn apply_placement_step(&mut self, action: GameAction) {
+        match action {
+            GameAction::MoveLeft => self.move_piece_left(),
+            GameAction::MoveRight => self.move_piece_right(),
+            GameAction::RotateClockwise => self.rotate_piece(RotationDirection::Clockwise),
+            GameAction::RotateCounterClockwise => self.rotate_piece(RotationDirection::CounterClockwise),
+            GameAction::SoftDrop if !self.detect_collision() => self.do_on_each_loop(),
+            GameAction::SoftDrop | GameAction::Hold => {}
+            GameAction::HardDrop => self.hard_drop(),
+        }
+    }
+
+    /// Returns every final placement the current piece can reach under the rotation system in use,
+    /// tucks under overhangs and spins included, each with the shortest path found to it.
+    /// Placements are told apart by the cells they cover, and by whether they are spins: the last
+    /// step is a rotation of a T piece that cannot fall further, so the lock may be a T-spin.
+    pub fn placements(&self) -> Vec<Placement> {
+        let Some(start) = self.current_piece.clone() else {
+            return Vec::new();
+        };
+
+        // Breadth-first search over the positions of the piece. Each state records the state
+        // and step it was reached from, so paths are rebuilt and the last step replayed when locking
+        let mut states = vec![SearchState {
+            piece: start.clone(),
+            was_rotation: self.last_move_was_rotation(),
+            parent: None,
+        }];
+        let mut seen: HashSet<(Vec<Point2D>, bool)> =
+            HashSet::from([search_key(&start, self.last_move_was_rotation())]);
+        let mut scratch = self.clone();
+        let mut index = 0;
+        while index < states.len() {
+            let piece = states[index].piece.clone();
+            for action in SEARCH_STEPS {
+                scratch.current_piece = Some(piece.clone());
+                scratch.apply_placement_step(action);
+                let Some(moved) = scratch.current_piece.take() else {
+                    continue;
+                };
+                let has_moved = moved.position != piece.position || moved.blocks != piece.blocks;
+                let was_rotation = scratch.last_move_was_rotation();
+                if has_moved && seen.insert(search_key(&moved, was_rotation)) {
+                    states.push(SearchState {
+                        piece: moved,
+                        was_rotation,
+                        parent: Some((index, action)),
+                    });
+                }
+            }
+            index += 1;
+        }
+
+        // States are in order of path length, the first to land somewhere has the shortest path
+        let mut placements: Vec<Placement> = Vec::new();
+        let mut landings: HashSet<(Vec<Point2D>, bool)> = HashSet::new();
+        for (index, state) in states.iter().enumerate() {
+            let piece = &state.piece;
+            scratch.current_piece = Some(piece.clone());
+            let is_resting = scratch.detect_collision();
+            while !scratch.detect_collision() {
+                scratch.move_piece_down();
+            }
+            let Some(landed) = scratch.current_piece.take() else {
+                continue;
+            };
+            let is_spin = is_resting && state.was_rotation && piece.piece_type == PieceType::T;
+            let cells = sorted_blocks(&landed);
+            if !landings.insert((cells.clone(), is_spin)) {
+                continue;
+            }
+
+            let mut inputs = path_to(&states, index);
+            let mut result = self.clone();
+            // The search stands in for the player, there are no key presses to check
+            result.tracks_finesse = false;
+            // Replaying the last step sets whether the lock follows a rotation
+            match state.parent {
+                Some((parent, action)) => {
+                    result.current_piece = Some(states[parent].piece.clone());
+                    result.apply_placement_step(action);
+                }
+                None => result.current_piece = Some(piece.clone()),
+            }
+            result.apply_placement_step(GameAction::HardDrop);
+            inputs.push(GameAction::HardDrop);
+            placements.push(Placement { cells, inputs, result });
+        }
+        placements
+    }
+}
+
+/// Identifies a state of the search. Whether the last move was a rotation only matters
+/// for T pieces, whose locks after a rotation can be T-spins.
+fn search_key(piece: &Piece, was_rotation: bool) -> (Vec<Point2D>, bool) {
+    (sorted_blocks(piece), was_rotation && piece.piece_type == PieceType::T)
+}
+
+/// Cells covered by `piece`, in reading order so equal placements compare equal.
+pub(crate) fn sorted_blocks(piece: &Piece) -> Vec<Point2D> {
+    let mut blocks = piece.get_blocks_position();
+    blocks.sort_by_key(|block| (block.y, block.x));
+    blocks
+}
+
+/// Rebuilds the steps leading from the first state to `state`.
+fn path_to(states: &[SearchState], mut state: usize) -> Vec<GameAction> {
+    let mut path = Vec::new();
+    while let Some((parent, action)) = states[state].parent {
+        path.push(action);
+        state = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::constants::{COLS, ROWS},
+        enums::{CellType, ClearType},
+    };
+
+    fn game_with_piece(piece_type: PieceType) -> Game {
+        let mut game = Game::new();
+        game.start();
+        game.current_piece = game.spawn_piece_of_type(&piece_type);
+        game
+    }
+
+    #[test]
+    fn o_piece_on_an_empty_board_has_one_placement_per_column_pair() {
+        let game = game_with_piece(PieceType::O);
+
+        let placements = game.placements();
+
+        assert_eq!(placements.len(), COLS - 1);
+        for placement in &placements {
+            assert!(!placement.inputs.contains(&GameAction::SoftDrop));
+            assert_eq!(placement.inputs.last(), Some(&GameAction::HardDrop));
+            assert_eq!(placement.result.pieces_placed, 1);
+        }
+    }
+
+    #[test]
+    fn i_piece_on_an_empty_board_has_seventeen_placements() {
+        // 7 flat and 10 upright, the two flat and two upright orientations landing on the same cells
+        assert_eq!(game_with_piece(PieceType::I).placements().len(), 17);
+    }
+
+    #[test]
+    fn following_the_inputs_reaches_the_placement() {
+        let game = game_with_piece(PieceType::L);
+
+        for placement in game.placements() {
+            let mut played = game.clone();
+            for &action in &placement.inputs {
+                played.apply_placement_step(action);
+            }
+            assert_eq!(played.board.cells, placement.result.board.cells);
+        }
+    }
+
+    #[test]
+    fn tucks_under_an_overhang_are_found() {
+        let mut game = game_with_piece(PieceType::O);
+        // A roof over columns 0 to 2, two rows above the floor
+        for col in 0..3 {
+            game.board.set_cell(ROWS - 3, col, CellType::Filled(BLUE));
+        }
+
+        let tuck = game
+            .placements()
+            .into_iter()
+            .find(|placement| {
+                let cells = &placement.result.board.cells;
+                (ROWS - 2..ROWS).all(|row| cells[row][..2].iter().all(|cell| *cell != CellType::Empty))
+            })
+            .expect("the O piece can slide under the roof");
+
+        assert!(tuck.inputs.contains(&GameAction::SoftDrop));
+    }
+
+    #[test]
+    fn a_covered_t_slot_is_filled_with_a_spin() {
+        let mut game = game_with_piece(PieceType::T);
+        // A T slot in columns 3 to 5 of the two bottom rows, closed by an overhang
+        for col in 0..COLS {
+            if col != 4 {
+                game.board.set_cell(ROWS - 1, col, CellType::Filled(BLUE));
+            }
+            if !(3..=5).contains(&col) {
+                game.board.set_cell(ROWS - 2, col, CellType::Filled(BLUE));
+            }
+        }
+        game.board.set_cell(ROWS - 3, 3, CellType::Filled(BLUE));
+
+        let spin = game
+            .placements()
+            .into_iter()
+            .find(|placement| placement.result.lines_cleared == 2)
+            .expect("the T slot can be filled with a spin");
+
+        assert_eq!(spin.result.last_clear.as_ref().map(|clear| clear.clear_type), Some(ClearType::TSpinDouble));
+    }
+}
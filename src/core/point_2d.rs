@@ -1,5 +1,5 @@
 /// Represents a 2D point with integer coordinates. Y comes first, then X to match a matrix-like structure.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct Point2D {
     pub y: isize,
     pub x: isize
@@ -10,9 +10,4 @@ impl Point2D {
     pub fn new(y: isize, x: isize) -> Self {
         Self { y, x }
     }
-
-    /// Creates a default [`Point2D`] at the origin (0, 0).
-    pub fn default() -> Self {
-        Self { y: 0, x: 0 }
-    }
 }
\ No newline at end of file
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use rand::{Rng, seq::SliceRandom};
+
+/// Pieces remembered by the TGM randomizer, which avoids dealing them again.
+pub(crate) const TGM_HISTORY_SIZE: usize = 4;
+
+/// Times the TGM randomizer rolls before accepting a piece that is in its history.
+pub(crate) const TGM_ROLLS: usize = 4;
+
+/// How the next piece is chosen from the piece set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Randomizer {
+    /// Every piece is equally likely every time.
+    Random,
+    /// Every piece of the set is dealt once, in random order, before the set is shuffled again.
+    Bag,
+    /// Rerolls pieces that were among the last ones dealt (Tetris The Grand Master).
+    Tgm,
+    /// Rolls one more outcome than there are pieces, and rerolls once on that outcome or on
+    /// the previous piece (NES).
+    Nes,
+}
+
+impl Randomizer {
+    /// Name used in rule files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Randomizer::Random => "random",
+            Randomizer::Bag => "bag",
+            Randomizer::Tgm => "tgm",
+            Randomizer::Nes => "nes",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [Randomizer::Random, Randomizer::Bag, Randomizer::Tgm, Randomizer::Nes]
+            .into_iter()
+            .find(|randomizer| randomizer.key() == key)
+    }
+}
+
+/// The state of a [`Randomizer`]: the bag being dealt or the history of recent pieces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceGenerator {
+    pub randomizer: Randomizer,
+    /// Pieces left in the current bag, dealt from the end.
+    bag: Vec<usize>,
+    /// Most recent pieces, the newest at the back. The NES randomizer only keeps the last one.
+    history: VecDeque<usize>,
+}
+
+impl PieceGenerator {
+    pub fn new(randomizer: Randomizer) -> Self {
+        Self {
+            randomizer,
+            bag: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Indices of the pieces left in the current bag, in no particular order. Empty when the
+    /// randomizer does not deal from bags.
+    pub fn bag(&self) -> &[usize] {
+        &self.bag
+    }
+
+    /// Returns the index of the next piece among `count` pieces.
+    pub fn next_index<R: Rng + ?Sized>(&mut self, count: usize, rng: &mut R) -> usize {
+        match self.randomizer {
+            Randomizer::Random => rng.random_range(0..count),
+            Randomizer::Bag => {
+                if self.bag.is_empty() {
+                    self.bag = (0..count).collect();
+                    self.bag.shuffle(rng);
+                }
+                self.bag.pop().unwrap_or(0)
+            }
+            Randomizer::Tgm => {
+                let mut index = rng.random_range(0..count);
+                for _ in 1..TGM_ROLLS {
+                    if !self.history.contains(&index) {
+                        break;
+                    }
+                    index = rng.random_range(0..count);
+                }
+                self.history.push_back(index);
+                if self.history.len() > TGM_HISTORY_SIZE {
+                    self.history.pop_front();
+                }
+                index
+            }
+            Randomizer::Nes => {
+                let roll = rng.random_range(0..=count);
+                let index = if roll == count || self.history.back() == Some(&roll) {
+                    rng.random_range(0..count)
+                } else {
+                    roll
+                };
+                self.history.clear();
+                self.history.push_back(index);
+                index
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn bag_deals_every_piece_once_before_repeating() {
+        let mut generator = PieceGenerator::new(Randomizer::Bag);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..3 {
+            let mut bag: Vec<usize> = (0..7).map(|_| generator.next_index(7, &mut rng)).collect();
+            bag.sort();
+            assert_eq!(bag, (0..7).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn tgm_randomizer_rarely_repeats_recent_pieces() {
+        let mut generator = PieceGenerator::new(Randomizer::Tgm);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let pieces: Vec<usize> = (0..700).map(|_| generator.next_index(7, &mut rng)).collect();
+        let repeats = pieces.windows(2).filter(|pair| pair[0] == pair[1]).count();
+
+        // A uniform randomizer repeats about one piece in seven, 100 out of 700
+        assert!(repeats < 50, "{} repeats", repeats);
+    }
+
+    #[test]
+    fn nes_randomizer_repeats_less_than_uniform_but_still_repeats() {
+        let mut generator = PieceGenerator::new(Randomizer::Nes);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let pieces: Vec<usize> = (0..7000).map(|_| generator.next_index(7, &mut rng)).collect();
+        let repeats = pieces.windows(2).filter(|pair| pair[0] == pair[1]).count();
+
+        // A repeat needs the reroll, about 2/8 of the time, to land on the previous piece: 1/28
+        assert!((150..350).contains(&repeats), "{} repeats", repeats);
+        assert!(pieces.iter().all(|&piece| piece < 7));
+    }
+}
@@ -0,0 +1,214 @@
+use toml::de::{DeTable, DeValue};
+
+/// A value read from a TOML file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<TomlValue>),
+}
+
+/// A `key = value` pair, the key prefixed with its table, e.g. `board.rows`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlEntry {
+    pub key: String,
+    pub value: TomlValue,
+    /// Line of the file the pair is on, starting at 1.
+    pub line: usize,
+}
+
+impl TomlValue {
+    /// Describes the kind of value, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TomlValue::String(_) => "a string",
+            TomlValue::Integer(_) => "an integer",
+            TomlValue::Float(_) => "a number",
+            TomlValue::Boolean(_) => "a boolean",
+            TomlValue::Array(_) => "an array",
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            TomlValue::String(text) => Ok(text),
+            other => Err(format!("expected a string, found {}", other.kind())),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            TomlValue::Boolean(value) => Ok(*value),
+            other => Err(format!("expected true or false, found {}", other.kind())),
+        }
+    }
+
+    /// Reads a whole number that is zero or more.
+    pub fn as_usize(&self) -> Result<usize, String> {
+        match self {
+            TomlValue::Integer(value) => {
+                usize::try_from(*value).map_err(|_| format!("expected a positive whole number, found {}", value))
+            }
+            other => Err(format!("expected a whole number, found {}", other.kind())),
+        }
+    }
+
+    /// Reads a number, integers included.
+    pub fn as_f64(&self) -> Result<f64, String> {
+        match self {
+            TomlValue::Integer(value) => Ok(*value as f64),
+            TomlValue::Float(value) => Ok(*value),
+            other => Err(format!("expected a number, found {}", other.kind())),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[TomlValue], String> {
+        match self {
+            TomlValue::Array(values) => Ok(values),
+            other => Err(format!("expected an array, found {}", other.kind())),
+        }
+    }
+}
+
+/// Reads the `key = value` pairs of a TOML file in file order with the `toml` crate, keys of
+/// tables (headers, dotted keys or inline tables) prefixed with the table, e.g. `board.rows`.
+/// Dates and arrays of tables are not used by rule files and are refused. Errors name the line
+/// they are on.
+pub fn parse(text: &str) -> Result<Vec<TomlEntry>, String> {
+    let table = DeTable::parse(text).map_err(|error| match error.span() {
+        Some(span) => format!("line {}: {}", line_at(text, span.start), error.message()),
+        None => error.message().to_string(),
+    })?;
+
+    let mut entries = Vec::new();
+    flatten(text, "", table.get_ref(), &mut entries)?;
+    entries.sort_by_key(|(offset, _)| *offset);
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Adds the pairs of `table` to `entries` with the offset of their key in `text`, the keys
+/// prefixed with `prefix`.
+fn flatten(text: &str, prefix: &str, table: &DeTable, entries: &mut Vec<(usize, TomlEntry)>) -> Result<(), String> {
+    for (key, value) in table {
+        let offset = key.span().start;
+        let line = line_at(text, offset);
+        let key = format!("{}{}", prefix, key.get_ref());
+        match value.get_ref() {
+            DeValue::Table(inner) => flatten(text, &format!("{}.", key), inner, entries)?,
+            value => {
+                let value = convert(value).map_err(|message| format!("line {}: `{}`: {}", line, key, message))?;
+                entries.push((offset, TomlEntry { key, value, line }));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert(value: &DeValue) -> Result<TomlValue, String> {
+    match value {
+        DeValue::String(text) => Ok(TomlValue::String(text.to_string())),
+        DeValue::Integer(integer) => i64::from_str_radix(integer.as_str(), integer.radix())
+            .map(TomlValue::Integer)
+            .map_err(|_| format!("integer `{}` is out of range", integer)),
+        DeValue::Float(float) => float
+            .as_str()
+            .parse()
+            .map(TomlValue::Float)
+            .map_err(|_| format!("invalid number `{}`", float)),
+        DeValue::Boolean(value) => Ok(TomlValue::Boolean(*value)),
+        DeValue::Datetime(_) => Err("dates are not supported in rule files".to_string()),
+        DeValue::Array(items) => items
+            .into_iter()
+            .map(|item| convert(item.get_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(TomlValue::Array),
+        DeValue::Table(_) => Err("tables inside arrays are not supported in rule files".to_string()),
+    }
+}
+
+/// Line of the byte at `offset`, starting at 1.
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_prefixed_with_their_table() {
+        let entries = parse(
+            "name = \"Fast # not a comment\" # a comment\n\
+             [board]\n\
+             rows = 22\n\
+             \n\
+             [gravity]\n\
+             frames_per_row = [48, 43, 38,]\n\
+             scale = 1.5\n\
+             enabled = true\n",
+        )
+        .unwrap();
+
+        let pairs: Vec<(&str, &TomlValue, usize)> =
+            entries.iter().map(|entry| (entry.key.as_str(), &entry.value, entry.line)).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("name", &TomlValue::String("Fast # not a comment".to_string()), 1),
+                ("board.rows", &TomlValue::Integer(22), 3),
+                (
+                    "gravity.frames_per_row",
+                    &TomlValue::Array(vec![TomlValue::Integer(48), TomlValue::Integer(43), TomlValue::Integer(38)]),
+                    6
+                ),
+                ("gravity.scale", &TomlValue::Float(1.5), 7),
+                ("gravity.enabled", &TomlValue::Boolean(true), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_whole_toml_syntax_is_read() {
+        let entries = parse(
+            "pieces.hold = false\n\
+             board = { rows = 22, cols = 12 }\n\
+             [gravity]\n\
+             curve = 'frames'\n\
+             frames_per_row = [\n\
+                 48,\n\
+                 43,\n\
+             ]\n",
+        )
+        .unwrap();
+
+        let keys: Vec<(&str, usize)> = entries.iter().map(|entry| (entry.key.as_str(), entry.line)).collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("pieces.hold", 1),
+                ("board.rows", 2),
+                ("board.cols", 2),
+                ("gravity.curve", 4),
+                ("gravity.frames_per_row", 5),
+            ]
+        );
+        assert_eq!(entries[3].value, TomlValue::String("frames".to_string()));
+    }
+
+    #[test]
+    fn errors_name_the_line_and_key() {
+        assert!(parse("[board]\nrows = twenty").unwrap_err().starts_with("line 2: "));
+        assert!(parse("a = 1\na = 2").unwrap_err().starts_with("line 2: "));
+        assert!(parse("[board\n").unwrap_err().starts_with("line 1: "));
+        assert_eq!(
+            parse("[board]\nbuilt = 2024-01-01").unwrap_err(),
+            "line 2: `board.built`: dates are not supported in rule files"
+        );
+        assert_eq!(
+            parse("[[board]]\nrows = 20").unwrap_err(),
+            "line 1: `board`: tables inside arrays are not supported in rule files"
+        );
+    }
+}
@@ -0,0 +1,614 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    core::{
+        attack::{GarbageSettings, HolePattern},
+        constants::{COLS, ROWS},
+        point_2d::Point2D,
+        randomizer::Randomizer,
+        rule_file::{self, TomlEntry, TomlValue},
+        scoring::{self, LevelProgression, ScoringTable},
+        timings::{ONE_ROW_PER_SECOND, Timings},
+    },
+    enums::ClearType,
+};
+
+/// Directory rule files are loaded from.
+pub(crate) const RULES_DIRECTORY: &str = "presets";
+
+/// Extension of rule files.
+pub(crate) const RULES_EXTENSION: &str = "toml";
+
+/// Most pieces shown in the next queue.
+pub(crate) const MAX_PREVIEWS: usize = 5;
+
+/// Smallest and largest board side allowed by rule files.
+pub(crate) const BOARD_SIZE_RANGE: std::ops::RangeInclusive<usize> = 4..=40;
+
+/// Frames per row on NES for each level from 0. Level 29, the kill screen, and every level
+/// above it drop one row per frame.
+pub(crate) const NES_FRAMES_PER_ROW: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1,
+];
+
+/// Gravity by TGM internal level, in 1/256 of a row per frame (256 = 1G, 5120 = 20G).
+pub(crate) const TGM_GRAVITY_TABLE: [(usize, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+/// How a rotation that collides is moved to find a free position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RotationSystem {
+    /// The kick offsets of each piece definition.
+    Standard,
+    /// One cell to the right, then one cell to the left (Tetris The Grand Master).
+    Tgm,
+    /// No kicks: a rotation that collides is discarded (NES).
+    None,
+}
+
+impl RotationSystem {
+    pub fn key(&self) -> &'static str {
+        match self {
+            RotationSystem::Standard => "standard",
+            RotationSystem::Tgm => "tgm",
+            RotationSystem::None => "none",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [RotationSystem::Standard, RotationSystem::Tgm, RotationSystem::None]
+            .into_iter()
+            .find(|system| system.key() == key)
+    }
+
+    /// Offsets tried in order after the unmoved rotation, given the kicks of the piece.
+    pub fn kicks(&self, piece_kicks: &[Point2D]) -> Vec<Point2D> {
+        match self {
+            RotationSystem::Standard => piece_kicks.to_vec(),
+            RotationSystem::Tgm => vec![Point2D::new(0, 1), Point2D::new(0, -1)],
+            RotationSystem::None => Vec::new(),
+        }
+    }
+}
+
+/// Gravity for each level.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GravityCurve {
+    /// The guideline curve, see [`scoring::fall_speed_for_level`].
+    Guideline,
+    /// Frames the piece takes to fall one row at each level from level 0,
+    /// the last entry applying to every higher level.
+    Frames(Vec<u32>),
+    /// Gravity in rows per frame from each level on, as (first level, gravity) in ascending order.
+    Steps(Vec<(usize, f64)>),
+}
+
+impl GravityCurve {
+    /// Returns the gravity in rows per frame at the given level.
+    pub fn gravity(&self, level: usize) -> f64 {
+        match self {
+            GravityCurve::Guideline => Timings::gravity_from_seconds_per_row(scoring::fall_speed_for_level(level)),
+            GravityCurve::Frames(frames) => {
+                let index = level.min(frames.len().saturating_sub(1));
+                frames.get(index).map_or(ONE_ROW_PER_SECOND, |&frames| 1.0 / frames.max(1) as f64)
+            }
+            GravityCurve::Steps(steps) => steps
+                .iter()
+                .rev()
+                .find(|(start, _)| level >= *start)
+                .or(steps.first())
+                .map_or(ONE_ROW_PER_SECOND, |(_, gravity)| *gravity),
+        }
+    }
+}
+
+/// Every tunable of the game rules: board size, pieces, timings, gravity, garbage and scoring.
+/// Built-in presets are [`RuleSet::guideline`], [`RuleSet::nes`] and [`RuleSet::tgm`],
+/// others are read from TOML files with [`RuleSet::parse`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleSet {
+    pub name: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub rotation_system: RotationSystem,
+    pub randomizer: Randomizer,
+    /// Pieces shown in the next queue, from 0 to [`MAX_PREVIEWS`].
+    pub previews: usize,
+    pub has_hold: bool,
+    /// Whether the hard drop input drops the piece, NES has no hard drop.
+    pub has_hard_drop: bool,
+    /// Delays, auto-shift and soft drop speed. The gravity comes from `gravity_curve`.
+    pub timings: Timings,
+    pub gravity_curve: GravityCurve,
+    /// Lowest level, the level games start at unless another start level is chosen.
+    pub first_level: usize,
+    pub level_progression: LevelProgression,
+    pub lines_per_level: usize,
+    pub garbage: GarbageSettings,
+    pub scoring: ScoringTable,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::guideline()
+    }
+}
+
+impl RuleSet {
+    /// Modern guideline rules: 7-bag, five previews, hold, kicks and lock delay.
+    pub fn guideline() -> Self {
+        Self {
+            name: "Guideline".to_string(),
+            rows: ROWS,
+            cols: COLS,
+            rotation_system: RotationSystem::Standard,
+            randomizer: Randomizer::Bag,
+            previews: MAX_PREVIEWS,
+            has_hold: true,
+            has_hard_drop: true,
+            timings: Timings::default(),
+            gravity_curve: GravityCurve::Guideline,
+            first_level: 1,
+            level_progression: LevelProgression::CatchUp,
+            lines_per_level: scoring::LINES_PER_LEVEL,
+            garbage: GarbageSettings::default(),
+            scoring: ScoringTable::default(),
+        }
+    }
+
+    /// Classic NES rules: one preview, no hold, hard drop or kicks, slow auto-shift, levels from 0
+    /// with the NES gravity table and level transition, and points multiplied by the level plus one.
+    pub fn nes() -> Self {
+        let line_points = |lines: usize| [0, 40, 100, 300, 1200][lines.min(4)];
+        Self {
+            name: "NES".to_string(),
+            rotation_system: RotationSystem::None,
+            randomizer: Randomizer::Nes,
+            previews: 1,
+            has_hold: false,
+            has_hard_drop: false,
+            timings: Timings {
+                gravity: ONE_ROW_PER_SECOND,
+                soft_drop_gravity: 0.5,
+                are: 10,
+                line_clear_delay: 17,
+                das: 16,
+                arr: 6,
+                lock_delay: 0,
+                // A landed piece locks when the next gravity drop fails
+                locks_on_failed_drop: true,
+            },
+            gravity_curve: GravityCurve::Frames(NES_FRAMES_PER_ROW.to_vec()),
+            first_level: 0,
+            level_progression: LevelProgression::Transition,
+            scoring: ScoringTable {
+                // No T-spins on NES: they score as plain line clears
+                clear_points: ClearType::ALL.map(|clear_type| line_points(clear_type.lines())),
+                back_to_back_percent: 100,
+                combo_points: 0,
+                soft_drop_points: 1,
+                hard_drop_points: 0,
+                has_perfect_clear_bonus: false,
+                level_bonus: 1,
+            },
+            ..Self::guideline()
+        }
+    }
+
+    /// Tetris The Grand Master rules: TGM randomizer and kicks, no hold, long delays and a gravity
+    /// table where every line is a level.
+    pub fn tgm() -> Self {
+        Self {
+            name: "TGM".to_string(),
+            rotation_system: RotationSystem::Tgm,
+            randomizer: Randomizer::Tgm,
+            previews: 1,
+            has_hold: false,
+            timings: Timings {
+                gravity: ONE_ROW_PER_SECOND,
+                soft_drop_gravity: 1.0,
+                are: 30,
+                line_clear_delay: 41,
+                das: 16,
+                arr: 1,
+                lock_delay: 30,
+                locks_on_failed_drop: false,
+            },
+            gravity_curve: GravityCurve::Steps(
+                TGM_GRAVITY_TABLE
+                    .iter()
+                    .map(|&(level, gravity)| (level + 1, gravity as f64 / 256.0))
+                    .collect(),
+            ),
+            lines_per_level: 1,
+            scoring: ScoringTable {
+                back_to_back_percent: 100,
+                ..ScoringTable::default()
+            },
+            ..Self::guideline()
+        }
+    }
+
+    /// The built-in presets, in menu order.
+    pub fn presets() -> Vec<Self> {
+        vec![Self::guideline(), Self::nes(), Self::tgm()]
+    }
+
+    /// Returns the built-in preset with the given name, ignoring case.
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::presets()
+            .into_iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Reads rules from a TOML file. Keys that are left out keep the value of the preset named
+    /// by the `preset` key, Guideline by default. Errors point at the offending key and line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let entries = rule_file::parse(text)?;
+        let lines: HashMap<&str, usize> = entries.iter().map(|entry| (entry.key.as_str(), entry.line)).collect();
+        let error_at = |key: &str, message: String| match lines.get(key) {
+            Some(line) => format!("line {}: `{}`: {}", line, key, message),
+            None => format!("`{}`: {}", key, message),
+        };
+
+        let mut rules = match entries.iter().find(|entry| entry.key == "preset") {
+            Some(entry) => {
+                let name = entry.value.as_str().map_err(|message| error_at("preset", message))?;
+                Self::by_name(name)
+                    .ok_or_else(|| error_at("preset", format!("unknown preset \"{}\" (expected Guideline, NES or TGM)", name)))?
+            }
+            None => Self::guideline(),
+        };
+        // A file based on a preset without a name of its own is not mistaken for the preset
+        if !lines.contains_key("name") {
+            rules.name = "Custom".to_string();
+        }
+
+        let mut steps = (None, None);
+        for entry in &entries {
+            rules
+                .apply(entry, &mut steps)
+                .map_err(|message| error_at(&entry.key, message))?;
+        }
+
+        match steps {
+            (Some(levels), Some(gravities)) => {
+                let levels: Vec<usize> = levels;
+                let gravities: Vec<f64> = gravities;
+                if levels.len() != gravities.len() {
+                    return Err(error_at(
+                        "gravity.levels",
+                        "must have as many entries as `gravity.rows_per_frame`".to_string(),
+                    ));
+                }
+                if levels.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(error_at("gravity.levels", "levels must be in ascending order".to_string()));
+                }
+                rules.gravity_curve = GravityCurve::Steps(levels.into_iter().zip(gravities).collect());
+            }
+            (None, None) => {}
+            (Some(_), None) => return Err(error_at("gravity.levels", "needs `gravity.rows_per_frame`".to_string())),
+            (None, Some(_)) => return Err(error_at("gravity.rows_per_frame", "needs `gravity.levels`".to_string())),
+        }
+
+        let is_steps_curve = matches!(rules.gravity_curve, GravityCurve::Steps(_));
+        if lines.contains_key("gravity.curve") && is_steps_curve != lines.contains_key("gravity.levels") {
+            return Err(error_at(
+                "gravity.curve",
+                "the steps curve is set with `gravity.levels` and `gravity.rows_per_frame`".to_string(),
+            ));
+        }
+        Ok(rules)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// The built-in presets followed by the valid rule files of [`RULES_DIRECTORY`].
+    /// Invalid files are skipped with an error message.
+    pub fn load_all() -> Vec<Self> {
+        let mut paths: Vec<_> = fs::read_dir(RULES_DIRECTORY)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == RULES_EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let mut rules = Self::presets();
+        rules.extend(
+            paths
+                .iter()
+                .filter_map(|path| Self::load(path).inspect_err(|error| eprintln!("Skipping rules {}", error)).ok()),
+        );
+        rules
+    }
+
+    /// Sets the value of one key. The steps of a steps gravity curve are collected in `steps`
+    /// and checked once the whole file has been read.
+    fn apply(&mut self, entry: &TomlEntry, steps: &mut (Option<Vec<usize>>, Option<Vec<f64>>)) -> Result<(), String> {
+        let value = &entry.value;
+        match entry.key.as_str() {
+            "preset" => {}
+            "name" => self.name = value.as_str()?.to_string(),
+            "board.rows" => self.rows = board_size(value)?,
+            "board.cols" => self.cols = board_size(value)?,
+            "pieces.rotation_system" => {
+                let key = value.as_str()?;
+                self.rotation_system = RotationSystem::from_key(key)
+                    .ok_or_else(|| format!("unknown rotation system \"{}\" (expected standard, tgm or none)", key))?;
+            }
+            "pieces.randomizer" => {
+                let key = value.as_str()?;
+                self.randomizer = Randomizer::from_key(key)
+                    .ok_or_else(|| format!("unknown randomizer \"{}\" (expected random, bag, tgm or nes)", key))?;
+            }
+            "pieces.previews" => {
+                self.previews = value.as_usize()?;
+                if self.previews > MAX_PREVIEWS {
+                    return Err(format!("at most {} previews are supported", MAX_PREVIEWS));
+                }
+            }
+            "pieces.hold" => self.has_hold = value.as_bool()?,
+            "pieces.hard_drop" => self.has_hard_drop = value.as_bool()?,
+            "timings.lock_delay" => self.timings.lock_delay = frames(value)?,
+            "timings.lock_on_failed_drop" => self.timings.locks_on_failed_drop = value.as_bool()?,
+            "timings.are" => self.timings.are = frames(value)?,
+            "timings.line_clear_delay" => self.timings.line_clear_delay = frames(value)?,
+            "timings.das" => self.timings.das = frames(value)?,
+            "timings.arr" => self.timings.arr = frames(value)?,
+            "timings.soft_drop_gravity" => self.timings.soft_drop_gravity = positive(value)?,
+            "gravity.curve" => match value.as_str()? {
+                "guideline" => self.gravity_curve = GravityCurve::Guideline,
+                "frames" if !matches!(self.gravity_curve, GravityCurve::Frames(_)) => {
+                    self.gravity_curve = GravityCurve::Frames(NES_FRAMES_PER_ROW.to_vec());
+                }
+                "steps" if !matches!(self.gravity_curve, GravityCurve::Steps(_)) => {
+                    self.gravity_curve = GravityCurve::Steps(Vec::new());
+                }
+                "frames" | "steps" => {}
+                other => return Err(format!("unknown gravity curve \"{}\" (expected guideline, frames or steps)", other)),
+            },
+            "gravity.frames_per_row" => {
+                let frames = array(value, frames)?;
+                if frames.is_empty() || frames.contains(&0) {
+                    return Err("expected at least one frame count, each at least 1".to_string());
+                }
+                self.gravity_curve = GravityCurve::Frames(frames);
+            }
+            "gravity.levels" => steps.0 = Some(array(value, |value| value.as_usize())?),
+            "gravity.rows_per_frame" => steps.1 = Some(array(value, positive)?),
+            "gravity.first_level" => self.first_level = value.as_usize()?,
+            "gravity.level_progression" => {
+                let key = value.as_str()?;
+                self.level_progression = LevelProgression::from_key(key)
+                    .ok_or_else(|| format!("unknown level progression \"{}\" (expected catch_up or transition)", key))?;
+            }
+            "gravity.lines_per_level" => {
+                self.lines_per_level = value.as_usize()?;
+                if self.lines_per_level == 0 {
+                    return Err("must be at least 1".to_string());
+                }
+            }
+            "garbage.delay_frames" => self.garbage.delay_frames = frames(value)?,
+            "garbage.cap_per_lock" => self.garbage.cap_per_lock = value.as_usize()?,
+            "garbage.hole_pattern" => {
+                let key = value.as_str()?;
+                self.garbage.hole_pattern = HolePattern::from_key(key).ok_or_else(|| {
+                    format!("unknown hole pattern \"{}\" (expected same_column, per_attack or per_row)", key)
+                })?;
+            }
+            "scoring.back_to_back_percent" => self.scoring.back_to_back_percent = points(value)?,
+            "scoring.combo" => self.scoring.combo_points = points(value)?,
+            "scoring.soft_drop" => self.scoring.soft_drop_points = points(value)?,
+            "scoring.hard_drop" => self.scoring.hard_drop_points = points(value)?,
+            "scoring.perfect_clear_bonus" => self.scoring.has_perfect_clear_bonus = value.as_bool()?,
+            key => {
+                let clear_type = key
+                    .strip_prefix("scoring.")
+                    .and_then(|name| ClearType::ALL.iter().position(|clear_type| clear_type_key(*clear_type) == name));
+                match clear_type {
+                    Some(index) => self.scoring.clear_points[index] = points(value)?,
+                    None => return Err("unknown key".to_string()),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Key of the points of a clear type in rule files, e.g. `t_spin_double`.
+pub fn clear_type_key(clear_type: ClearType) -> String {
+    clear_type.name().to_lowercase().replace(['-', ' '], "_")
+}
+
+fn board_size(value: &TomlValue) -> Result<usize, String> {
+    let size = value.as_usize()?;
+    if !BOARD_SIZE_RANGE.contains(&size) {
+        return Err(format!(
+            "must be between {} and {}",
+            BOARD_SIZE_RANGE.start(),
+            BOARD_SIZE_RANGE.end()
+        ));
+    }
+    Ok(size)
+}
+
+fn frames(value: &TomlValue) -> Result<u32, String> {
+    u32::try_from(value.as_usize()?).map_err(|_| "too many frames".to_string())
+}
+
+fn points(value: &TomlValue) -> Result<u64, String> {
+    Ok(value.as_usize()? as u64)
+}
+
+fn positive(value: &TomlValue) -> Result<f64, String> {
+    let number = value.as_f64()?;
+    if number <= 0.0 {
+        return Err(format!("must be more than 0, found {}", number));
+    }
+    Ok(number)
+}
+
+fn array<T>(value: &TomlValue, read: impl Fn(&TomlValue) -> Result<T, String>) -> Result<Vec<T>, String> {
+    value
+        .as_array()?
+        .iter()
+        .enumerate()
+        .map(|(index, item)| read(item).map_err(|message| format!("item {}: {}", index + 1, message)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_left_out_keep_the_preset_values() {
+        let rules = RuleSet::parse(
+            "name = \"Wide NES\"\n\
+             preset = \"nes\"\n\
+             [board]\n\
+             cols = 12\n\
+             [scoring]\n\
+             tetris = 1600\n",
+        )
+        .unwrap();
+
+        assert_eq!(rules.name, "Wide NES");
+        assert_eq!(rules.cols, 12);
+        assert_eq!(rules.rows, ROWS);
+        assert_eq!(rules.rotation_system, RotationSystem::None);
+        assert_eq!(rules.scoring.clear_points[3], 1600);
+        assert_eq!(rules.scoring.clear_points[0], 40);
+    }
+
+    #[test]
+    fn every_tunable_can_be_set() {
+        let rules = RuleSet::parse(
+            "[pieces]\n\
+             rotation_system = \"tgm\"\n\
+             randomizer = \"random\"\n\
+             previews = 3\n\
+             hold = false\n\
+             [timings]\n\
+             lock_delay = 20\n\
+             lock_on_failed_drop = true\n\
+             are = 6\n\
+             line_clear_delay = 12\n\
+             das = 8\n\
+             arr = 0\n\
+             soft_drop_gravity = 0.5\n\
+             [gravity]\n\
+             curve = \"steps\"\n\
+             levels = [1, 5]\n\
+             rows_per_frame = [0.1, 1]\n\
+             lines_per_level = 5\n\
+             [garbage]\n\
+             delay_frames = 0\n\
+             cap_per_lock = 4\n\
+             hole_pattern = \"per_row\"\n\
+             [scoring]\n\
+             t_spin_double = 1500\n\
+             combo = 0\n\
+             perfect_clear_bonus = false\n",
+        )
+        .unwrap();
+
+        assert_eq!(rules.name, "Custom");
+        assert_eq!(rules.randomizer, Randomizer::Random);
+        assert_eq!(rules.previews, 3);
+        assert!(!rules.has_hold);
+        assert_eq!(rules.timings.are, 6);
+        assert_eq!(rules.timings.arr, 0);
+        assert!(rules.timings.locks_on_failed_drop);
+        assert_eq!(rules.gravity_curve, GravityCurve::Steps(vec![(1, 0.1), (5, 1.0)]));
+        assert_eq!(rules.gravity_curve.gravity(7), 1.0);
+        assert_eq!(rules.lines_per_level, 5);
+        assert_eq!(rules.garbage.hole_pattern, HolePattern::PerRow);
+        assert_eq!(rules.scoring.clear_points[9], 1500);
+        assert!(!rules.scoring.has_perfect_clear_bonus);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_key() {
+        let error = |text: &str| RuleSet::parse(text).unwrap_err();
+
+        assert_eq!(error("[board]\nrows = 2"), "line 2: `board.rows`: must be between 4 and 40");
+        assert_eq!(error("[board]\nwidth = 12"), "line 2: `board.width`: unknown key");
+        assert_eq!(
+            error("[pieces]\nrandomizer = \"nintendo\""),
+            "line 2: `pieces.randomizer`: unknown randomizer \"nintendo\" (expected random, bag, tgm or nes)"
+        );
+        assert_eq!(
+            error("[timings]\ndas = -1"),
+            "line 2: `timings.das`: expected a positive whole number, found -1"
+        );
+        assert_eq!(
+            error("[gravity]\nlevels = [1, 10]\nrows_per_frame = [0.5]"),
+            "line 2: `gravity.levels`: must have as many entries as `gravity.rows_per_frame`"
+        );
+        assert_eq!(
+            error("[gravity]\nframes_per_row = [4, \"fast\"]"),
+            "line 2: `gravity.frames_per_row`: item 2: expected a whole number, found a string"
+        );
+    }
+
+    #[test]
+    fn gravity_curves_give_rows_per_frame_by_level() {
+        assert_eq!(GravityCurve::Guideline.gravity(1), ONE_ROW_PER_SECOND);
+        let nes = RuleSet::nes().gravity_curve;
+        assert_eq!(nes.gravity(0), 1.0 / 48.0);
+        assert_eq!(nes.gravity(28), 0.5);
+        assert_eq!(nes.gravity(29), 1.0);
+        assert_eq!(nes.gravity(99), 1.0);
+        let tgm = RuleSet::tgm().gravity_curve;
+        assert_eq!(tgm.gravity(1), 4.0 / 256.0);
+        assert_eq!(tgm.gravity(501), 20.0);
+    }
+
+    #[test]
+    fn rule_files_in_the_repository_are_valid() {
+        for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(RULES_DIRECTORY)).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(error) = RuleSet::load(&path) {
+                panic!("{}", error);
+            }
+        }
+    }
+}
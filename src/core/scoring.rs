@@ -0,0 +1,271 @@
+use crate::enums::{ClearType, TSpinType};
+
+/// Number of cleared lines needed to advance one level.
+pub(crate) const LINES_PER_LEVEL: usize = 10;
+
+/// Points awarded per cell when the piece is soft dropped.
+pub(crate) const SOFT_DROP_POINTS_PER_CELL: u64 = 1;
+
+/// Points awarded per cell when the piece is hard dropped.
+pub(crate) const HARD_DROP_POINTS_PER_CELL: u64 = 2;
+
+/// Describes what happened when a piece was locked and cleared lines (or made a T-spin).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineClear {
+    pub clear_type: ClearType,
+    /// Number of consecutive locks that cleared lines before this one, `0` for the first clear.
+    pub combo: usize,
+    /// Set when this difficult clear directly follows another difficult clear.
+    pub is_back_to_back: bool,
+    /// Set when the board is completely empty after the clear.
+    pub is_perfect_clear: bool,
+    /// Total points awarded for the clear, including combo, back-to-back and perfect clear bonuses.
+    pub points: u64,
+}
+
+/// Running totals for one [`ClearType`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClearStats {
+    pub count: usize,
+    pub points: u64,
+}
+
+/// Returns the clear type for clearing `lines` rows with the given kind of T-spin,
+/// or `None` when nothing worth scoring happened.
+pub fn classify_clear(lines: usize, t_spin: TSpinType) -> Option<ClearType> {
+    match (t_spin, lines) {
+        (TSpinType::None, 0) => None,
+        (TSpinType::None, 1) => Some(ClearType::Single),
+        (TSpinType::None, 2) => Some(ClearType::Double),
+        (TSpinType::None, 3) => Some(ClearType::Triple),
+        (TSpinType::None, _) => Some(ClearType::Tetris),
+        (TSpinType::Mini, 0) => Some(ClearType::TSpinMini),
+        (TSpinType::Mini, 1) => Some(ClearType::TSpinMiniSingle),
+        (TSpinType::Mini, _) => Some(ClearType::TSpinMiniDouble),
+        (TSpinType::Full, 0) => Some(ClearType::TSpin),
+        (TSpinType::Full, 1) => Some(ClearType::TSpinSingle),
+        (TSpinType::Full, 2) => Some(ClearType::TSpinDouble),
+        (TSpinType::Full, _) => Some(ClearType::TSpinTriple),
+    }
+}
+
+/// Points awarded for clears and drops, the guideline values by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoringTable {
+    /// Points of each clear type at level 1, in the order of [`ClearType::ALL`].
+    pub clear_points: [u64; 11],
+    /// Percentage of the points a back-to-back difficult clear is worth, 150 in the guideline.
+    pub back_to_back_percent: u64,
+    /// Points per combo step at level 1.
+    pub combo_points: u64,
+    pub soft_drop_points: u64,
+    pub hard_drop_points: u64,
+    /// Whether emptying the board awards the perfect clear bonus.
+    pub has_perfect_clear_bonus: bool,
+    /// Added to the level points are multiplied by, 1 on NES where the first level is 0.
+    pub level_bonus: u64,
+}
+
+impl Default for ScoringTable {
+    fn default() -> Self {
+        Self {
+            clear_points: ClearType::ALL.map(|clear_type| clear_type_points(clear_type, 1, false)),
+            back_to_back_percent: 150,
+            combo_points: combo_points(1, 1),
+            soft_drop_points: SOFT_DROP_POINTS_PER_CELL,
+            hard_drop_points: HARD_DROP_POINTS_PER_CELL,
+            has_perfect_clear_bonus: true,
+            level_bonus: 0,
+        }
+    }
+}
+
+impl ScoringTable {
+    /// Returns the points for a clear at the given `level`.
+    pub fn clear_points(&self, clear_type: ClearType, level: usize, is_back_to_back: bool) -> u64 {
+        let index = ClearType::ALL.iter().position(|other| *other == clear_type).unwrap_or(0);
+        let points = self.clear_points[index] * (level as u64 + self.level_bonus);
+        if is_back_to_back { points * self.back_to_back_percent / 100 } else { points }
+    }
+
+    /// Returns the bonus for the `combo`-th consecutive clear at the given `level`.
+    pub fn combo_points(&self, combo: usize, level: usize) -> u64 {
+        self.combo_points * combo as u64 * (level as u64 + self.level_bonus)
+    }
+}
+
+/// Returns the points for a clear at the given `level`, using the guideline table.
+/// Back-to-back difficult clears are worth 1.5 times as much.
+pub fn clear_type_points(clear_type: ClearType, level: usize, is_back_to_back: bool) -> u64 {
+    let base = match clear_type {
+        ClearType::Single => 100,
+        ClearType::Double => 300,
+        ClearType::Triple => 500,
+        ClearType::Tetris => 800,
+        ClearType::TSpinMini => 100,
+        ClearType::TSpinMiniSingle => 200,
+        ClearType::TSpinMiniDouble => 400,
+        ClearType::TSpin => 400,
+        ClearType::TSpinSingle => 800,
+        ClearType::TSpinDouble => 1200,
+        ClearType::TSpinTriple => 1600,
+    };
+    let points = base * level as u64;
+    if is_back_to_back { points * 3 / 2 } else { points }
+}
+
+/// Returns the bonus for the `combo`-th consecutive clear at the given `level`.
+pub fn combo_points(combo: usize, level: usize) -> u64 {
+    50 * combo as u64 * level as u64
+}
+
+/// Returns the bonus for emptying the board by clearing `lines` rows at the given `level`.
+pub fn perfect_clear_points(lines: usize, level: usize, is_back_to_back: bool) -> u64 {
+    let base = match lines {
+        0 => 0,
+        1 => 800,
+        2 => 1200,
+        3 => 1800,
+        _ if is_back_to_back => 3200,
+        _ => 2000,
+    };
+    base * level as u64
+}
+
+/// How the level rises with the lines cleared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelProgression {
+    /// One level every `lines_per_level` lines counted from the first level, a higher start
+    /// level being kept until the lines catch up (guideline).
+    CatchUp,
+    /// The first level up comes after the transition line count of the start level
+    /// (see [`nes_transition_lines`]), then one level every `lines_per_level` lines (NES).
+    Transition,
+}
+
+impl LevelProgression {
+    /// Name used in rule files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            LevelProgression::CatchUp => "catch_up",
+            LevelProgression::Transition => "transition",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [LevelProgression::CatchUp, LevelProgression::Transition]
+            .into_iter()
+            .find(|progression| progression.key() == key)
+    }
+
+    /// Returns the level reached after clearing `lines_cleared` lines in a game started at
+    /// `start_level`, the lowest level being `first_level`.
+    pub fn level(&self, first_level: usize, start_level: usize, lines_cleared: usize, lines_per_level: usize) -> usize {
+        let lines_per_level = lines_per_level.max(1);
+        match self {
+            LevelProgression::CatchUp => start_level.max(first_level + lines_cleared / lines_per_level),
+            LevelProgression::Transition => {
+                let transition = nes_transition_lines(start_level);
+                if lines_cleared < transition {
+                    start_level
+                } else {
+                    start_level + 1 + (lines_cleared - transition) / lines_per_level
+                }
+            }
+        }
+    }
+}
+
+/// Lines to clear before the first level up on NES when starting at `start_level`:
+/// ten lines per level up to level 9, then never less than 100 lines.
+pub fn nes_transition_lines(start_level: usize) -> usize {
+    (start_level * 10 + 10).min(100.max((start_level * 10).saturating_sub(50)))
+}
+
+/// Returns how many seconds the piece takes to fall one row at the given `level`.
+/// Uses the guideline gravity curve: (0.8 - (level - 1) * 0.007) ^ (level - 1).
+pub fn fall_speed_for_level(level: usize) -> f64 {
+    let level = level.max(1) as f64 - 1.0;
+    (0.8 - level * 0.007).max(0.0).powf(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_points_follow_guideline_table() {
+        assert_eq!(clear_type_points(ClearType::Single, 1, false), 100);
+        assert_eq!(clear_type_points(ClearType::Double, 1, false), 300);
+        assert_eq!(clear_type_points(ClearType::Triple, 2, false), 1000);
+        assert_eq!(clear_type_points(ClearType::Tetris, 3, false), 2400);
+        assert_eq!(clear_type_points(ClearType::TSpinDouble, 1, false), 1200);
+    }
+
+    #[test]
+    fn back_to_back_clears_are_worth_one_and_a_half() {
+        assert_eq!(clear_type_points(ClearType::Tetris, 1, true), 1200);
+        assert_eq!(clear_type_points(ClearType::TSpinTriple, 2, true), 4800);
+    }
+
+    #[test]
+    fn classify_clear_combines_lines_and_t_spins() {
+        assert_eq!(classify_clear(0, TSpinType::None), None);
+        assert_eq!(classify_clear(4, TSpinType::None), Some(ClearType::Tetris));
+        assert_eq!(classify_clear(0, TSpinType::Full), Some(ClearType::TSpin));
+        assert_eq!(classify_clear(1, TSpinType::Mini), Some(ClearType::TSpinMiniSingle));
+        assert_eq!(classify_clear(2, TSpinType::Full), Some(ClearType::TSpinDouble));
+    }
+
+    #[test]
+    fn combo_and_perfect_clear_bonuses_scale_with_level() {
+        assert_eq!(combo_points(0, 5), 0);
+        assert_eq!(combo_points(3, 2), 300);
+        assert_eq!(perfect_clear_points(4, 1, false), 2000);
+        assert_eq!(perfect_clear_points(4, 2, true), 6400);
+    }
+
+    #[test]
+    fn default_scoring_table_matches_the_guideline_points() {
+        let table = ScoringTable::default();
+        for clear_type in ClearType::ALL {
+            assert_eq!(table.clear_points(clear_type, 3, true), clear_type_points(clear_type, 3, true));
+        }
+        assert_eq!(table.combo_points(3, 2), combo_points(3, 2));
+    }
+
+    #[test]
+    fn level_advances_every_ten_lines() {
+        let level = |start, lines| LevelProgression::CatchUp.level(1, start, lines, LINES_PER_LEVEL);
+        assert_eq!(level(1, 0), 1);
+        assert_eq!(level(1, 9), 1);
+        assert_eq!(level(1, 10), 2);
+        assert_eq!(level(1, 149), 15);
+        // A higher start level is kept until the lines catch up
+        assert_eq!(level(5, 12), 5);
+    }
+
+    #[test]
+    fn nes_levels_rise_after_the_transition_of_the_start_level() {
+        assert_eq!(nes_transition_lines(0), 10);
+        assert_eq!(nes_transition_lines(9), 100);
+        assert_eq!(nes_transition_lines(15), 100);
+        assert_eq!(nes_transition_lines(18), 130);
+        assert_eq!(nes_transition_lines(19), 140);
+
+        let level = |start, lines| LevelProgression::Transition.level(0, start, lines, LINES_PER_LEVEL);
+        assert_eq!(level(0, 9), 0);
+        assert_eq!(level(0, 10), 1);
+        assert_eq!(level(18, 129), 18);
+        assert_eq!(level(18, 130), 19);
+        assert_eq!(level(18, 139), 19);
+        assert_eq!(level(18, 140), 20);
+    }
+
+    #[test]
+    fn fall_speed_gets_faster_with_level() {
+        assert_eq!(fall_speed_for_level(1), 1.0);
+        assert!(fall_speed_for_level(2) < fall_speed_for_level(1));
+        assert!(fall_speed_for_level(15) < 0.01);
+    }
+}
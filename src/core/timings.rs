@@ -0,0 +1,66 @@
+use crate::core::constants::FRAMES_PER_SECOND;
+
+/// Gravity of a piece that falls one row per second, in rows per frame.
+pub(crate) const ONE_ROW_PER_SECOND: f64 = 1.0 / FRAMES_PER_SECOND;
+
+/// Gravity that drops a piece to the bottom of a 20 row board on the frame it appears.
+pub(crate) const TWENTY_G: f64 = 20.0;
+
+/// Timings that control how fast the game plays, expressed in frames at [`FRAMES_PER_SECOND`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timings {
+    /// Rows the piece falls per frame, e.g. [`ONE_ROW_PER_SECOND`] or [`TWENTY_G`].
+    pub gravity: f64,
+    /// Rows per frame while soft drop is held, unless the normal gravity is faster.
+    pub soft_drop_gravity: f64,
+    /// Frames between a piece locking and the next one appearing (ARE).
+    pub are: u32,
+    /// Extra frames added to ARE when the locked piece cleared lines.
+    pub line_clear_delay: u32,
+    /// Frames a direction must be held before the piece starts auto-shifting (DAS).
+    pub das: u32,
+    /// Frames between moves once auto-shift has started (ARR), 0 moves straight to the wall.
+    pub arr: u32,
+    /// Frames a piece can rest on the stack before it locks.
+    pub lock_delay: u32,
+    /// Whether a resting piece locks only once gravity fails to move it down, as on NES, so it
+    /// can slide for a whole gravity period. `lock_delay` is then not used.
+    pub locks_on_failed_drop: bool,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            gravity: ONE_ROW_PER_SECOND,
+            soft_drop_gravity: 20.0 * ONE_ROW_PER_SECOND,
+            are: 0,
+            line_clear_delay: 0,
+            das: 10,
+            arr: 2,
+            lock_delay: 30,
+            locks_on_failed_drop: false,
+        }
+    }
+}
+
+impl Timings {
+    /// Converts a fall speed in seconds per row to gravity in rows per frame.
+    pub fn gravity_from_seconds_per_row(seconds_per_row: f64) -> f64 {
+        if seconds_per_row <= 0.0 {
+            return TWENTY_G;
+        }
+        1.0 / (seconds_per_row * FRAMES_PER_SECOND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_from_seconds_per_row_converts_to_rows_per_frame() {
+        assert_eq!(Timings::gravity_from_seconds_per_row(1.0), ONE_ROW_PER_SECOND);
+        assert_eq!(Timings::gravity_from_seconds_per_row(1.0 / 120.0), 2.0);
+        assert_eq!(Timings::gravity_from_seconds_per_row(0.0), TWENTY_G);
+    }
+}
@@ -0,0 +1,60 @@
+/// How strong the built-in versus bot plays.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BotDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl BotDifficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotDifficulty::Easy => "Easy",
+            BotDifficulty::Medium => "Medium",
+            BotDifficulty::Hard => "Hard",
+        }
+    }
+
+    /// Returns the difficulty with the given name, ignoring case.
+    pub fn by_name(name: &str) -> Option<Self> {
+        [BotDifficulty::Easy, BotDifficulty::Medium, BotDifficulty::Hard]
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The next difficulty in the cycle Easy, Medium, Hard.
+    pub fn next(&self) -> Self {
+        match self {
+            BotDifficulty::Easy => BotDifficulty::Medium,
+            BotDifficulty::Medium => BotDifficulty::Hard,
+            BotDifficulty::Hard => BotDifficulty::Easy,
+        }
+    }
+
+    /// Most pieces the bot places per second.
+    pub fn pieces_per_second(&self) -> f64 {
+        match self {
+            BotDifficulty::Easy => 0.8,
+            BotDifficulty::Medium => 1.6,
+            BotDifficulty::Hard => 3.0,
+        }
+    }
+
+    /// Chance that the bot picks a random placement instead of its best one.
+    pub fn mistake_chance(&self) -> f64 {
+        match self {
+            BotDifficulty::Easy => 0.2,
+            BotDifficulty::Medium => 0.07,
+            BotDifficulty::Hard => 0.0,
+        }
+    }
+
+    /// Pieces of the queue the bot plans ahead for, beyond the one it places.
+    pub fn lookahead(&self) -> usize {
+        match self {
+            BotDifficulty::Easy => 0,
+            BotDifficulty::Medium => 1,
+            BotDifficulty::Hard => 2,
+        }
+    }
+}
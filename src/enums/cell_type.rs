@@ -4,4 +4,6 @@ use macroquad::color::Color;
 pub enum CellType {
     Empty,
     Filled(Color),
-}
\ No newline at end of file
+    /// A cell of a garbage row pushed up from the bottom of the board.
+    Garbage,
+}
@@ -0,0 +1,73 @@
+/// The kind of line clear made by locking a piece, used for scoring and statistics.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum ClearType {
+    Single,
+    Double,
+    Triple,
+    Tetris,
+    TSpinMini,
+    TSpinMiniSingle,
+    TSpinMiniDouble,
+    TSpin,
+    TSpinSingle,
+    TSpinDouble,
+    TSpinTriple,
+}
+
+impl ClearType {
+    /// Every clear type, in display order.
+    pub const ALL: [ClearType; 11] = [
+        ClearType::Single,
+        ClearType::Double,
+        ClearType::Triple,
+        ClearType::Tetris,
+        ClearType::TSpinMini,
+        ClearType::TSpinMiniSingle,
+        ClearType::TSpinMiniDouble,
+        ClearType::TSpin,
+        ClearType::TSpinSingle,
+        ClearType::TSpinDouble,
+        ClearType::TSpinTriple,
+    ];
+
+    /// Human readable name, e.g. "T-Spin Double".
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClearType::Single => "Single",
+            ClearType::Double => "Double",
+            ClearType::Triple => "Triple",
+            ClearType::Tetris => "Tetris",
+            ClearType::TSpinMini => "T-Spin Mini",
+            ClearType::TSpinMiniSingle => "T-Spin Mini Single",
+            ClearType::TSpinMiniDouble => "T-Spin Mini Double",
+            ClearType::TSpin => "T-Spin",
+            ClearType::TSpinSingle => "T-Spin Single",
+            ClearType::TSpinDouble => "T-Spin Double",
+            ClearType::TSpinTriple => "T-Spin Triple",
+        }
+    }
+
+    /// Number of lines removed by this clear.
+    pub fn lines(&self) -> usize {
+        match self {
+            ClearType::TSpinMini | ClearType::TSpin => 0,
+            ClearType::Single | ClearType::TSpinMiniSingle | ClearType::TSpinSingle => 1,
+            ClearType::Double | ClearType::TSpinMiniDouble | ClearType::TSpinDouble => 2,
+            ClearType::Triple | ClearType::TSpinTriple => 3,
+            ClearType::Tetris => 4,
+        }
+    }
+
+    /// Tetrises and T-spins that clear lines are "difficult" clears and keep back-to-back chains alive.
+    pub fn is_difficult(&self) -> bool {
+        matches!(
+            self,
+            ClearType::Tetris
+                | ClearType::TSpinMiniSingle
+                | ClearType::TSpinMiniDouble
+                | ClearType::TSpinSingle
+                | ClearType::TSpinDouble
+                | ClearType::TSpinTriple
+        )
+    }
+}
@@ -0,0 +1,14 @@
+/// A key press of a finesse path (see [`crate::core::game::Game::finesse_path`]).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FinesseInput {
+    /// A move key tapped, shifting the piece one column.
+    MoveLeft,
+    MoveRight,
+    /// A move key held until auto-shift takes the piece to the wall or the stack.
+    DasLeft,
+    DasRight,
+    RotateClockwise,
+    RotateCounterClockwise,
+    /// Soft drop held until the piece rests on the stack.
+    SoftDrop,
+}
@@ -0,0 +1,11 @@
+/// An input the player (or a bot) can send to the game.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum GameAction {
+    MoveLeft,
+    MoveRight,
+    RotateClockwise,
+    RotateCounterClockwise,
+    SoftDrop,
+    HardDrop,
+    Hold,
+}
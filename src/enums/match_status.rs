@@ -0,0 +1,9 @@
+/// Outcome of a match between several players.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MatchStatus {
+    Playing,
+    /// The player at this index won.
+    Won(usize),
+    /// Every player topped out on the same frame.
+    Draw,
+}
@@ -2,8 +2,24 @@ pub mod piece_type;
 pub mod direction;
 pub mod rotation_direction;
 pub mod cell_type;
+pub mod mode_status;
+pub mod clear_type;
+pub mod t_spin_type;
+pub mod game_action;
+pub mod stack_visibility;
+pub mod match_status;
+pub mod bot_difficulty;
+pub mod finesse_input;
 
 pub use piece_type::PieceType;
 pub use direction::Direction;
 pub use rotation_direction::RotationDirection;
-pub use cell_type::CellType;
\ No newline at end of file
+pub use cell_type::CellType;
+pub use mode_status::ModeStatus;
+pub use clear_type::ClearType;
+pub use t_spin_type::TSpinType;
+pub use game_action::GameAction;
+pub use stack_visibility::StackVisibility;
+pub use match_status::MatchStatus;
+pub use bot_difficulty::BotDifficulty;
+pub use finesse_input::FinesseInput;
\ No newline at end of file
@@ -0,0 +1,6 @@
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ModeStatus {
+    Playing,
+    Won,
+    Lost,
+}
@@ -1,4 +1,4 @@
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
 pub enum PieceType {
     I,
     J,
@@ -6,5 +6,7 @@ pub enum PieceType {
     O,
     S,
     T,
-    Z
-}
\ No newline at end of file
+    Z,
+    /// A piece defined by a [`crate::core::piece_definition::PieceDefinition`] outside the seven tetrominoes.
+    Custom(String),
+}
@@ -0,0 +1,73 @@
+/// Seconds a locked block stays visible in the default fading stack.
+pub(crate) const DEFAULT_FADE_SECONDS: f64 = 5.0;
+
+/// How the locked blocks of the stack are shown, used by the invisible and fading challenges.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum StackVisibility {
+    Visible,
+    /// Blocks fade out over `seconds` after being locked.
+    Fading { seconds: f64 },
+    /// Blocks disappear as soon as they are locked.
+    Invisible,
+}
+
+impl StackVisibility {
+    /// Human readable name, e.g. "Fading (5s)".
+    pub fn name(&self) -> String {
+        match self {
+            StackVisibility::Visible => "Visible".to_string(),
+            StackVisibility::Fading { seconds } => format!("Fading ({}s)", seconds),
+            StackVisibility::Invisible => "Invisible".to_string(),
+        }
+    }
+
+    /// The next setting in the cycle Visible, Fading, Invisible.
+    pub fn next(&self) -> Self {
+        match self {
+            StackVisibility::Visible => StackVisibility::Fading { seconds: DEFAULT_FADE_SECONDS },
+            StackVisibility::Fading { .. } => StackVisibility::Invisible,
+            StackVisibility::Invisible => StackVisibility::Visible,
+        }
+    }
+
+    /// Opacity of a block locked `age_seconds` ago, from 0 (hidden) to 1 (fully drawn).
+    /// Blocks without a lock time (e.g. set up by a mode) are treated as just locked.
+    pub fn alpha(&self, age_seconds: Option<f64>) -> f32 {
+        match self {
+            StackVisibility::Visible => 1.0,
+            StackVisibility::Invisible => 0.0,
+            StackVisibility::Fading { seconds } => {
+                let age = age_seconds.unwrap_or(0.0);
+                if *seconds <= 0.0 {
+                    0.0
+                } else {
+                    (1.0 - age / seconds).clamp(0.0, 1.0) as f32
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fading_blocks_lose_opacity_with_age() {
+        let fading = StackVisibility::Fading { seconds: 4.0 };
+        assert_eq!(fading.alpha(Some(0.0)), 1.0);
+        assert_eq!(fading.alpha(Some(1.0)), 0.75);
+        assert_eq!(fading.alpha(Some(10.0)), 0.0);
+
+        assert_eq!(StackVisibility::Visible.alpha(Some(10.0)), 1.0);
+        assert_eq!(StackVisibility::Invisible.alpha(Some(0.0)), 0.0);
+    }
+
+    #[test]
+    fn next_cycles_through_every_setting() {
+        let fading = StackVisibility::Visible.next();
+        assert_eq!(fading, StackVisibility::Fading { seconds: DEFAULT_FADE_SECONDS });
+        assert_eq!(fading.next(), StackVisibility::Invisible);
+        assert_eq!(StackVisibility::Invisible.next(), StackVisibility::Visible);
+    }
+}
@@ -0,0 +1,6 @@
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TSpinType {
+    None,
+    Mini,
+    Full,
+}
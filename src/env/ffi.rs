@@ -0,0 +1,174 @@
+//! A C ABI over [`Environment`] for training scripts in other languages, built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//!
+//! Observations are written flattened as [`Environment::observation_vec`] to buffers of
+//! [`tetris_env_observation_size`] values, and shorter buffers are refused. Every function
+//! takes the pointer returned by [`tetris_env_new`], which is freed with [`tetris_env_free`].
+
+use std::slice;
+
+use crate::env::{Action, Environment, Observation};
+
+/// What [`tetris_env_step_key`] and [`tetris_env_step_placement`] report besides the observation.
+#[repr(C)]
+pub struct TetrisStepResult {
+    pub reward: f64,
+    pub done: bool,
+    pub is_invalid_action: bool,
+    pub is_truncated: bool,
+    pub lines_cleared: u32,
+}
+
+/// A new environment with the guideline rules and default rewards.
+#[unsafe(no_mangle)]
+pub extern "C" fn tetris_env_new() -> *mut Environment {
+    Box::into_raw(Box::new(Environment::default()))
+}
+
+/// # Safety
+/// `env` must come from [`tetris_env_new`] and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_free(env: *mut Environment) {
+    if !env.is_null() {
+        drop(unsafe { Box::from_raw(env) });
+    }
+}
+
+/// Number of values written to observation buffers, the same for every step of every
+/// episode.
+///
+/// # Safety
+/// `env` must come from [`tetris_env_new`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_observation_size(env: *const Environment) -> usize {
+    unsafe { &*env }.observation_size()
+}
+
+/// Starts an episode and writes its first observation to `observation`, a buffer of
+/// `observation_len` values. Returns false without starting an episode when the buffer is
+/// shorter than [`tetris_env_observation_size`].
+///
+/// # Safety
+/// `env` must come from [`tetris_env_new`] and `observation` must hold `observation_len`
+/// values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_reset(
+    env: *mut Environment,
+    seed: u64,
+    observation: *mut i32,
+    observation_len: usize,
+) -> bool {
+    let env = unsafe { &mut *env };
+    if observation_len < env.observation_size() {
+        return false;
+    }
+    let first = env.reset(seed);
+    unsafe { write_observation(env, &first, observation, observation_len) }
+}
+
+/// Number of placements of the current piece, the valid indices of [`tetris_env_step_placement`].
+///
+/// # Safety
+/// `env` must come from [`tetris_env_new`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_placement_count(env: *mut Environment) -> usize {
+    unsafe { &mut *env }.placements().len()
+}
+
+/// Plays the low-level action `key_index`, see [`Action::from_key_index`]. Nothing is played
+/// and the action is reported invalid when the observation buffer is too short.
+///
+/// # Safety
+/// As [`tetris_env_reset`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_step_key(
+    env: *mut Environment,
+    key_index: usize,
+    observation: *mut i32,
+    observation_len: usize,
+) -> TetrisStepResult {
+    let env = unsafe { &mut *env };
+    match Action::from_key_index(key_index) {
+        Some(action) => unsafe { step(env, action, observation, observation_len) },
+        None => {
+            unsafe { write_observation(env, &env.observation(), observation, observation_len) };
+            invalid_action(env)
+        }
+    }
+}
+
+/// Makes the placement at `index` of the current piece, as [`tetris_env_step_key`] does for keys.
+///
+/// # Safety
+/// As [`tetris_env_reset`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tetris_env_step_placement(
+    env: *mut Environment,
+    index: usize,
+    observation: *mut i32,
+    observation_len: usize,
+) -> TetrisStepResult {
+    unsafe { step(&mut *env, Action::Placement(index), observation, observation_len) }
+}
+
+unsafe fn step(
+    env: &mut Environment,
+    action: Action,
+    observation: *mut i32,
+    observation_len: usize,
+) -> TetrisStepResult {
+    if observation_len < env.observation_size() {
+        return invalid_action(env);
+    }
+    let (next, reward, done, info) = env.step(action);
+    unsafe { write_observation(env, &next, observation, observation_len) };
+    TetrisStepResult {
+        reward,
+        done,
+        is_invalid_action: info.is_invalid_action,
+        is_truncated: info.is_truncated,
+        lines_cleared: info.lines_cleared as u32,
+    }
+}
+
+fn invalid_action(env: &Environment) -> TetrisStepResult {
+    TetrisStepResult {
+        reward: 0.0,
+        done: env.game.is_game_over,
+        is_invalid_action: true,
+        is_truncated: false,
+        lines_cleared: 0,
+    }
+}
+
+/// Writes the flattened observation to `buffer` unless it is null or shorter than it.
+unsafe fn write_observation(env: &Environment, observation: &Observation, buffer: *mut i32, len: usize) -> bool {
+    let values = env.observation_vec(observation);
+    if buffer.is_null() || len < values.len() {
+        return false;
+    }
+    unsafe { slice::from_raw_parts_mut(buffer, values.len()) }.copy_from_slice(&values);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_observation_buffers_are_refused() {
+        unsafe {
+            let env = tetris_env_new();
+            let size = tetris_env_observation_size(env);
+            let mut buffer = vec![7; size];
+
+            assert!(!tetris_env_reset(env, 1, buffer.as_mut_ptr(), size - 1));
+            assert!(buffer.iter().all(|&value| value == 7));
+            assert!(tetris_env_reset(env, 1, buffer.as_mut_ptr(), size));
+            assert!(tetris_env_step_key(env, 0, buffer.as_mut_ptr(), size - 1).is_invalid_action);
+            assert!(!tetris_env_step_key(env, 0, buffer.as_mut_ptr(), size).is_invalid_action);
+            assert_eq!(tetris_env_observation_size(env), size);
+            tetris_env_free(env);
+        }
+    }
+}
@@ -0,0 +1,416 @@
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+use crate::{
+    bot::heuristic::Features,
+    core::{game::Game, placement::Placement, point_2d::Point2D, rules::RuleSet},
+    enums::{GameAction, PieceType},
+};
+
+/// Keys of the low-level action space, in the order of [`Action::from_key_index`].
+pub const KEY_ACTIONS: [GameAction; 7] = [
+    GameAction::MoveLeft,
+    GameAction::MoveRight,
+    GameAction::RotateClockwise,
+    GameAction::RotateCounterClockwise,
+    GameAction::SoftDrop,
+    GameAction::HardDrop,
+    GameAction::Hold,
+];
+
+/// Frames simulated after a placement action at most, waiting for the next piece to appear.
+const MAX_SPAWN_FRAMES: u32 = 120;
+
+/// What the agent does in one step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Lets the frames of the step pass without touching a key.
+    Wait,
+    /// Presses the key, lets the frames of the step pass and releases it, so a held soft drop
+    /// lasts the whole step.
+    Key(GameAction),
+    /// Makes the placement of [`Environment::placements`] at this index with its inputs,
+    /// then lets frames pass until the next piece appears.
+    Placement(usize),
+}
+
+impl Action {
+    /// The low-level action at `index` of a discrete action space: 0 waits and the next ones
+    /// press the keys of [`KEY_ACTIONS`].
+    pub fn from_key_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Action::Wait),
+            _ => KEY_ACTIONS.get(index - 1).map(|&key| Action::Key(key)),
+        }
+    }
+}
+
+/// What the agent sees of the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// Occupancy of the board, 1 for a filled cell, as [`crate::core::board::Board::get_board_representation`].
+    /// The falling piece is not part of it.
+    pub board: Vec<Vec<usize>>,
+    /// Cells covered by the falling piece, empty while the next one has not appeared.
+    pub piece_cells: Vec<Point2D>,
+    pub current: Option<PieceType>,
+    pub hold: Option<PieceType>,
+    /// The pieces shown in the previews, the next one first.
+    pub queue: Vec<PieceType>,
+}
+
+impl Observation {
+    fn of(game: &Game) -> Self {
+        Self {
+            board: game.board.get_board_representation(),
+            piece_cells: game.current_piece.as_ref().map(|piece| piece.get_blocks_position()).unwrap_or_default(),
+            current: game.current_piece.as_ref().map(|piece| piece.piece_type.clone()),
+            hold: game.hold_piece.as_ref().map(|piece| piece.piece_type.clone()),
+            queue: game.previews().iter().map(|piece| piece.piece_type.clone()).collect(),
+        }
+    }
+
+    /// Flattens the observation into numbers: the board row by row with 2 for the cells of the
+    /// falling piece, then the current piece, the held piece and `previews` queued pieces as
+    /// [`piece_index`]es, -1 standing for no piece. The queue is padded with -1 so the length
+    /// stays the same while fewer pieces are shown, e.g. before the game starts.
+    pub fn to_vec(&self, previews: usize) -> Vec<i32> {
+        let mut values: Vec<i32> = self.board.iter().flatten().map(|&cell| cell as i32).collect();
+        let cols = self.board.first().map_or(0, Vec::len);
+        for cell in &self.piece_cells {
+            if cell.y >= 0
+                && cell.x >= 0
+                && (cell.x as usize) < cols
+                && let Some(value) = values.get_mut(cell.y as usize * cols + cell.x as usize)
+            {
+                *value = 2;
+            }
+        }
+        values.push(self.current.as_ref().map_or(-1, piece_index));
+        values.push(self.hold.as_ref().map_or(-1, piece_index));
+        let queue = self.queue.iter().map(piece_index).chain(std::iter::repeat(-1));
+        values.extend(queue.take(previews));
+        values
+    }
+}
+
+/// Number for a piece type in flattened observations: the tetrominoes I, J, L, O, S, T and Z
+/// from 0 to 6, any other piece 7.
+pub fn piece_index(piece_type: &PieceType) -> i32 {
+    match piece_type {
+        PieceType::I => 0,
+        PieceType::J => 1,
+        PieceType::L => 2,
+        PieceType::O => 3,
+        PieceType::S => 4,
+        PieceType::T => 5,
+        PieceType::Z => 6,
+        PieceType::Custom(_) => 7,
+    }
+}
+
+/// Reward given for each part of what a step changed. Board features are rewarded by how
+/// much they changed, e.g. a negative `holes` weight punishes every hole a step made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardShaping {
+    /// Per line cleared.
+    pub lines: f64,
+    /// Per point scored.
+    pub score: f64,
+    /// Per garbage line sent.
+    pub attack: f64,
+    pub holes: f64,
+    pub aggregate_height: f64,
+    pub bumpiness: f64,
+    /// For every step that does not end the game.
+    pub survival: f64,
+    /// Once, on the step the stack tops out.
+    pub game_over: f64,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        Self {
+            lines: 1.0,
+            score: 0.0,
+            attack: 0.0,
+            holes: 0.0,
+            aggregate_height: 0.0,
+            bumpiness: 0.0,
+            survival: 0.0,
+            game_over: -1.0,
+        }
+    }
+}
+
+impl RewardShaping {
+    fn reward(&self, before: &Game, after: &Game) -> f64 {
+        let was = Features::measure(before, before);
+        let is = Features::measure(before, after);
+        let change = |was: usize, is: usize| is as f64 - was as f64;
+        let mut reward = self.lines * is.lines as f64
+            + self.score * (after.score - before.score) as f64
+            + self.attack * is.attack as f64
+            + self.holes * change(was.holes, is.holes)
+            + self.aggregate_height * change(was.aggregate_height, is.aggregate_height)
+            + self.bumpiness * change(was.bumpiness, is.bumpiness);
+        reward += if after.is_game_over { self.game_over } else { self.survival };
+        reward
+    }
+}
+
+/// Facts about a step beyond the reward.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepInfo {
+    pub lines_cleared: usize,
+    pub pieces_placed: usize,
+    pub score: u64,
+    /// Set when the action could not be taken, e.g. a placement index out of range.
+    /// Nothing happened then.
+    pub is_invalid_action: bool,
+    /// Set when the episode ended on the step limit rather than by topping out.
+    pub is_truncated: bool,
+}
+
+/// A gym-style environment over a [`Game`]: [`Environment::reset`] starts an episode and
+/// [`Environment::step`] plays one action, with either keys or whole placements.
+pub struct Environment {
+    pub game: Game,
+    /// Rules every episode is played with.
+    pub rules: RuleSet,
+    pub reward_shaping: RewardShaping,
+    /// Frames simulated by a [`Action::Wait`] or [`Action::Key`] step.
+    pub frames_per_step: u32,
+    /// Steps after which an episode ends, if any.
+    pub max_steps: Option<usize>,
+    steps: usize,
+    /// Placements of the current piece, computed at most once per step.
+    placements: Option<Vec<Placement>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new(RuleSet::guideline())
+    }
+}
+
+impl Environment {
+    pub fn new(rules: RuleSet) -> Self {
+        // The board has the size of the rules before the first episode too
+        let mut game = Game::new();
+        game.apply_rules(rules.clone());
+        Self {
+            game,
+            rules,
+            reward_shaping: RewardShaping::default(),
+            frames_per_step: 1,
+            max_steps: None,
+            steps: 0,
+            placements: None,
+        }
+    }
+
+    /// Starts a new episode whose piece sequence is seeded with `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new();
+        self.game.apply_rules(self.rules.clone());
+        self.game.set_seed(seed);
+        self.game.start();
+        self.steps = 0;
+        self.placements = None;
+        self.observation()
+    }
+
+    pub fn observation(&self) -> Observation {
+        Observation::of(&self.game)
+    }
+
+    /// Length of flattened observations under the rules: the board cells, the current and held
+    /// pieces and the previews.
+    pub fn observation_size(&self) -> usize {
+        self.rules.rows * self.rules.cols + 2 + self.rules.previews
+    }
+
+    /// The observation flattened as [`Observation::to_vec`], [`Environment::observation_size`]
+    /// values long.
+    pub fn observation_vec(&self, observation: &Observation) -> Vec<i32> {
+        observation.to_vec(self.rules.previews)
+    }
+
+    /// Every final placement of the current piece, the high-level action space.
+    pub fn placements(&mut self) -> &[Placement] {
+        self.placements.get_or_insert_with(|| self.game.placements())
+    }
+
+    /// Plays `action` and returns what the agent sees next, the reward, whether the episode
+    /// is over and more about the step.
+    pub fn step(&mut self, action: Action) -> (Observation, f64, bool, StepInfo) {
+        let before = self.game.clone();
+        let mut info = StepInfo::default();
+        if self.game.is_game_over {
+            info.is_invalid_action = true;
+            return (self.observation(), 0.0, true, info);
+        }
+
+        match action {
+            Action::Wait => self.advance(),
+            Action::Key(key) => {
+                self.game.press(key);
+                self.advance();
+                self.game.release(key);
+            }
+            Action::Placement(index) => match self.placements().get(index) {
+                Some(placement) => {
+                    let inputs = placement.inputs.clone();
+                    for input in inputs {
+                        self.game.apply_placement_step(input);
+                    }
+                    for _ in 0..MAX_SPAWN_FRAMES {
+                        if self.game.current_piece.is_some() || self.game.is_game_over {
+                            break;
+                        }
+                        self.game.step_frame();
+                    }
+                }
+                None => info.is_invalid_action = true,
+            },
+        }
+        if !info.is_invalid_action {
+            self.steps += 1;
+            self.placements = None;
+        }
+
+        let reward = self.reward_shaping.reward(&before, &self.game);
+        info.lines_cleared = self.game.lines_cleared - before.lines_cleared;
+        info.pieces_placed = self.game.pieces_placed - before.pieces_placed;
+        info.score = self.game.score - before.score;
+        info.is_truncated = !self.game.is_game_over && self.max_steps.is_some_and(|max| self.steps >= max);
+        let done = self.game.is_game_over || info.is_truncated;
+        (self.observation(), reward, done, info)
+    }
+
+    fn advance(&mut self) {
+        for _ in 0..self.frames_per_step {
+            self.game.step_frame();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::constants::{COLS, ROWS},
+        enums::CellType,
+    };
+
+    #[test]
+    fn episodes_with_the_same_seed_start_the_same() {
+        let mut env = Environment::default();
+        let first = env.reset(7);
+        env.step(Action::Placement(0));
+
+        assert_eq!(env.reset(7), first);
+        assert_eq!(first.board.len(), ROWS);
+        assert_eq!(first.piece_cells.len(), 4);
+        assert_eq!(first.queue.len(), 5);
+        // Board cells, then the current piece, the empty hold and five previews
+        assert_eq!(env.observation_vec(&first).len(), ROWS * COLS + 2 + 5);
+        assert_eq!(first.to_vec(5)[..ROWS * COLS].iter().filter(|&&value| value == 2).count(), 4);
+    }
+
+    #[test]
+    fn observations_have_the_same_size_before_and_after_reset() {
+        let rules = RuleSet {
+            rows: 22,
+            cols: 12,
+            previews: 3,
+            ..RuleSet::guideline()
+        };
+        let mut env = Environment::new(rules);
+        let before = env.observation_vec(&env.observation());
+        let after = env.reset(4);
+
+        assert_eq!(env.observation_size(), 22 * 12 + 2 + 3);
+        assert_eq!(before.len(), env.observation_size());
+        assert_eq!(env.observation_vec(&after).len(), env.observation_size());
+        // No piece is shown before the game starts
+        assert_eq!(before[22 * 12..], [-1; 5]);
+    }
+
+    #[test]
+    fn placement_actions_lock_a_piece_and_reward_the_lines() {
+        let mut env = Environment::default();
+        env.reset(3);
+        for col in 0..COLS - 1 {
+            env.game.board.set_cell(ROWS - 1, col, CellType::Filled(BLUE));
+        }
+        env.game.current_piece = env.game.spawn_piece_of_type(&PieceType::I);
+
+        let index = env
+            .placements()
+            .iter()
+            .position(|placement| placement.result.lines_cleared == 1)
+            .expect("an upright I fills the last column");
+        let (observation, reward, done, info) = env.step(Action::Placement(index));
+
+        assert_eq!(reward, 1.0);
+        assert!(!done);
+        assert_eq!(info.lines_cleared, 1);
+        assert_eq!(info.pieces_placed, 1);
+        assert!(observation.current.is_some());
+    }
+
+    #[test]
+    fn key_actions_move_the_piece_and_bad_placements_do_nothing() {
+        let mut env = Environment::default();
+        let start = env.reset(5);
+
+        let (moved, ..) = env.step(Action::from_key_index(1).unwrap());
+        assert!(moved.piece_cells.iter().zip(&start.piece_cells).all(|(after, before)| after.x == before.x - 1));
+
+        let (_, reward, done, info) = env.step(Action::Placement(1000));
+        assert!(info.is_invalid_action);
+        assert_eq!((reward, done), (0.0, false));
+        assert_eq!(Action::from_key_index(KEY_ACTIONS.len() + 1), None);
+    }
+
+    #[test]
+    fn topping_out_ends_the_episode_with_the_game_over_reward() {
+        let mut env = Environment {
+            reward_shaping: RewardShaping {
+                survival: 0.01,
+                ..RewardShaping::default()
+            },
+            ..Environment::default()
+        };
+        env.reset(1);
+        // A stack up to the second row, except where nothing could clear it
+        for row in 2..ROWS {
+            for col in 1..COLS {
+                env.game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+
+        let (_, reward, done, info) = env.step(Action::Key(GameAction::HardDrop));
+
+        assert!(done);
+        assert!(!info.is_truncated);
+        assert_eq!(reward, -1.0);
+    }
+
+    #[test]
+    fn episodes_can_be_cut_after_a_number_of_steps() {
+        let mut env = Environment {
+            max_steps: Some(2),
+            ..Environment::default()
+        };
+        env.reset(2);
+
+        assert!(!env.step(Action::Wait).2);
+        let (_, _, done, info) = env.step(Action::Wait);
+        assert!(done && info.is_truncated);
+    }
+}
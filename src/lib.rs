@@ -0,0 +1,23 @@
+pub mod bot;
+
+pub mod core {
+    pub mod attack;
+    pub mod board;
+    pub mod constants;
+    pub mod finesse;
+    pub mod fumen;
+    pub mod game;
+    pub mod piece;
+    pub mod piece_definition;
+    pub mod placement;
+    pub mod point_2d;
+    pub mod randomizer;
+    pub mod rule_file;
+    pub mod rules;
+    pub mod scoring;
+    pub mod timings;
+}
+
+pub mod enums;
+pub mod env;
+pub mod modes;
@@ -1,36 +1,132 @@
 use macroquad::prelude::*;
 
-use crate::{
+use tetris_rust::{
+    bot::{
+        Bot,
+        perfect_clear::{BackgroundSolver, PerfectClearSolver},
+        tbp::TbpBot,
+    },
     core::{
+        attack::HolePattern,
         board::Board,
-        constants::{BOARD_WIDTH, BOARD_X, BOARD_Y, BORDER_THICKNESS, CELL_SIZE},
+        fumen::{self, FumenPage},
         game::Game,
+        piece_definition::PieceSet,
+        rules::RuleSet,
+    },
+    enums::{BotDifficulty, GameAction, MatchStatus, ModeStatus, StackVisibility},
+    modes::{
+        self, GameMode,
+        classic::CLASSIC_MAX_START_LEVEL,
+        coop::{COOP_COLS, Coop},
+        marathon::Marathon,
+        puzzle,
+        versus::Versus,
+        zen::{ZEN_DEFAULT_UNDO_LIMIT, Zen},
     },
-    enums::{CellType, PieceType, RotationDirection},
-    ui::{audio_player::AudioPlayer, render_engine},
 };
 
-mod core {
-    pub mod board;
-    pub mod constants;
-    pub mod game;
-    pub mod piece;
-    pub mod point_2d;
-}
+use crate::ui::{
+    audio_player::AudioPlayer,
+    render_engine::{self, Viewport},
+};
 
 mod ui {
     pub mod audio_player;
     pub mod render_engine;
 }
 
-mod enums;
+#[derive(PartialEq)]
+enum Screen {
+    Menu,
+    Playing,
+    Results,
+    Versus,
+    VersusResults,
+    Coop,
+    CoopResults,
+}
+
+/// Menu entry of the local two-player match, listed after the single player modes.
+const VERSUS_MENU_ENTRY: &str = "2P Versus";
+
+/// Menu entry of the match against the built-in bot.
+const VERSUS_BOT_MENU_ENTRY: &str = "Versus Bot";
+
+/// Menu entry of the two-player game on a shared wide board.
+const COOP_MENU_ENTRY: &str = "2P Co-op";
+
+/// Menu entry of endless Marathon played by the built-in bot.
+const WATCH_BOT_MENU_ENTRY: &str = "Watch Bot";
 
-#[macroquad::main("Tetris")]
+fn window_conf() -> Conf {
+    // Wide enough for two boards side by side
+    Conf {
+        window_title: "Tetris".to_string(),
+        window_width: 1100,
+        window_height: 700,
+        ..Default::default()
+    }
+}
+
+#[macroquad::main(window_conf)]
 async fn main() {
-    let mut game = Game::new();
-    let mut last_update = get_time();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // `--check-puzzles` reports which puzzle files can be solved, then quits
+    if args.iter().any(|arg| arg == "--check-puzzles") {
+        for line in puzzle::check_puzzle_pack() {
+            println!("{}", line);
+        }
+        return;
+    }
+    // An optional first argument selects the piece set, e.g. `pentomino` or `mixed`
+    let piece_set = args
+        .first()
+        .and_then(|name| PieceSet::by_name(name))
+        .unwrap_or_else(PieceSet::tetrominoes);
+    // `--tbp <command>` has Watch Bot launch an external bot speaking the Tetris Bot Protocol
+    let tbp_command = args.iter().position(|arg| arg == "--tbp").and_then(|index| args.get(index + 1));
+    // `--fumen <data>` has Zen start from the board of the first page of a fumen
+    let fumen_board: Option<Board> = args
+        .iter()
+        .position(|arg| arg == "--fumen")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|data| {
+            fumen::decode(data)
+                .inspect_err(|error| eprintln!("Could not load the fumen: {}", error))
+                .ok()
+        })
+        .and_then(|pages| pages.into_iter().next())
+        .map(|page| page.board);
+    let mut mode_names: Vec<String> = modes::mode_names();
+    mode_names.push(VERSUS_MENU_ENTRY.to_string());
+    mode_names.push(VERSUS_BOT_MENU_ENTRY.to_string());
+    mode_names.push(COOP_MENU_ENTRY.to_string());
+    mode_names.push(WATCH_BOT_MENU_ENTRY.to_string());
 
-    game.start();
+    let mut screen = Screen::Menu;
+    let mut selected_mode = 0;
+    let mut visibility = StackVisibility::Visible;
+    let mut is_big = false;
+    let mut hole_pattern = HolePattern::PerAttack;
+    let mut bot_difficulty = BotDifficulty::Medium;
+    // Built-in presets first, then the rule files of the presets directory
+    let rule_sets = RuleSet::load_all();
+    let mut selected_rules = 0;
+    let mut classic_start_level = 0;
+    let mut shows_perfect_clear_hint = false;
+    let mut mode: Box<dyn GameMode> = modes::create_mode(selected_mode);
+    let mut game = Game::with_piece_set(piece_set.clone());
+    // Plays the single player game instead of the keyboard when watching the bot
+    let mut demo_bot: Option<Bot> = None;
+    let mut tbp_bot: Option<TbpBot> = None;
+    // Outlines where the current piece goes on the way to a perfect clear
+    let mut perfect_clear_hint: Option<BackgroundSolver> = None;
+    // Line shown in the HUD after exporting a fumen, until the given time
+    let mut fumen_notice: Option<(String, f64)> = None;
+    let mut versus = Versus::new(0, 0, |_| {});
+    let mut coop = Coop::new(0, |_| {});
+    let mut last_frame = get_time();
 
     let player = AudioPlayer::new();
     player.play_loop();
@@ -38,56 +134,365 @@ async fn main() {
     loop {
         clear_background(BLACK);
 
-        // Handle user input
-        if is_key_released(KeyCode::Escape){
-            break;
-        }
+        // High resolution frame time so timed modes are accurate to the millisecond
+        let now = get_time();
+        let delta_seconds = now - last_frame;
+        last_frame = now;
 
-        if is_key_released(KeyCode::Left) || is_key_released(KeyCode::A) {
-            game.move_piece_left();
-        }
+        match screen {
+            Screen::Menu => {
+                if is_key_released(KeyCode::Escape) {
+                    break;
+                }
 
-        if is_key_released(KeyCode::Right) || is_key_released(KeyCode::D) {
-            game.move_piece_right();
-        }
+                if is_key_released(KeyCode::Up) && selected_mode > 0 {
+                    selected_mode -= 1;
+                }
 
-        if is_key_released(KeyCode::Up) || is_key_released(KeyCode::W) || is_key_released(KeyCode::X)
-        {
-            game.rotate_piece(RotationDirection::Clockwise);
-        }
+                if is_key_released(KeyCode::Down) && selected_mode + 1 < mode_names.len() {
+                    selected_mode += 1;
+                }
 
-        if is_key_released(KeyCode::Q) || is_key_released(KeyCode::Z)
-        {
-            game.rotate_piece(RotationDirection::CounterClockwise);
-        }
+                if is_key_released(KeyCode::Left) || is_key_released(KeyCode::Right) {
+                    visibility = visibility.next();
+                }
 
-        if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
-            game.start_soft_drop();
-        }
+                if is_key_released(KeyCode::B) {
+                    is_big = !is_big;
+                }
+
+                if is_key_released(KeyCode::G) {
+                    hole_pattern = hole_pattern.next();
+                }
+
+                if is_key_released(KeyCode::D) {
+                    bot_difficulty = bot_difficulty.next();
+                }
+
+                if is_key_released(KeyCode::R) {
+                    selected_rules = (selected_rules + 1) % rule_sets.len();
+                }
+
+                if is_key_released(KeyCode::L) {
+                    classic_start_level = (classic_start_level + 1) % (CLASSIC_MAX_START_LEVEL + 1);
+                }
+
+                if is_key_released(KeyCode::P) {
+                    shows_perfect_clear_hint = !shows_perfect_clear_hint;
+                }
+
+                let piece_scale = if is_big { 2 } else { 1 };
+                let rules = &rule_sets[selected_rules];
+                let prepare_versus = |game: &mut Game| {
+                    game.apply_rules(rules.clone());
+                    game.piece_set = piece_set.clone();
+                    game.piece_scale = piece_scale;
+                    game.garbage_settings.hole_pattern = hole_pattern;
+                };
+                if is_key_released(KeyCode::Enter) && mode_names[selected_mode] == VERSUS_MENU_ENTRY {
+                    versus = Versus::new(2, ::rand::random(), prepare_versus);
+                    screen = Screen::Versus;
+                } else if is_key_released(KeyCode::Enter) && mode_names[selected_mode] == VERSUS_BOT_MENU_ENTRY {
+                    versus = Versus::against_bot(bot_difficulty, ::rand::random(), prepare_versus);
+                    screen = Screen::Versus;
+                } else if is_key_released(KeyCode::Enter) && mode_names[selected_mode] == COOP_MENU_ENTRY {
+                    coop = Coop::new(::rand::random(), |game| {
+                        game.apply_rules(rules.clone());
+                        game.piece_set = piece_set.clone();
+                        game.piece_scale = piece_scale;
+                    });
+                    screen = Screen::Coop;
+                } else if is_key_released(KeyCode::Enter) {
+                    let is_watching_bot = mode_names[selected_mode] == WATCH_BOT_MENU_ENTRY;
+                    let zen = Zen::new(ZEN_DEFAULT_UNDO_LIMIT);
+                    mode = if is_watching_bot {
+                        Box::new(Marathon::endless())
+                    } else if let Some(board) = fumen_board.as_ref().filter(|_| mode_names[selected_mode] == zen.name()) {
+                        Box::new(zen.with_starting_board(board.clone()))
+                    } else {
+                        modes::create_mode(selected_mode)
+                    };
+                    tbp_bot = match tbp_command.filter(|_| is_watching_bot) {
+                        Some(command) => TbpBot::launch(command, bot_difficulty.pieces_per_second())
+                            .inspect_err(|error| eprintln!("Could not start the TBP bot: {}", error))
+                            .ok(),
+                        None => None,
+                    };
+                    // The built-in bot stands in when no external bot could be started
+                    demo_bot = (is_watching_bot && tbp_bot.is_none()).then(|| Bot::new(bot_difficulty, ::rand::random()));
+                    game = Game::with_piece_set(piece_set.clone());
+                    game.apply_rules(rules.clone());
+                    game.piece_scale = piece_scale;
+                    game.tracks_finesse = !is_watching_bot;
+                    perfect_clear_hint = (shows_perfect_clear_hint && !is_watching_bot)
+                        .then(|| BackgroundSolver::new(PerfectClearSolver::default()));
+                    mode.set_start_level(classic_start_level);
+                    mode.setup(&mut game);
+                    screen = Screen::Playing;
+                }
+
+                let settings = [
+                    format!("Stack: {} (Left/Right)", visibility.name()),
+                    format!("Big: {} (B)", if is_big { "On" } else { "Off" }),
+                    format!("Versus garbage holes: {} (G)", hole_pattern.name()),
+                    format!("Bot: {} (D)", bot_difficulty.name()),
+                    format!("Rules: {} (R)", rules.name),
+                    format!("Classic start level: {} (L)", classic_start_level),
+                    format!("Perfect clear hint: {} (P)", if shows_perfect_clear_hint { "On" } else { "Off" }),
+                ];
+                render_engine::draw_menu("Tetris", &mode_names, selected_mode, &settings);
+            }
+            Screen::Playing => {
+                if is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
+
+                if let Some(bot) = &mut tbp_bot {
+                    if let Err(error) = bot.update(&mut game, delta_seconds) {
+                        eprintln!("The TBP bot stopped playing: {}", error);
+                        tbp_bot = None;
+                    }
+                } else if let Some(bot) = &mut demo_bot {
+                    bot.update(&mut game, delta_seconds);
+                } else if handle_game_input(&mut game, &KEY_BINDINGS) {
+                    mode.on_input(&mut game);
+                }
+
+                if is_key_pressed(KeyCode::U) {
+                    mode.undo(&mut game);
+                }
+
+                if is_key_pressed(KeyCode::Y) {
+                    mode.redo(&mut game);
+                }
+
+                // Shares the current moment of the game as a fumen, copied to the clipboard
+                if is_key_pressed(KeyCode::F) {
+                    let notice = match fumen::encode(&[FumenPage::from_game(&game)]) {
+                        Ok(data) => {
+                            miniquad::window::clipboard_set(&data);
+                            "Fumen copied to the clipboard".to_string()
+                        }
+                        Err(error) => format!("Could not export the fumen: {}", error),
+                    };
+                    fumen_notice = Some((notice, now + 3.0));
+                }
+
+                game.update(delta_seconds);
+                mode.update(&mut game, delta_seconds);
+                if mode.take_warning_cue() {
+                    player.play_warning_beep();
+                }
+                if mode.status(&game) != ModeStatus::Playing {
+                    screen = Screen::Results;
+                }
+
+                let mut hud = mode.hud(&game);
+                if game.tracks_finesse {
+                    hud.push(format!("Finesse faults: {}", game.finesse_faults));
+                }
+                if let Some(hint) = &mut perfect_clear_hint {
+                    hint.update(&game);
+                    hud.push(perfect_clear_hint_text(hint));
+                }
+                if let Some((notice, _)) = fumen_notice.as_ref().filter(|(_, until)| now < *until) {
+                    hud.push(notice.clone());
+                }
+                draw_game(&game, &hud, visibility, Viewport::MAIN);
+                if let Some(solution) = perfect_clear_hint.as_ref().and_then(|hint| hint.solutions.as_ref()?.first()) {
+                    render_engine::draw_hint(&solution.placements[0].cells, Viewport::MAIN);
+                }
+                if mode.is_in_warning(&game) {
+                    render_engine::draw_warning_border(now, game.board.rows, game.board.cols, Viewport::MAIN);
+                }
+            }
+            Screen::Results => {
+                if is_key_released(KeyCode::Enter) || is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
 
-        if is_key_released(KeyCode::S) || is_key_released(KeyCode::Down) {
-            game.stop_soft_drop();
+                // The whole stack is revealed once the game is over
+                draw_game(&game, &mode.hud(&game), StackVisibility::Visible, Viewport::MAIN);
+                let title = match mode.status(&game) {
+                    ModeStatus::Won => format!("{} - Complete!", mode.name()),
+                    _ => format!("{} - Game Over", mode.name()),
+                };
+                render_engine::draw_results(&title, &mode.results(&game));
+            }
+            Screen::Versus => {
+                if is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
+
+                // Against a bot the human player can use either side of the keyboard
+                let bindings = if versus.bots.iter().any(Option::is_some) {
+                    [&KEY_BINDINGS, &PLAYER_TWO_KEY_BINDINGS]
+                } else {
+                    [&PLAYER_ONE_KEY_BINDINGS, &PLAYER_TWO_KEY_BINDINGS]
+                };
+                for ((game, bot), keys) in versus.players.iter_mut().zip(&versus.bots).zip(bindings) {
+                    if bot.is_none() {
+                        handle_game_input(game, keys);
+                    }
+                }
+
+                versus.update(delta_seconds);
+                if versus.status() != MatchStatus::Playing {
+                    screen = Screen::VersusResults;
+                }
+
+                draw_versus(&versus, visibility);
+            }
+            Screen::VersusResults => {
+                if is_key_released(KeyCode::Enter) || is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
+
+                draw_versus(&versus, StackVisibility::Visible);
+                render_engine::draw_results(&versus.title(), &versus.results());
+            }
+            Screen::Coop => {
+                if is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
+
+                let bindings = [&PLAYER_ONE_KEY_BINDINGS, &PLAYER_TWO_KEY_BINDINGS];
+                for (player, keys) in bindings.into_iter().enumerate() {
+                    coop.play(player, |game| handle_game_input(game, keys));
+                }
+
+                coop.update(delta_seconds);
+                if coop.is_over() {
+                    screen = Screen::CoopResults;
+                }
+
+                draw_coop(&coop, visibility);
+            }
+            Screen::CoopResults => {
+                if is_key_released(KeyCode::Enter) || is_key_released(KeyCode::Escape) {
+                    screen = Screen::Menu;
+                }
+
+                draw_coop(&coop, StackVisibility::Visible);
+                render_engine::draw_results("Co-op - Game Over", &coop.results());
+            }
         }
 
-        if is_key_released(KeyCode::Space) {
-            game.hard_drop();
+        next_frame().await;
+    }
+}
+
+/// Keys bound to each game action.
+type KeyBindings = [(GameAction, &'static [KeyCode]); 7];
+
+/// Keys of a single player game, both sides of the keyboard work.
+const KEY_BINDINGS: KeyBindings = [
+    (GameAction::MoveLeft, &[KeyCode::Left, KeyCode::A]),
+    (GameAction::MoveRight, &[KeyCode::Right, KeyCode::D]),
+    (GameAction::RotateClockwise, &[KeyCode::Up, KeyCode::W, KeyCode::X]),
+    (GameAction::RotateCounterClockwise, &[KeyCode::Q, KeyCode::Z]),
+    (GameAction::SoftDrop, &[KeyCode::S, KeyCode::Down]),
+    (GameAction::HardDrop, &[KeyCode::Space]),
+    (GameAction::Hold, &[KeyCode::C, KeyCode::LeftShift]),
+];
+
+/// Keys of the player on the left in versus.
+const PLAYER_ONE_KEY_BINDINGS: KeyBindings = [
+    (GameAction::MoveLeft, &[KeyCode::A]),
+    (GameAction::MoveRight, &[KeyCode::D]),
+    (GameAction::RotateClockwise, &[KeyCode::W]),
+    (GameAction::RotateCounterClockwise, &[KeyCode::Q]),
+    (GameAction::SoftDrop, &[KeyCode::S]),
+    (GameAction::HardDrop, &[KeyCode::Space]),
+    (GameAction::Hold, &[KeyCode::LeftShift]),
+];
+
+/// Keys of the player on the right in versus.
+const PLAYER_TWO_KEY_BINDINGS: KeyBindings = [
+    (GameAction::MoveLeft, &[KeyCode::Left]),
+    (GameAction::MoveRight, &[KeyCode::Right]),
+    (GameAction::RotateClockwise, &[KeyCode::Up]),
+    (GameAction::RotateCounterClockwise, &[KeyCode::Slash]),
+    (GameAction::SoftDrop, &[KeyCode::Down]),
+    (GameAction::HardDrop, &[KeyCode::Enter]),
+    (GameAction::Hold, &[KeyCode::RightShift]),
+];
+
+/// Sends pressed and released keys to the game. Returns `true` if any game key was pressed.
+fn handle_game_input(game: &mut Game, bindings: &KeyBindings) -> bool {
+    let mut has_input = false;
+
+    for &(action, keys) in bindings {
+        if keys.iter().any(|key| is_key_pressed(*key)) {
+            game.press(action);
+            has_input = true;
         }
 
-        // Update piece position every interval
-        let now = get_time();
-        if now - last_update > game.fall_speed_seconds_per_line {
-            game.do_on_each_loop();
-            last_update = now;
+        if keys.iter().any(|key| is_key_released(*key)) {
+            game.release(action);
         }
+    }
 
-        render_engine::draw_board(&game.board);
+    has_input
+}
 
-        if let Some(ref mut game_piece) = game.current_piece {
-            render_engine::draw_piece(&game_piece);
+/// HUD line of the perfect clear hint: the chance of the best solution, once solved.
+fn perfect_clear_hint_text(hint: &BackgroundSolver) -> String {
+    match hint.solutions.as_ref().map(|solutions| solutions.first()) {
+        None => "Perfect clear: searching...".to_string(),
+        Some(None) => "Perfect clear: none found".to_string(),
+        Some(Some(solution)) => {
+            let holds = solution.placements[0].inputs.first() == Some(&GameAction::Hold);
+            format!(
+                "Perfect clear: {:.0}% in {} lines{}",
+                solution.probability * 100.0,
+                solution.lines,
+                if holds { " (hold)" } else { "" }
+            )
         }
+    }
+}
 
-        render_engine::draw_next_piece_section(&game.next_piece);
+fn draw_game(game: &Game, hud: &[String], visibility: StackVisibility, viewport: Viewport) {
+    render_engine::draw_board(&game.board, visibility, viewport);
 
-        next_frame().await;
+    if let Some(ref game_piece) = game.current_piece {
+        render_engine::draw_piece(game_piece, viewport);
+    }
+
+    // Boards wider than the standard one push the side panel to the right
+    let panel = viewport.side_panel(game.board.cols, 0);
+    render_engine::draw_next_piece_section(&game.previews(), panel);
+    if game.rules.has_hold {
+        render_engine::draw_hold_piece_section(&game.hold_piece, panel);
+    }
+    render_engine::draw_hud(hud, panel);
+    render_engine::draw_garbage_meter(
+        game.incoming_garbage.total(),
+        game.incoming_garbage.ready(),
+        game.board.rows,
+        viewport,
+    );
+}
+
+fn draw_versus(versus: &Versus, visibility: StackVisibility) {
+    for (index, game) in versus.players.iter().enumerate() {
+        draw_game(game, &versus.hud(index), visibility, Viewport::for_player(index, game.board.cols));
+    }
+}
+
+fn draw_coop(coop: &Coop, visibility: StackVisibility) {
+    render_engine::draw_board(&coop.board, visibility, Viewport::MAIN);
+    for (index, game) in coop.players.iter().enumerate() {
+        if let Some(ref game_piece) = game.current_piece {
+            render_engine::draw_piece(game_piece, Viewport::MAIN);
+        }
+
+        let panel = Viewport::MAIN.side_panel(COOP_COLS, index);
+        render_engine::draw_next_piece_section(&game.previews(), panel);
+        if game.rules.has_hold {
+            render_engine::draw_hold_piece_section(&game.hold_piece, panel);
+        }
+        render_engine::draw_hud(&coop.hud(index), panel);
     }
 }
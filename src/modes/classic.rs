@@ -0,0 +1,162 @@
+use crate::{
+    core::{game::Game, rules::RuleSet, scoring::nes_transition_lines},
+    enums::{ClearType, ModeStatus},
+    modes::{GameMode, format_time},
+};
+
+/// Highest level that can be picked to start Classic, 19 as on NES with A held.
+pub const CLASSIC_MAX_START_LEVEL: usize = 19;
+
+/// Classic: endless play on NES rules from the chosen start level, until the stack tops out.
+pub struct Classic {
+    pub start_level: usize,
+    pub elapsed_seconds: f64,
+}
+
+impl Classic {
+    pub fn new(start_level: usize) -> Self {
+        Self {
+            start_level: start_level.min(CLASSIC_MAX_START_LEVEL),
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    /// Share of the cleared lines that were cleared by tetrises, in percent.
+    fn tetris_rate(game: &Game) -> f64 {
+        if game.lines_cleared == 0 {
+            return 0.0;
+        }
+        let tetrises = game.clear_stats.get(&ClearType::Tetris).map_or(0, |stats| stats.count);
+        (tetrises * 4) as f64 * 100.0 / game.lines_cleared as f64
+    }
+}
+
+impl GameMode for Classic {
+    fn name(&self) -> String {
+        "Classic (NES)".to_string()
+    }
+
+    fn set_start_level(&mut self, level: usize) {
+        self.start_level = level.min(CLASSIC_MAX_START_LEVEL);
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        game.apply_rules(RuleSet::nes());
+        game.set_start_level(self.start_level);
+        game.start();
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) == ModeStatus::Playing {
+            self.elapsed_seconds += delta_seconds;
+        }
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if game.is_game_over { ModeStatus::Lost } else { ModeStatus::Playing }
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        let mut hud = vec![
+            format!("Score: {}", game.score),
+            format!("Level: {:02}", game.level),
+            format!("Lines: {:03}", game.lines_cleared),
+        ];
+        // Until the first level up, show how far away the transition is
+        if game.level == self.start_level {
+            let lines_left = nes_transition_lines(self.start_level).saturating_sub(game.lines_cleared);
+            hud.push(format!("Transition in: {}", lines_left));
+        }
+        hud.push(format!("Tetris rate: {:.0}%", Self::tetris_rate(game)));
+        hud
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        vec![
+            ("Score".to_string(), game.score.to_string()),
+            ("Start level".to_string(), self.start_level.to_string()),
+            ("Level".to_string(), game.level.to_string()),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Tetris rate".to_string(), format!("{:.0}%", Self::tetris_rate(game))),
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::{
+            constants::{COLS, ROWS},
+            piece::Piece,
+            point_2d::Point2D,
+        },
+        enums::{CellType, GameAction, PieceType, RotationDirection},
+    };
+
+    #[test]
+    fn classic_starts_on_nes_rules_at_the_chosen_level() {
+        let mut game = Game::new();
+        let mut classic = Classic::new(0);
+        classic.set_start_level(18);
+        classic.setup(&mut game);
+
+        assert_eq!(game.rules.name, "NES");
+        assert_eq!(game.level, 18);
+        assert_eq!(game.timings.gravity, 1.0 / 3.0);
+        assert_eq!(game.previews().len(), 1);
+    }
+
+    #[test]
+    fn a_tetris_scores_1200_times_the_level_plus_one() {
+        let mut game = Game::new();
+        let mut classic = Classic::new(5);
+        classic.setup(&mut game);
+        for row in ROWS - 4..ROWS {
+            for col in 0..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(ROWS as isize - 3, COLS as isize - 1)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
+
+        // No hard drop on NES: the input does nothing and the piece locks once gravity next
+        // fails to drop it
+        game.press(GameAction::HardDrop);
+        game.step_frame();
+        assert_eq!(game.pieces_placed, 0);
+        while game.pieces_placed == 0 {
+            game.step_frame();
+        }
+
+        assert_eq!(game.lines_cleared, 4);
+        assert_eq!(game.score, 1200 * 6);
+        assert_eq!(Classic::tetris_rate(&game), 100.0);
+    }
+
+    #[test]
+    fn a_landed_piece_can_still_shift_until_gravity_drops_it_again() {
+        let mut game = Game::new();
+        let mut classic = Classic::new(0);
+        classic.setup(&mut game);
+        game.current_piece = Piece::new(&PieceType::T, Point2D::new(ROWS as isize - 1, 4));
+
+        // Level 0 drops a row every 48 frames
+        for _ in 0..30 {
+            game.step_frame();
+        }
+        game.press(GameAction::MoveRight);
+        game.release(GameAction::MoveRight);
+
+        assert_eq!(game.pieces_placed, 0);
+        assert_eq!(game.current_piece.as_ref().unwrap().position.x, 5);
+        for _ in 0..18 {
+            game.step_frame();
+        }
+        assert_eq!(game.pieces_placed, 1);
+    }
+}
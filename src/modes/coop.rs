@@ -0,0 +1,212 @@
+use crate::{
+    core::{
+        board::Board,
+        constants::{FRAMES_PER_SECOND, ROWS},
+        game::{Game, MAX_FRAMES_PER_UPDATE},
+        point_2d::Point2D,
+    },
+    modes::{format_time, pieces_per_second},
+};
+
+/// Columns of the board shared in co-op, twice the usual width.
+pub const COOP_COLS: usize = 20;
+
+/// Number of players sharing the co-op board.
+pub(crate) const COOP_PLAYERS: usize = 2;
+
+/// Co-op: two players share one wide board, each with their own active piece, queue and hold.
+/// Pieces collide with the stack and with each other, and the game ends when either player tops out.
+pub struct Coop {
+    /// The shared board, lent to each player's [`Game`] while it runs (see [`Coop::play`]).
+    pub board: Board,
+    pub players: Vec<Game>,
+    pub elapsed_seconds: f64,
+    /// Seconds not yet simulated because they do not add up to a whole frame.
+    unsimulated_seconds: f64,
+}
+
+impl Coop {
+    /// Creates a game whose players get their own piece sequences derived from `seed`.
+    /// `prepare` is applied to each game before it starts.
+    pub fn new(seed: u64, prepare: impl Fn(&mut Game)) -> Self {
+        let players = (0..COOP_PLAYERS)
+            .map(|index| {
+                let mut game = Game::new();
+                game.set_seed(seed.wrapping_add(index as u64));
+                prepare(&mut game);
+                // Each player's pieces spawn over their own half of the board
+                game.spawn_column_offset = (index * COOP_COLS / COOP_PLAYERS) as isize;
+                game.board = Board::with_size(ROWS, COOP_COLS);
+                game
+            })
+            .collect();
+
+        let mut coop = Self {
+            board: Board::with_size(ROWS, COOP_COLS),
+            players,
+            elapsed_seconds: 0.0,
+            unsimulated_seconds: 0.0,
+        };
+        for player in 0..COOP_PLAYERS {
+            coop.play(player, Game::start);
+        }
+        coop
+    }
+
+    /// Runs `action` on the game of the given player with the shared board, where the pieces
+    /// of the other players block its piece like filled cells.
+    pub fn play<T>(&mut self, player: usize, action: impl FnOnce(&mut Game) -> T) -> T {
+        self.board.blocked_cells = self.pieces_of_others(player);
+        std::mem::swap(&mut self.board, &mut self.players[player].board);
+        let result = action(&mut self.players[player]);
+        std::mem::swap(&mut self.board, &mut self.players[player].board);
+        self.board.blocked_cells.clear();
+
+        // Rows cleared under the other pieces can bring the stack down into them
+        for game in &mut self.players {
+            if let Some(piece) = &mut game.current_piece {
+                self.board.lift_out_of_stack(piece);
+            }
+        }
+        result
+    }
+
+    fn pieces_of_others(&self, player: usize) -> Vec<Point2D> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != player)
+            .filter_map(|(_, game)| game.current_piece.as_ref())
+            .flat_map(|piece| piece.get_blocks_position())
+            .collect()
+    }
+
+    /// Advances both players by `delta_seconds`, one frame at a time so their pieces
+    /// move in turn.
+    pub fn update(&mut self, delta_seconds: f64) {
+        if self.is_over() {
+            return;
+        }
+
+        self.elapsed_seconds += delta_seconds;
+        self.unsimulated_seconds += delta_seconds;
+        let frame_seconds = 1.0 / FRAMES_PER_SECOND;
+        let mut frames = 0;
+        while self.unsimulated_seconds >= frame_seconds {
+            self.unsimulated_seconds -= frame_seconds;
+            frames += 1;
+            if frames > MAX_FRAMES_PER_UPDATE {
+                self.unsimulated_seconds = 0.0;
+                break;
+            }
+            self.step_frame();
+        }
+    }
+
+    /// Simulates one frame of every player, the shared clock advancing once.
+    pub fn step_frame(&mut self) {
+        let clock_seconds = self.board.clock_seconds;
+        for player in 0..self.players.len() {
+            self.board.clock_seconds = clock_seconds;
+            self.play(player, Game::step_frame);
+        }
+        self.board.clock_seconds = clock_seconds + 1.0 / FRAMES_PER_SECOND;
+    }
+
+    /// The game is over as soon as one of the players tops out.
+    pub fn is_over(&self) -> bool {
+        self.players.iter().any(|game| game.is_game_over)
+    }
+
+    pub fn lines_cleared(&self) -> usize {
+        self.players.iter().map(|game| game.lines_cleared).sum()
+    }
+
+    /// Lines of text shown beside the board for the given player.
+    pub fn hud(&self, player: usize) -> Vec<String> {
+        let game = &self.players[player];
+        vec![
+            format!("Player {}", player + 1),
+            format!("Lines: {}", game.lines_cleared),
+            format!("Score: {}", game.score),
+            format!("Team lines: {}", self.lines_cleared()),
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+        ]
+    }
+
+    pub fn results(&self) -> Vec<(String, String)> {
+        let score: u64 = self.players.iter().map(|game| game.score).sum();
+        let mut results = vec![
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+            ("Lines".to_string(), self.lines_cleared().to_string()),
+            ("Score".to_string(), score.to_string()),
+        ];
+        for (index, game) in self.players.iter().enumerate() {
+            results.push((
+                format!("Player {}", index + 1),
+                format!(
+                    "{} lines, {} pieces, {:.2} PPS",
+                    game.lines_cleared,
+                    game.pieces_placed,
+                    pieces_per_second(game.pieces_placed, self.elapsed_seconds)
+                ),
+            ));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::piece::Piece,
+        enums::{CellType, PieceType},
+    };
+
+    fn set_piece(coop: &mut Coop, player: usize, position: Point2D) {
+        coop.players[player].current_piece = Piece::new(&PieceType::I, position);
+    }
+
+    #[test]
+    fn players_spawn_over_their_own_half() {
+        let coop = Coop::new(4, |_| {});
+
+        let blocks = |player: usize| coop.players[player].current_piece.as_ref().unwrap().get_blocks_position();
+        assert!(blocks(0).iter().all(|block| (block.x as usize) < COOP_COLS / 2));
+        assert!(blocks(1).iter().all(|block| (block.x as usize) >= COOP_COLS / 2));
+    }
+
+    #[test]
+    fn a_piece_lands_on_the_other_players_piece() {
+        let mut coop = Coop::new(4, |_| {});
+        set_piece(&mut coop, 0, Point2D::new(5, 1));
+        set_piece(&mut coop, 1, Point2D::new(10, 1));
+
+        coop.play(0, Game::hard_drop);
+
+        // The I piece of player 1 is on row 10, player 0 lands on top of it
+        assert!(coop.board.cells[9][..4].iter().all(|cell| *cell != CellType::Empty));
+        assert!(coop.board.cells[10].iter().all(|cell| *cell == CellType::Empty));
+    }
+
+    #[test]
+    fn both_players_complete_rows_of_the_shared_board() {
+        let mut coop = Coop::new(4, |_| {});
+        for col in 4..COOP_COLS - 4 {
+            coop.board.set_cell(ROWS - 1, col, CellType::Filled(BLUE));
+        }
+        set_piece(&mut coop, 0, Point2D::new(ROWS as isize - 2, 1));
+        set_piece(&mut coop, 1, Point2D::new(ROWS as isize - 2, COOP_COLS as isize - 3));
+
+        coop.play(0, Game::hard_drop);
+        assert_eq!(coop.lines_cleared(), 0);
+        coop.play(1, Game::hard_drop);
+
+        assert_eq!(coop.players[1].lines_cleared, 1);
+        assert!(coop.board.get_filled_cells().is_empty());
+        assert!(!coop.is_over());
+    }
+}
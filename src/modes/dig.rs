@@ -0,0 +1,169 @@
+use crate::{
+    core::game::Game,
+    enums::ModeStatus,
+    modes::{GameMode, format_time, pieces_per_second},
+};
+
+/// Garbage lines to clear in a standard Dig race.
+pub(crate) const DIG_LINE_GOAL: usize = 100;
+
+/// Garbage rows kept on the board while there is garbage left to send.
+pub(crate) const DIG_VISIBLE_GARBAGE_ROWS: usize = 10;
+
+/// Default probability that the hole of a garbage row moves away from the hole of the row below it.
+pub(crate) const DIG_DEFAULT_MESSINESS: f64 = 0.5;
+
+/// Dig (cheese race): the board starts with garbage rows and more are pushed in as the player
+/// digs, until `line_goal` garbage lines have been cleared.
+pub struct Dig {
+    pub line_goal: usize,
+    /// Probability that a garbage row's hole differs from the hole of the row below it.
+    pub messiness: f64,
+    pub visible_rows: usize,
+    pub elapsed_seconds: f64,
+    /// Number of garbage rows pushed onto the board so far.
+    pub garbage_generated: usize,
+    last_hole: Option<usize>,
+}
+
+impl Dig {
+    pub fn new(line_goal: usize, messiness: f64) -> Self {
+        Self {
+            line_goal,
+            messiness,
+            visible_rows: DIG_VISIBLE_GARBAGE_ROWS,
+            elapsed_seconds: 0.0,
+            garbage_generated: 0,
+            last_hole: None,
+        }
+    }
+
+    fn garbage_remaining(&self, game: &Game) -> usize {
+        self.line_goal.saturating_sub(game.garbage_lines_cleared)
+    }
+
+    /// Pushes in new garbage rows so that `visible_rows` are on the board,
+    /// without generating more garbage than the goal.
+    fn refill_garbage(&mut self, game: &mut Game) {
+        let on_board = self.garbage_generated - game.garbage_lines_cleared.min(self.garbage_generated);
+        let wanted = self.visible_rows.min(self.line_goal - self.garbage_generated + on_board);
+        if on_board >= wanted {
+            return;
+        }
+
+        let count = wanted - on_board;
+        let holes = game
+            .board
+            .generate_garbage_holes(count, self.messiness, self.last_hole, &mut rand::rng());
+        // The newest row goes to the bottom, so the pattern continues downwards
+        self.last_hole = holes.last().copied();
+        game.add_garbage_rows(&holes);
+        self.garbage_generated += count;
+    }
+}
+
+impl GameMode for Dig {
+    fn name(&self) -> String {
+        format!("Dig ({} lines)", self.line_goal)
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        game.has_level_progression = false;
+        game.start();
+        self.refill_garbage(game);
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) != ModeStatus::Playing {
+            return;
+        }
+
+        self.elapsed_seconds += delta_seconds;
+        self.refill_garbage(game);
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if self.garbage_remaining(game) == 0 {
+            ModeStatus::Won
+        } else if game.is_game_over {
+            ModeStatus::Lost
+        } else {
+            ModeStatus::Playing
+        }
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        vec![
+            format!("Garbage left: {}", self.garbage_remaining(game)),
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+            format!("Pieces: {}", game.pieces_placed),
+        ]
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        let pieces_per_line = if game.garbage_lines_cleared == 0 {
+            0.0
+        } else {
+            game.pieces_placed as f64 / game.garbage_lines_cleared as f64
+        };
+        vec![
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+            ("Garbage cleared".to_string(), game.garbage_lines_cleared.to_string()),
+            ("Pieces".to_string(), game.pieces_placed.to_string()),
+            (
+                "Pieces/sec".to_string(),
+                format!("{:.2}", pieces_per_second(game.pieces_placed, self.elapsed_seconds)),
+            ),
+            ("Pieces/line".to_string(), format!("{:.2}", pieces_per_line)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{core::constants::ROWS, enums::CellType};
+
+    use super::*;
+
+    fn garbage_rows(game: &Game) -> usize {
+        game.board
+            .cells
+            .iter()
+            .filter(|row| row.contains(&CellType::Garbage))
+            .count()
+    }
+
+    #[test]
+    fn setup_fills_the_bottom_with_garbage() {
+        let mut game = Game::new();
+        let mut dig = Dig::new(DIG_LINE_GOAL, DIG_DEFAULT_MESSINESS);
+        dig.setup(&mut game);
+
+        assert_eq!(garbage_rows(&game), DIG_VISIBLE_GARBAGE_ROWS);
+        assert!(game.board.cells[ROWS - DIG_VISIBLE_GARBAGE_ROWS..]
+            .iter()
+            .all(|row| row.iter().filter(|&&cell| cell == CellType::Empty).count() == 1));
+    }
+
+    #[test]
+    fn garbage_is_refilled_as_lines_are_dug_without_exceeding_the_goal() {
+        let mut game = Game::new();
+        let mut dig = Dig::new(12, 0.0);
+        dig.setup(&mut game);
+
+        // Simulate digging out the bottom three garbage rows
+        game.board.cells.drain(ROWS - 3..);
+        for _ in 0..3 {
+            game.board.cells.insert(0, vec![CellType::Empty; game.board.cols]);
+        }
+        game.garbage_lines_cleared = 3;
+        dig.update(&mut game, 1.0);
+
+        assert_eq!(dig.garbage_generated, 12);
+        assert_eq!(garbage_rows(&game), 9);
+        assert_eq!(dig.status(&game), ModeStatus::Playing);
+
+        game.garbage_lines_cleared = 12;
+        assert_eq!(dig.status(&game), ModeStatus::Won);
+    }
+}
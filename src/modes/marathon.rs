@@ -0,0 +1,137 @@
+use crate::{
+    core::game::Game,
+    enums::ModeStatus,
+    modes::{GameMode, format_time, pieces_per_second},
+};
+
+/// Number of lines needed to win a standard Marathon.
+pub(crate) const MARATHON_LINE_GOAL: usize = 150;
+
+/// Classic Marathon: the level rises every ten lines and the game is won after
+/// [`MARATHON_LINE_GOAL`] lines, or keeps going until top out in the endless variant.
+pub struct Marathon {
+    /// Lines needed to win, `None` for the endless variant.
+    pub line_goal: Option<usize>,
+    pub elapsed_seconds: f64,
+}
+
+impl Default for Marathon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Marathon {
+    /// Creates a Marathon that is won after [`MARATHON_LINE_GOAL`] lines.
+    pub fn new() -> Self {
+        Self {
+            line_goal: Some(MARATHON_LINE_GOAL),
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    /// Creates a Marathon that only ends when the stack tops out.
+    pub fn endless() -> Self {
+        Self {
+            line_goal: None,
+            elapsed_seconds: 0.0,
+        }
+    }
+}
+
+impl GameMode for Marathon {
+    fn name(&self) -> String {
+        match self.line_goal {
+            Some(goal) => format!("Marathon ({} lines)", goal),
+            None => "Marathon (endless)".to_string(),
+        }
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) == ModeStatus::Playing {
+            self.elapsed_seconds += delta_seconds;
+        }
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if let Some(goal) = self.line_goal
+            && game.lines_cleared >= goal
+        {
+            return ModeStatus::Won;
+        }
+
+        if game.is_game_over {
+            ModeStatus::Lost
+        } else {
+            ModeStatus::Playing
+        }
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        let lines = match self.line_goal {
+            Some(goal) => format!("Lines: {}/{}", game.lines_cleared, goal),
+            None => format!("Lines: {}", game.lines_cleared),
+        };
+        vec![
+            format!("Score: {}", game.score),
+            format!("Level: {}", game.level),
+            lines,
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+        ]
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        vec![
+            ("Score".to_string(), game.score.to_string()),
+            ("Level".to_string(), game.level.to_string()),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+            (
+                "Pieces/sec".to_string(),
+                format!("{:.2}", pieces_per_second(game.pieces_placed, self.elapsed_seconds)),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marathon_is_won_after_the_line_goal() {
+        let mut game = Game::new();
+        let mut marathon = Marathon::new();
+        marathon.setup(&mut game);
+        assert_eq!(marathon.status(&game), ModeStatus::Playing);
+
+        game.lines_cleared = MARATHON_LINE_GOAL;
+        assert_eq!(marathon.status(&game), ModeStatus::Won);
+    }
+
+    #[test]
+    fn endless_marathon_only_ends_on_top_out() {
+        let mut game = Game::new();
+        let mut marathon = Marathon::endless();
+        marathon.setup(&mut game);
+
+        game.lines_cleared = MARATHON_LINE_GOAL * 2;
+        assert_eq!(marathon.status(&game), ModeStatus::Playing);
+
+        game.is_game_over = true;
+        assert_eq!(marathon.status(&game), ModeStatus::Lost);
+    }
+
+    #[test]
+    fn timer_stops_once_the_mode_has_ended() {
+        let mut game = Game::new();
+        let mut marathon = Marathon::new();
+        marathon.setup(&mut game);
+
+        marathon.update(&mut game, 1.5);
+        game.is_game_over = true;
+        marathon.update(&mut game, 1.5);
+
+        assert_eq!(marathon.elapsed_seconds, 1.5);
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{
+    core::{game::Game, rules::TGM_GRAVITY_TABLE, timings::Timings},
+    enums::ModeStatus,
+    modes::{GameMode, format_time},
+};
+
+/// Internal level at which Master ends.
+pub(crate) const MASTER_FINAL_LEVEL: usize = 999;
+
+/// Levels per section; the level stops at the end of each section until a line is cleared.
+pub(crate) const MASTER_SECTION_LEVELS: usize = 100;
+
+/// Delays by internal level: (level, ARE, line clear delay, DAS, lock delay), in frames.
+const SECTION_DELAYS: [(usize, u32, u32, u32, u32); 6] = [
+    (0, 25, 40, 14, 30),
+    (500, 25, 25, 8, 30),
+    (600, 25, 16, 8, 30),
+    (700, 16, 12, 8, 30),
+    (800, 12, 6, 8, 30),
+    (900, 12, 6, 6, 17),
+];
+
+/// Minimum score for each grade, from the lowest grade "9" to "S9".
+const GRADE_TABLE: [(u64, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100000, "S8"),
+    (120000, "S9"),
+];
+
+/// Conditions to be awarded the Grand Master grade: (level, minimum score, maximum seconds).
+const GRAND_MASTER_CHECKPOINTS: [(usize, u64, f64); 3] = [
+    (300, 12000, 255.0),
+    (500, 40000, 450.0),
+    (MASTER_FINAL_LEVEL, 126000, 810.0),
+];
+
+/// Returns the timings of Master mode at the given internal `level`.
+pub fn master_timings(level: usize) -> Timings {
+    let gravity = TGM_GRAVITY_TABLE
+        .iter()
+        .rev()
+        .find(|(start, _)| level >= *start)
+        .map_or(4, |(_, gravity)| *gravity);
+    let (_, are, line_clear_delay, das, lock_delay) = *SECTION_DELAYS
+        .iter()
+        .rev()
+        .find(|(start, ..)| level >= *start)
+        .unwrap_or(&SECTION_DELAYS[0]);
+
+    Timings {
+        gravity: gravity as f64 / 256.0,
+        soft_drop_gravity: 1.0,
+        are,
+        line_clear_delay,
+        das,
+        arr: 1,
+        lock_delay,
+        locks_on_failed_drop: false,
+    }
+}
+
+/// Master: TGM style mode where an internal level rises with every piece and every line,
+/// and a section table raises gravity up to 20G and shortens the delays.
+pub struct Master {
+    /// Internal level from 0 to [`MASTER_FINAL_LEVEL`].
+    pub level: usize,
+    /// Score computed with the TGM formula, which decides the grade.
+    pub score: u64,
+    pub elapsed_seconds: f64,
+    /// Time taken by each finished section.
+    pub section_times: Vec<f64>,
+    /// Cleared when a Grand Master checkpoint is missed.
+    pub is_grand_master_eligible: bool,
+    combo: u64,
+    pieces_seen: usize,
+    lines_seen: usize,
+    section_start_seconds: f64,
+    checkpoints_passed: usize,
+}
+
+impl Default for Master {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Master {
+    pub fn new() -> Self {
+        Self {
+            level: 0,
+            score: 0,
+            elapsed_seconds: 0.0,
+            section_times: Vec::new(),
+            is_grand_master_eligible: true,
+            combo: 1,
+            pieces_seen: 0,
+            lines_seen: 0,
+            section_start_seconds: 0.0,
+            checkpoints_passed: 0,
+        }
+    }
+
+    /// Returns the current grade, "GM" once every Grand Master checkpoint was passed.
+    pub fn grade(&self) -> &'static str {
+        if self.checkpoints_passed == GRAND_MASTER_CHECKPOINTS.len() && self.is_grand_master_eligible {
+            return "GM";
+        }
+        GRADE_TABLE
+            .iter()
+            .rev()
+            .find(|(minimum, _)| self.score >= *minimum)
+            .map_or("9", |(_, grade)| grade)
+    }
+
+    /// Level at which the level stops until a line is cleared (x99 or the final level).
+    fn level_stop(&self) -> usize {
+        let next_section = (self.level / MASTER_SECTION_LEVELS + 1) * MASTER_SECTION_LEVELS - 1;
+        next_section.min(MASTER_FINAL_LEVEL - 1)
+    }
+
+    /// Scores a clear with the TGM formula:
+    /// ceil((level + lines) / 4) * lines * combo * bravo, where bravo is 4 for a perfect clear.
+    fn register_clear(&mut self, lines: usize, is_perfect_clear: bool) {
+        self.combo += 2 * lines as u64 - 2;
+        let bravo = if is_perfect_clear { 4 } else { 1 };
+        let base = (self.level + lines).div_ceil(4) as u64;
+        self.score += base * lines as u64 * self.combo * bravo;
+    }
+
+    fn advance_level(&mut self, game: &mut Game, levels: usize, can_pass_section: bool) {
+        let previous = self.level;
+        let limit = if can_pass_section { MASTER_FINAL_LEVEL } else { self.level_stop() };
+        self.level = (self.level + levels).min(limit.max(self.level));
+
+        if self.level / MASTER_SECTION_LEVELS > previous / MASTER_SECTION_LEVELS
+            || self.level == MASTER_FINAL_LEVEL
+        {
+            self.section_times.push(self.elapsed_seconds - self.section_start_seconds);
+            self.section_start_seconds = self.elapsed_seconds;
+        }
+        self.check_grand_master_conditions();
+        game.timings = master_timings(self.level);
+    }
+
+    fn check_grand_master_conditions(&mut self) {
+        while let Some(&(level, score, seconds)) = GRAND_MASTER_CHECKPOINTS.get(self.checkpoints_passed) {
+            if self.level < level {
+                return;
+            }
+            if self.score < score || self.elapsed_seconds > seconds {
+                self.is_grand_master_eligible = false;
+            }
+            self.checkpoints_passed += 1;
+        }
+    }
+}
+
+impl GameMode for Master {
+    fn name(&self) -> String {
+        "Master (20G)".to_string()
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        game.has_level_progression = false;
+        game.timings = master_timings(self.level);
+        game.start();
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) != ModeStatus::Playing {
+            return;
+        }
+        self.elapsed_seconds += delta_seconds;
+
+        let new_lines = game.lines_cleared - self.lines_seen;
+        let new_pieces = game.pieces_placed - self.pieces_seen;
+        self.lines_seen = game.lines_cleared;
+        self.pieces_seen = game.pieces_placed;
+
+        if new_lines > 0 {
+            let is_perfect_clear = game
+                .last_clear
+                .as_ref()
+                .is_some_and(|clear| clear.is_perfect_clear);
+            self.register_clear(new_lines, is_perfect_clear);
+            self.advance_level(game, new_lines, true);
+        } else if new_pieces > 0 {
+            // The combo only carries over consecutive clears
+            self.combo = 1;
+        }
+
+        if new_pieces > 0 {
+            self.advance_level(game, new_pieces, false);
+        }
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if self.level >= MASTER_FINAL_LEVEL {
+            ModeStatus::Won
+        } else if game.is_game_over {
+            ModeStatus::Lost
+        } else {
+            ModeStatus::Playing
+        }
+    }
+
+    fn hud(&self, _game: &Game) -> Vec<String> {
+        let next_section = (self.level_stop() + 1).min(MASTER_FINAL_LEVEL);
+        vec![
+            format!("Level: {}/{}", self.level, next_section),
+            format!("Grade: {}", self.grade()),
+            format!("Score: {}", self.score),
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+        ]
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        let mut results = vec![
+            ("Grade".to_string(), self.grade().to_string()),
+            ("Level".to_string(), self.level.to_string()),
+            ("Score".to_string(), self.score.to_string()),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+        ];
+        for (index, time) in self.section_times.iter().enumerate() {
+            let start = index * MASTER_SECTION_LEVELS;
+            results.push((
+                format!("{:03}-{:03}", start, start + MASTER_SECTION_LEVELS - 1),
+                format_time(*time),
+            ));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::timings::TWENTY_G;
+
+    use super::*;
+
+    #[test]
+    fn master_timings_follow_the_section_table() {
+        let start = master_timings(0);
+        assert_eq!(start.gravity, 4.0 / 256.0);
+        assert_eq!(start.are, 25);
+        assert_eq!(start.das, 14);
+
+        assert_eq!(master_timings(251).gravity, 1.0);
+        assert_eq!(master_timings(500).gravity, TWENTY_G);
+        assert_eq!(master_timings(500).das, 8);
+
+        let last = master_timings(950);
+        assert_eq!(last.are, 12);
+        assert_eq!(last.lock_delay, 17);
+    }
+
+    #[test]
+    fn level_rises_with_pieces_but_stops_at_the_end_of_a_section() {
+        let mut game = Game::new();
+        let mut master = Master::new();
+        master.setup(&mut game);
+
+        game.pieces_placed = 98;
+        master.update(&mut game, 1.0);
+        assert_eq!(master.level, 98);
+
+        game.pieces_placed = 105;
+        master.update(&mut game, 1.0);
+        assert_eq!(master.level, 99);
+
+        // Only a line clear moves past the level stop, the locked piece then counts as well
+        game.pieces_placed = 106;
+        game.lines_cleared = 2;
+        master.update(&mut game, 1.0);
+        assert_eq!(master.level, 102);
+        assert_eq!(master.section_times, vec![3.0]);
+        assert_eq!(game.timings, master_timings(102));
+    }
+
+    #[test]
+    fn clears_are_scored_with_the_tgm_formula() {
+        let mut game = Game::new();
+        let mut master = Master::new();
+        master.setup(&mut game);
+        master.level = 10;
+
+        game.pieces_placed = 1;
+        game.lines_cleared = 4;
+        master.update(&mut game, 1.0);
+        // ceil((10 + 4) / 4) * 4 lines * combo (1 + 2 * 4 - 2)
+        assert_eq!(master.score, 4 * 4 * 7);
+        assert_eq!(master.grade(), "9");
+    }
+
+    #[test]
+    fn reaching_the_final_level_wins() {
+        let mut game = Game::new();
+        let mut master = Master::new();
+        master.setup(&mut game);
+        master.level = 998;
+
+        game.pieces_placed = 1;
+        game.lines_cleared = 1;
+        master.update(&mut game, 1.0);
+
+        assert_eq!(master.level, MASTER_FINAL_LEVEL);
+        assert_eq!(master.status(&game), ModeStatus::Won);
+    }
+
+    #[test]
+    fn missing_a_checkpoint_rules_out_grand_master() {
+        let mut master = Master::new();
+        master.level = 300;
+        master.score = 5000;
+        master.elapsed_seconds = 200.0;
+        master.check_grand_master_conditions();
+        assert!(!master.is_grand_master_eligible);
+        assert_eq!(master.grade(), "4");
+    }
+}
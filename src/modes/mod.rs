@@ -0,0 +1,137 @@
+pub mod classic;
+pub mod coop;
+pub mod dig;
+pub mod marathon;
+pub mod master;
+pub mod puzzle;
+pub mod sprint;
+pub mod ultra;
+pub mod versus;
+pub mod zen;
+
+use crate::{core::game::Game, enums::ModeStatus};
+
+pub use classic::Classic;
+pub use dig::Dig;
+pub use marathon::Marathon;
+pub use master::Master;
+pub use puzzle::Puzzle;
+pub use sprint::Sprint;
+pub use ultra::Ultra;
+pub use zen::Zen;
+
+/// A set of rules layered on top of a [`Game`]: how it is set up, when it ends
+/// and what is shown to the player while playing and once it is over.
+pub trait GameMode {
+    /// Name shown in the mode selection menu and on the results screen.
+    fn name(&self) -> String;
+
+    /// Sets the level to start at, for modes that let the player choose it. Called before
+    /// [`GameMode::setup`].
+    fn set_start_level(&mut self, _level: usize) {}
+
+    /// Prepares the game before the first piece spawns.
+    fn setup(&mut self, game: &mut Game) {
+        game.start();
+    }
+
+    /// Called whenever the player presses a key that acts on the game.
+    fn on_input(&mut self, _game: &mut Game) {}
+
+    /// Advances the mode's own state (timers, goals) by `delta_seconds`.
+    fn update(&mut self, game: &mut Game, delta_seconds: f64);
+
+    /// Takes back the last placement, for modes that keep a history.
+    fn undo(&mut self, _game: &mut Game) {}
+
+    /// Puts back the last placement taken back with [`GameMode::undo`].
+    fn redo(&mut self, _game: &mut Game) {}
+
+    /// Whether the player should be warned that the mode is about to end, e.g. a running out timer.
+    fn is_in_warning(&self, _game: &Game) -> bool {
+        false
+    }
+
+    /// Returns `true` once each time an audible warning cue should be played.
+    fn take_warning_cue(&mut self) -> bool {
+        false
+    }
+
+    /// Reports whether the game is still running, has been won or has been lost.
+    fn status(&self, game: &Game) -> ModeStatus;
+
+    /// Lines of text shown beside the board while playing.
+    fn hud(&self, game: &Game) -> Vec<String>;
+
+    /// Label and value rows shown on the results screen once the mode has ended.
+    fn results(&self, game: &Game) -> Vec<(String, String)>;
+}
+
+/// Creates a fresh instance of a mode.
+type ModeConstructor = fn() -> Box<dyn GameMode>;
+
+/// Every selectable mode in menu order: its name and how to create a fresh instance of it,
+/// so the menu can be listed without creating the modes.
+const MODES: [(&str, ModeConstructor); 9] = [
+    ("Marathon (150 lines)", || Box::new(Marathon::new())),
+    ("Marathon (endless)", || Box::new(Marathon::endless())),
+    ("Classic (NES)", || Box::new(Classic::new(0))),
+    ("Sprint (40 lines)", || Box::new(Sprint::new())),
+    ("Ultra (2 minutes)", || Box::new(Ultra::new())),
+    ("Dig (100 lines)", || Box::new(Dig::new(dig::DIG_LINE_GOAL, dig::DIG_DEFAULT_MESSINESS))),
+    ("Master (20G)", || Box::new(Master::new())),
+    ("Zen", || Box::new(Zen::new(zen::ZEN_DEFAULT_UNDO_LIMIT))),
+    ("Puzzle", || Box::new(Puzzle::from_pack())),
+];
+
+/// Names of the selectable modes, in menu order.
+pub fn mode_names() -> Vec<String> {
+    MODES.iter().map(|(name, _)| name.to_string()).collect()
+}
+
+/// Returns a fresh instance of the selectable mode at `index` in menu order.
+pub fn create_mode(index: usize) -> Box<dyn GameMode> {
+    (MODES[index].1)()
+}
+
+/// Formats a duration in seconds as `m:ss.mmm`.
+pub fn format_time(seconds: f64) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let minutes = total_millis / 60_000;
+    let secs = (total_millis % 60_000) / 1000;
+    let millis = total_millis % 1000;
+    format!("{}:{:02}.{:03}", minutes, secs, millis)
+}
+
+/// Returns the average number of pieces locked per second.
+pub fn pieces_per_second(pieces: usize, seconds: f64) -> f64 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+    pieces as f64 / seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_names_match_the_modes() {
+        for (index, name) in mode_names().iter().enumerate() {
+            assert_eq!(create_mode(index).name(), *name);
+        }
+    }
+
+    #[test]
+    fn format_time_pads_seconds_and_milliseconds() {
+        assert_eq!(format_time(0.0), "0:00.000");
+        assert_eq!(format_time(5.25), "0:05.250");
+        assert_eq!(format_time(83.0071), "1:23.007");
+    }
+
+    #[test]
+    fn pieces_per_second_handles_zero_time() {
+        assert_eq!(pieces_per_second(10, 0.0), 0.0);
+        assert_eq!(pieces_per_second(10, 4.0), 2.5);
+    }
+}
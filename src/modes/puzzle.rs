@@ -0,0 +1,530 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    core::{
+        board::Board,
+        constants::{COLS, ROWS},
+        game::Game,
+        piece::Piece,
+        piece_definition::PieceDefinition,
+    },
+    enums::{CellType, ClearType, ModeStatus, PieceType},
+    modes::{GameMode, format_time},
+};
+
+/// Directory puzzle files are loaded from, the built-in pack is used when it has none.
+pub(crate) const PUZZLE_DIRECTORY: &str = "puzzles";
+
+/// Extension of puzzle files.
+pub(crate) const PUZZLE_EXTENSION: &str = "puzzle";
+
+/// Puzzles shipped with the game.
+const BUILT_IN_PUZZLES: [&str; 5] = [
+    include_str!("../../puzzles/01_tetris_ready.puzzle"),
+    include_str!("../../puzzles/02_t_slot.puzzle"),
+    include_str!("../../puzzles/03_swap_it_out.puzzle"),
+    include_str!("../../puzzles/04_clean_sweep.puzzle"),
+    include_str!("../../puzzles/05_building_blocks.puzzle"),
+];
+
+/// What the player has to achieve to solve a challenge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PuzzleGoal {
+    ClearLines(usize),
+    TSpinTriple,
+    PerfectClear,
+    /// The filled cells of the board must match exactly, `true` for a filled cell.
+    Shape(Vec<Vec<bool>>),
+}
+
+impl PuzzleGoal {
+    pub fn description(&self) -> String {
+        match self {
+            PuzzleGoal::ClearLines(1) => "Clear 1 line".to_string(),
+            PuzzleGoal::ClearLines(lines) => format!("Clear {} lines", lines),
+            PuzzleGoal::TSpinTriple => "T-Spin Triple".to_string(),
+            PuzzleGoal::PerfectClear => "Perfect Clear".to_string(),
+            PuzzleGoal::Shape(_) => "Build the shape".to_string(),
+        }
+    }
+
+    pub fn is_met(&self, game: &Game) -> bool {
+        match self {
+            PuzzleGoal::ClearLines(lines) => game.lines_cleared >= *lines,
+            PuzzleGoal::TSpinTriple => game.clear_stats.contains_key(&ClearType::TSpinTriple),
+            PuzzleGoal::PerfectClear => game.perfect_clears > 0,
+            PuzzleGoal::Shape(target) => game
+                .board
+                .cells
+                .iter()
+                .zip(target)
+                .all(|(row, target_row)| {
+                    row.iter()
+                        .zip(target_row)
+                        .all(|(cell, filled)| (*cell != CellType::Empty) == *filled)
+                }),
+        }
+    }
+}
+
+/// An authored challenge: a starting board, a fixed piece queue, an optional held piece and a goal.
+///
+/// Challenges are written as text, one `key: value` per line and `#` for comments:
+///
+/// ```text
+/// name: T Slot
+/// goal: lines 2          (or tspin-triple, perfect-clear, shape)
+/// queue: T I
+/// hold: O                (optional)
+/// board:                 (bottom rows of the board, '.' empty, 'G' garbage, a piece letter for its color)
+/// GGG...GGGG
+/// GGGG.GGGGG
+/// target:                (for the shape goal, any character but '.' is filled)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub name: String,
+    pub goal: PuzzleGoal,
+    pub queue: Vec<PieceType>,
+    pub hold: Option<PieceType>,
+    /// Every cell of the board, [`ROWS`] rows of [`COLS`] cells.
+    pub board: Vec<Vec<CellType>>,
+}
+
+impl Challenge {
+    /// Parses a challenge, the error names the offending line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut goal = None;
+        let mut queue = None;
+        let mut hold = None;
+        let mut board_rows: Vec<Vec<CellType>> = Vec::new();
+        let mut target_rows: Vec<Vec<bool>> = Vec::new();
+        let mut section = None;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line = raw_line.trim();
+            let line_number = index + 1;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                match section {
+                    Some("board") => board_rows.push(parse_board_row(line, line_number)?),
+                    Some("target") => target_rows.push(parse_target_row(line, line_number)?),
+                    _ => return Err(format!("line {}: expected `key: value`", line_number)),
+                }
+                continue;
+            };
+
+            let value = value.trim();
+            section = None;
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "goal" => goal = Some(parse_goal(value, line_number)?),
+                "queue" => {
+                    let pieces = value
+                        .split_whitespace()
+                        .map(|piece| parse_piece_type(piece, line_number))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    queue = Some(pieces);
+                }
+                "hold" if !value.is_empty() => hold = Some(parse_piece_type(value, line_number)?),
+                "hold" => {}
+                "board" => section = Some("board"),
+                "target" => section = Some("target"),
+                other => return Err(format!("line {}: unknown key `{}`", line_number, other)),
+            }
+        }
+
+        let name = name.ok_or("missing `name`")?;
+        let queue = queue.filter(|queue| !queue.is_empty()).ok_or("missing or empty `queue`")?;
+        let mut goal = goal.ok_or("missing `goal`")?;
+        if board_rows.len() > ROWS || target_rows.len() > ROWS {
+            return Err(format!("the board has more than {} rows", ROWS));
+        }
+
+        if let PuzzleGoal::Shape(target) = &mut goal {
+            if target_rows.is_empty() {
+                return Err("the `shape` goal needs a `target`".to_string());
+            }
+            *target = pad_rows(target_rows, false);
+        }
+
+        Ok(Self {
+            name,
+            goal,
+            queue,
+            hold,
+            board: pad_rows(board_rows, CellType::Empty),
+        })
+    }
+
+    /// Loads a challenge from a file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Creates a game set up with the challenge's board, queue and hold. Pieces do not fall
+    /// and only lock when hard dropped, so the player can take their time.
+    pub fn create_game(&self) -> Game {
+        let mut game = Game::new();
+        game.has_level_progression = false;
+        game.timings.gravity = 0.0;
+        game.timings.lock_delay = u32::MAX;
+
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                game.board.set_cell(row, col, *cell);
+            }
+        }
+        game.set_fixed_queue(&self.queue);
+        game.hold_piece = self.hold.as_ref().and_then(|piece_type| game.spawn_piece_of_type(piece_type));
+        game.start();
+        game
+    }
+
+    /// Checks that the goal can be reached with the given pieces, trying every reachable
+    /// placement of every piece, with and without using the hold.
+    pub fn is_solvable(&self) -> bool {
+        let mut visited = HashSet::new();
+        self.search(&self.create_game(), &mut visited)
+    }
+
+    fn search(&self, game: &Game, visited: &mut HashSet<SearchKey>) -> bool {
+        if self.goal.is_met(game) {
+            return true;
+        }
+        if game.is_game_over || game.current_piece.is_none() || !visited.insert(SearchKey::new(game)) {
+            return false;
+        }
+
+        let mut starts = vec![game.clone()];
+        let mut held = game.clone();
+        held.hold();
+        if piece_type_of(&held.hold_piece) != piece_type_of(&game.hold_piece) {
+            starts.push(held);
+        }
+
+        starts
+            .iter()
+            .any(|start| start.placements().iter().any(|placement| self.search(&placement.result, visited)))
+    }
+}
+
+/// Identifies a position of the search: the board, the pieces left and what was achieved so far.
+#[derive(PartialEq, Eq, Hash)]
+struct SearchKey {
+    board: Vec<Vec<usize>>,
+    pieces: Vec<Option<PieceType>>,
+    remaining: Vec<PieceType>,
+    lines_cleared: usize,
+}
+
+impl SearchKey {
+    fn new(game: &Game) -> Self {
+        Self {
+            board: game.board.get_board_representation(),
+            pieces: vec![
+                piece_type_of(&game.current_piece),
+                piece_type_of(&game.next_piece),
+                piece_type_of(&game.hold_piece),
+            ],
+            remaining: game.remaining_queue(),
+            lines_cleared: game.lines_cleared,
+        }
+    }
+}
+
+fn piece_type_of(piece: &Option<Piece>) -> Option<PieceType> {
+    piece.as_ref().map(|piece| piece.piece_type.clone())
+}
+
+/// Pads authored rows on top so they sit at the bottom of the board.
+fn pad_rows<T: Clone>(rows: Vec<Vec<T>>, empty: T) -> Vec<Vec<T>> {
+    let mut padded = vec![vec![empty; COLS]; ROWS - rows.len()];
+    padded.extend(rows);
+    padded
+}
+
+fn parse_board_row(line: &str, line_number: usize) -> Result<Vec<CellType>, String> {
+    if line.chars().count() != COLS {
+        return Err(format!("line {}: board rows must be {} cells wide", line_number, COLS));
+    }
+    line.chars()
+        .map(|cell| {
+            Board::cell_from_ascii(cell).ok_or_else(|| format!("line {}: unknown piece `{}`", line_number, cell))
+        })
+        .collect()
+}
+
+fn parse_target_row(line: &str, line_number: usize) -> Result<Vec<bool>, String> {
+    if line.chars().count() != COLS {
+        return Err(format!("line {}: target rows must be {} cells wide", line_number, COLS));
+    }
+    Ok(line.chars().map(|cell| cell != '.').collect())
+}
+
+fn parse_goal(value: &str, line_number: usize) -> Result<PuzzleGoal, String> {
+    let mut words = value.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("lines"), Some(lines)) => lines
+            .parse()
+            .ok()
+            .filter(|lines| *lines > 0)
+            .map(PuzzleGoal::ClearLines)
+            .ok_or_else(|| format!("line {}: invalid number of lines `{}`", line_number, lines)),
+        (Some("tspin-triple"), None) => Ok(PuzzleGoal::TSpinTriple),
+        (Some("perfect-clear"), None) => Ok(PuzzleGoal::PerfectClear),
+        (Some("shape"), None) => Ok(PuzzleGoal::Shape(Vec::new())),
+        _ => Err(format!("line {}: unknown goal `{}`", line_number, value)),
+    }
+}
+
+/// Parses a tetromino letter or the name of another built-in piece, e.g. `T` or `P'`.
+fn parse_piece_type(name: &str, line_number: usize) -> Result<PieceType, String> {
+    let piece_type = match name {
+        "I" => PieceType::I,
+        "J" => PieceType::J,
+        "L" => PieceType::L,
+        "O" => PieceType::O,
+        "S" => PieceType::S,
+        "T" => PieceType::T,
+        "Z" => PieceType::Z,
+        custom => PieceType::Custom(custom.to_string()),
+    };
+    if PieceDefinition::from_type(&piece_type).is_none() {
+        return Err(format!("line {}: unknown piece `{}`", line_number, name));
+    }
+    Ok(piece_type)
+}
+
+/// Returns the challenges found in [`PUZZLE_DIRECTORY`] in file name order, or the built-in
+/// pack when there are none. Files that cannot be parsed are reported and skipped. Whether the
+/// challenges can be solved is not checked here, see [`check_puzzle_pack`].
+pub fn load_puzzle_pack() -> Vec<Challenge> {
+    let challenges: Vec<Challenge> = puzzle_files()
+        .iter()
+        .filter_map(|path| {
+            Challenge::load(path)
+                .inspect_err(|error| eprintln!("Skipping puzzle {}", error))
+                .ok()
+        })
+        .collect();
+
+    if challenges.is_empty() { built_in_puzzles() } else { challenges }
+}
+
+/// Tries every placement of every puzzle file of [`PUZZLE_DIRECTORY`] and returns a line per
+/// file saying whether it can be solved, or why it cannot be read. This can take long on big
+/// puzzles, so it is run with `--check-puzzles` rather than when the mode starts.
+pub fn check_puzzle_pack() -> Vec<String> {
+    puzzle_files()
+        .iter()
+        .map(|path| match Challenge::load(path) {
+            Ok(challenge) if challenge.is_solvable() => format!("{}: solvable", path.display()),
+            Ok(_) => format!("{}: it cannot be solved", path.display()),
+            Err(error) => error,
+        })
+        .collect()
+}
+
+/// The puzzle files of [`PUZZLE_DIRECTORY`] in file name order.
+fn puzzle_files() -> Vec<PathBuf> {
+    let mut paths: Vec<_> = fs::read_dir(PUZZLE_DIRECTORY)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == PUZZLE_EXTENSION))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+pub fn built_in_puzzles() -> Vec<Challenge> {
+    BUILT_IN_PUZZLES
+        .iter()
+        .map(|text| Challenge::parse(text).expect("built-in puzzles are valid"))
+        .collect()
+}
+
+/// Puzzle: solve a pack of authored challenges one after the other.
+pub struct Puzzle {
+    pub challenges: Vec<Challenge>,
+    /// Index of the challenge being played, equal to the number solved so far.
+    pub current: usize,
+    pub elapsed_seconds: f64,
+}
+
+impl Puzzle {
+    pub fn new(challenges: Vec<Challenge>) -> Self {
+        Self {
+            challenges,
+            current: 0,
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    /// Creates a Puzzle over the pack of [`load_puzzle_pack`], loaded once the mode starts.
+    pub fn from_pack() -> Self {
+        Self::new(Vec::new())
+    }
+
+    fn challenge(&self) -> Option<&Challenge> {
+        self.challenges.get(self.current)
+    }
+
+    fn is_out_of_pieces(game: &Game) -> bool {
+        game.current_piece.is_none() && game.next_piece.is_none()
+    }
+}
+
+impl GameMode for Puzzle {
+    fn name(&self) -> String {
+        "Puzzle".to_string()
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        if self.challenges.is_empty() {
+            self.challenges = load_puzzle_pack();
+        }
+        if let Some(challenge) = self.challenge() {
+            *game = challenge.create_game();
+        }
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) != ModeStatus::Playing {
+            return;
+        }
+        self.elapsed_seconds += delta_seconds;
+
+        if self.challenge().is_some_and(|challenge| challenge.goal.is_met(game)) {
+            self.current += 1;
+            self.setup(game);
+        }
+    }
+
+    /// Restarts the current challenge.
+    fn undo(&mut self, game: &mut Game) {
+        self.setup(game);
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        match self.challenge() {
+            None => ModeStatus::Won,
+            Some(challenge) if challenge.goal.is_met(game) => ModeStatus::Playing,
+            Some(_) if game.is_game_over || Self::is_out_of_pieces(game) => ModeStatus::Lost,
+            Some(_) => ModeStatus::Playing,
+        }
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        let Some(challenge) = self.challenge() else {
+            return Vec::new();
+        };
+        vec![
+            format!("{} ({}/{})", challenge.name, self.current + 1, self.challenges.len()),
+            format!("Goal: {}", challenge.goal.description()),
+            format!("Pieces left: {}", game.remaining_queue().len()),
+            "Restart (U)".to_string(),
+        ]
+    }
+
+    fn results(&self, _game: &Game) -> Vec<(String, String)> {
+        let mut results = vec![
+            ("Solved".to_string(), format!("{}/{}", self.current, self.challenges.len())),
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+        ];
+        if let Some(challenge) = self.challenge() {
+            results.push(("Failed".to_string(), challenge.name.clone()));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::RotationDirection;
+
+    #[test]
+    fn built_in_puzzles_are_solvable() {
+        for challenge in built_in_puzzles() {
+            assert!(challenge.is_solvable(), "{} is not solvable", challenge.name);
+        }
+    }
+
+    #[test]
+    fn challenge_without_the_right_pieces_is_not_solvable() {
+        let challenge = Challenge::parse(
+            "name: Wrong Piece\ngoal: lines 4\nqueue: O\nboard:\nGGGGGGGGG.\nGGGGGGGGG.\nGGGGGGGGG.\nGGGGGGGGG.\n",
+        )
+        .unwrap();
+        assert!(!challenge.is_solvable());
+    }
+
+    #[test]
+    fn parse_reads_board_queue_hold_and_goal() {
+        let challenge = Challenge::parse("name: Test\ngoal: shape\nqueue: T P'\nhold: I\nboard:\nGT........\ntarget:\nGTT.......\n").unwrap();
+
+        assert_eq!(challenge.queue, vec![PieceType::T, PieceType::Custom("P'".to_string())]);
+        assert_eq!(challenge.hold, Some(PieceType::I));
+        assert_eq!(challenge.board.len(), ROWS);
+        assert_eq!(challenge.board[ROWS - 1][0], CellType::Garbage);
+        assert!(matches!(challenge.board[ROWS - 1][1], CellType::Filled(_)));
+        let PuzzleGoal::Shape(target) = &challenge.goal else {
+            panic!("expected a shape goal");
+        };
+        assert_eq!(&target[ROWS - 1][..4], &[true, true, true, false]);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let error = Challenge::parse("name: Test\ngoal: lines 2\nqueue: T Q\n").unwrap_err();
+        assert_eq!(error, "line 3: unknown piece `Q`");
+
+        let error = Challenge::parse("name: Test\ngoal: lines 2\nqueue: T\nboard:\nGGG\n").unwrap_err();
+        assert_eq!(error, "line 5: board rows must be 10 cells wide");
+    }
+
+    #[test]
+    fn solving_a_challenge_moves_to_the_next_one() {
+        let mut game = Game::new();
+        let mut puzzle = Puzzle::new(built_in_puzzles());
+        puzzle.setup(&mut game);
+
+        // Tetris Ready: stand the I up in the right-hand well
+        game.rotate_piece(RotationDirection::Clockwise);
+        for _ in 0..COLS {
+            game.move_piece_right();
+        }
+        game.hard_drop();
+        puzzle.update(&mut game, 1.0);
+
+        assert_eq!(puzzle.current, 1);
+        assert_eq!(puzzle.status(&game), ModeStatus::Playing);
+        assert_eq!(game.current_piece.as_ref().unwrap().piece_type, PieceType::T);
+    }
+
+    #[test]
+    fn running_out_of_pieces_fails_the_challenge() {
+        let mut game = Game::new();
+        let mut puzzle = Puzzle::new(built_in_puzzles());
+        puzzle.setup(&mut game);
+
+        game.hard_drop();
+        puzzle.update(&mut game, 1.0);
+
+        assert_eq!(puzzle.status(&game), ModeStatus::Lost);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    core::game::Game,
+    enums::ModeStatus,
+    modes::{GameMode, format_time, pieces_per_second},
+};
+
+/// Number of lines needed to finish a Sprint.
+pub(crate) const SPRINT_LINE_GOAL: usize = 40;
+
+/// A split is recorded every time this many lines have been cleared.
+pub(crate) const SPRINT_SPLIT_LINES: usize = 10;
+
+/// File the personal best splits are kept in, one time in seconds per line.
+pub(crate) const SPRINT_RECORD_FILE: &str = "sprint_pb.txt";
+
+/// 40-line Sprint: clear [`SPRINT_LINE_GOAL`] lines as fast as possible.
+/// The timer starts on the first input and a split is taken every [`SPRINT_SPLIT_LINES`] lines,
+/// compared against the personal best.
+pub struct Sprint {
+    pub elapsed_seconds: f64,
+    /// Elapsed time each time another [`SPRINT_SPLIT_LINES`] lines were cleared.
+    pub splits: Vec<f64>,
+    /// Splits of the fastest finished run, if any.
+    pub personal_best: Option<Vec<f64>>,
+    /// Set when the finished run beat the previous personal best.
+    pub is_new_personal_best: bool,
+    timer_started: bool,
+    record_path: Option<PathBuf>,
+}
+
+impl Default for Sprint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sprint {
+    /// Creates a Sprint that reads and saves its personal best in [`SPRINT_RECORD_FILE`].
+    pub fn new() -> Self {
+        Self::with_record_path(Some(PathBuf::from(SPRINT_RECORD_FILE)))
+    }
+
+    /// Creates a Sprint that keeps its personal best in `record_path`, or only in memory when `None`.
+    pub fn with_record_path(record_path: Option<PathBuf>) -> Self {
+        Self {
+            elapsed_seconds: 0.0,
+            splits: Vec::new(),
+            personal_best: record_path.as_deref().and_then(load_splits),
+            is_new_personal_best: false,
+            timer_started: false,
+            record_path,
+        }
+    }
+
+    /// Returns how far ahead (negative) or behind (positive) the split at `index` is
+    /// compared to the personal best.
+    pub fn split_difference(&self, index: usize) -> Option<f64> {
+        let split = self.splits.get(index)?;
+        let best = self.personal_best.as_ref()?.get(index)?;
+        Some(split - best)
+    }
+
+    fn is_finished(&self, game: &Game) -> bool {
+        game.lines_cleared >= SPRINT_LINE_GOAL
+    }
+
+    fn record_splits(&mut self, game: &Game) {
+        let reached = game.lines_cleared.min(SPRINT_LINE_GOAL) / SPRINT_SPLIT_LINES;
+        while self.splits.len() < reached {
+            self.splits.push(self.elapsed_seconds);
+        }
+    }
+
+    fn save_if_personal_best(&mut self) {
+        let final_time = self.elapsed_seconds;
+        let is_better = match self.personal_best.as_ref().and_then(|best| best.last()) {
+            Some(best_time) => final_time < *best_time,
+            None => true,
+        };
+        if !is_better {
+            return;
+        }
+
+        self.is_new_personal_best = true;
+        self.personal_best = Some(self.splits.clone());
+        if let Some(path) = &self.record_path {
+            let contents: Vec<String> = self.splits.iter().map(|split| split.to_string()).collect();
+            if let Err(error) = fs::write(path, contents.join("\n")) {
+                eprintln!("Could not save the sprint personal best: {}", error);
+            }
+        }
+    }
+
+    fn format_split(&self, index: usize) -> String {
+        let lines = (index + 1) * SPRINT_SPLIT_LINES;
+        let time = format_time(self.splits[index]);
+        match self.split_difference(index) {
+            Some(difference) => format!("{}L {} ({})", lines, time, format_difference(difference)),
+            None => format!("{}L {}", lines, time),
+        }
+    }
+}
+
+impl GameMode for Sprint {
+    fn name(&self) -> String {
+        format!("Sprint ({} lines)", SPRINT_LINE_GOAL)
+    }
+
+    fn on_input(&mut self, _game: &mut Game) {
+        self.timer_started = true;
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        // The clock runs from the first input until the run ends either way
+        let is_finished = self.splits.len() >= SPRINT_LINE_GOAL / SPRINT_SPLIT_LINES;
+        if !self.timer_started || is_finished || game.is_game_over {
+            return;
+        }
+
+        self.elapsed_seconds += delta_seconds;
+        self.record_splits(game);
+        if self.is_finished(game) {
+            self.save_if_personal_best();
+        }
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if self.is_finished(game) {
+            ModeStatus::Won
+        } else if game.is_game_over {
+            ModeStatus::Lost
+        } else {
+            ModeStatus::Playing
+        }
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        let lines_remaining = SPRINT_LINE_GOAL.saturating_sub(game.lines_cleared);
+        let mut hud = vec![
+            format!("Lines left: {}", lines_remaining),
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+        ];
+        hud.extend((0..self.splits.len()).map(|index| self.format_split(index)));
+        hud
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        let mut results = vec![
+            ("Time".to_string(), format_time(self.elapsed_seconds)),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Pieces".to_string(), game.pieces_placed.to_string()),
+            (
+                "Pieces/sec".to_string(),
+                format!("{:.2}", pieces_per_second(game.pieces_placed, self.elapsed_seconds)),
+            ),
+            ("Finesse faults".to_string(), game.finesse_faults.to_string()),
+        ];
+        for index in 0..self.splits.len() {
+            results.push((format!("Split {}", index + 1), self.format_split(index)));
+        }
+        if self.is_new_personal_best {
+            results.push(("Personal best".to_string(), "New record!".to_string()));
+        } else if let Some(best_time) = self.personal_best.as_ref().and_then(|best| best.last()) {
+            results.push(("Personal best".to_string(), format_time(*best_time)));
+        }
+        results
+    }
+}
+
+/// Formats a split difference with an explicit sign, e.g. `-0.250` or `+1.020`.
+fn format_difference(difference: f64) -> String {
+    if difference < 0.0 {
+        format!("-{:.3}", -difference)
+    } else {
+        format!("+{:.3}", difference)
+    }
+}
+
+fn load_splits(path: &Path) -> Option<Vec<f64>> {
+    let contents = fs::read_to_string(path).ok()?;
+    let splits: Vec<f64> = contents
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect();
+    if splits.is_empty() { None } else { Some(splits) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_only_starts_on_first_input() {
+        let mut game = Game::new();
+        let mut sprint = Sprint::with_record_path(None);
+        sprint.setup(&mut game);
+
+        sprint.update(&mut game, 2.0);
+        assert_eq!(sprint.elapsed_seconds, 0.0);
+
+        sprint.on_input(&mut game);
+        sprint.update(&mut game, 0.125);
+        assert_eq!(sprint.elapsed_seconds, 0.125);
+    }
+
+    #[test]
+    fn splits_are_recorded_every_ten_lines_and_compared_to_personal_best() {
+        let mut game = Game::new();
+        let mut sprint = Sprint::with_record_path(None);
+        sprint.personal_best = Some(vec![10.0, 20.0, 30.0, 40.0]);
+        sprint.setup(&mut game);
+        sprint.on_input(&mut game);
+
+        game.lines_cleared = 10;
+        sprint.update(&mut game, 9.5);
+        // No new split until the next boundary is crossed
+        game.lines_cleared = 13;
+        sprint.update(&mut game, 11.0);
+        game.lines_cleared = 22;
+        sprint.update(&mut game, 0.0);
+
+        assert_eq!(sprint.splits, vec![9.5, 20.5]);
+        assert_eq!(sprint.split_difference(0), Some(-0.5));
+        assert_eq!(sprint.split_difference(1), Some(0.5));
+        assert_eq!(sprint.status(&game), ModeStatus::Playing);
+    }
+
+    #[test]
+    fn finishing_faster_sets_a_new_personal_best_and_stops_the_timer() {
+        let mut game = Game::new();
+        let mut sprint = Sprint::with_record_path(None);
+        sprint.personal_best = Some(vec![10.0, 20.0, 30.0, 40.0]);
+        sprint.setup(&mut game);
+        sprint.on_input(&mut game);
+
+        game.lines_cleared = SPRINT_LINE_GOAL;
+        sprint.update(&mut game, 35.0);
+        sprint.update(&mut game, 5.0);
+
+        assert_eq!(sprint.status(&game), ModeStatus::Won);
+        assert_eq!(sprint.elapsed_seconds, 35.0);
+        assert!(sprint.is_new_personal_best);
+        assert_eq!(sprint.personal_best, Some(vec![35.0; 4]));
+    }
+
+    #[test]
+    fn personal_best_is_saved_and_loaded_from_file() {
+        let path = std::env::temp_dir().join("tetris_rust_sprint_pb_test.txt");
+        let _ = fs::remove_file(&path);
+
+        let mut game = Game::new();
+        let mut sprint = Sprint::with_record_path(Some(path.clone()));
+        sprint.on_input(&mut game);
+        game.lines_cleared = SPRINT_LINE_GOAL;
+        sprint.update(&mut game, 42.5);
+
+        let reloaded = Sprint::with_record_path(Some(path.clone()));
+        assert_eq!(reloaded.personal_best, Some(vec![42.5; 4]));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn format_difference_has_explicit_sign() {
+        assert_eq!(format_difference(-0.25), "-0.250");
+        assert_eq!(format_difference(1.02), "+1.020");
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    core::game::Game,
+    enums::{ClearType, ModeStatus},
+    modes::{GameMode, format_time, pieces_per_second},
+};
+
+/// Length of an Ultra game in seconds.
+pub(crate) const ULTRA_DURATION_SECONDS: f64 = 120.0;
+
+/// Warning cues are given once per second during the last seconds of the game.
+pub(crate) const ULTRA_WARNING_SECONDS: f64 = 10.0;
+
+/// Ultra: score as many points as possible in [`ULTRA_DURATION_SECONDS`] at a fixed gravity.
+pub struct Ultra {
+    pub remaining_seconds: f64,
+    /// Whole seconds left when the last warning cue was given.
+    last_warning_second: Option<u64>,
+    pending_warning_cue: bool,
+}
+
+impl Default for Ultra {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ultra {
+    pub fn new() -> Self {
+        Self {
+            remaining_seconds: ULTRA_DURATION_SECONDS,
+            last_warning_second: None,
+            pending_warning_cue: false,
+        }
+    }
+
+    fn elapsed_seconds(&self) -> f64 {
+        ULTRA_DURATION_SECONDS - self.remaining_seconds
+    }
+}
+
+impl GameMode for Ultra {
+    fn name(&self) -> String {
+        "Ultra (2 minutes)".to_string()
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        // Gravity stays the same for the whole game
+        game.has_level_progression = false;
+        game.start();
+    }
+
+    fn update(&mut self, game: &mut Game, delta_seconds: f64) {
+        if self.status(game) != ModeStatus::Playing {
+            return;
+        }
+
+        self.remaining_seconds = (self.remaining_seconds - delta_seconds).max(0.0);
+
+        if self.is_in_warning(game) {
+            let second = self.remaining_seconds.ceil() as u64;
+            if self.last_warning_second != Some(second) {
+                self.last_warning_second = Some(second);
+                self.pending_warning_cue = true;
+            }
+        }
+    }
+
+    fn status(&self, game: &Game) -> ModeStatus {
+        if self.remaining_seconds <= 0.0 {
+            ModeStatus::Won
+        } else if game.is_game_over {
+            ModeStatus::Lost
+        } else {
+            ModeStatus::Playing
+        }
+    }
+
+    fn is_in_warning(&self, game: &Game) -> bool {
+        self.status(game) == ModeStatus::Playing && self.remaining_seconds <= ULTRA_WARNING_SECONDS
+    }
+
+    fn take_warning_cue(&mut self) -> bool {
+        std::mem::take(&mut self.pending_warning_cue)
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        vec![
+            format!("Time left: {}", format_time(self.remaining_seconds)),
+            format!("Score: {}", game.score),
+            format!("Lines: {}", game.lines_cleared),
+        ]
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        let elapsed = self.elapsed_seconds();
+        let mut results = vec![
+            ("Score".to_string(), game.score.to_string()),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Pieces".to_string(), game.pieces_placed.to_string()),
+            (
+                "Pieces/sec".to_string(),
+                format!("{:.2}", pieces_per_second(game.pieces_placed, elapsed)),
+            ),
+            (
+                "Score/piece".to_string(),
+                format!("{:.1}", ratio(game.score as f64, game.pieces_placed as f64)),
+            ),
+            (
+                "Score/line".to_string(),
+                format!("{:.1}", ratio(game.score as f64, game.lines_cleared as f64)),
+            ),
+        ];
+
+        for clear_type in ClearType::ALL {
+            if let Some(stats) = game.clear_stats.get(&clear_type) {
+                results.push((
+                    clear_type.name().to_string(),
+                    format!("{} x  {} pts", stats.count, stats.points),
+                ));
+            }
+        }
+        if game.perfect_clears > 0 {
+            results.push(("Perfect Clear".to_string(), game.perfect_clears.to_string()));
+        }
+
+        // Efficiency: share of the lines that were cleared with tetrises and T-spins
+        let difficult_lines: usize = game
+            .clear_stats
+            .iter()
+            .filter(|(clear_type, _)| clear_type.is_difficult())
+            .map(|(clear_type, stats)| clear_type.lines() * stats.count)
+            .sum();
+        results.push((
+            "Efficiency".to_string(),
+            format!(
+                "{:.0}%",
+                100.0 * ratio(difficult_lines as f64, game.lines_cleared as f64)
+            ),
+        ));
+        results
+    }
+}
+
+fn ratio(value: f64, total: f64) -> f64 {
+    if total <= 0.0 { 0.0 } else { value / total }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::scoring::ClearStats;
+
+    use super::*;
+
+    #[test]
+    fn ultra_ends_when_the_time_is_up() {
+        let mut game = Game::new();
+        let mut ultra = Ultra::new();
+        ultra.setup(&mut game);
+        assert!(!game.has_level_progression);
+
+        ultra.update(&mut game, ULTRA_DURATION_SECONDS - 1.0);
+        assert_eq!(ultra.status(&game), ModeStatus::Playing);
+
+        ultra.update(&mut game, 2.0);
+        assert_eq!(ultra.remaining_seconds, 0.0);
+        assert_eq!(ultra.status(&game), ModeStatus::Won);
+    }
+
+    #[test]
+    fn warning_cue_is_given_once_per_second_in_the_last_ten_seconds() {
+        let mut game = Game::new();
+        let mut ultra = Ultra::new();
+        ultra.setup(&mut game);
+
+        ultra.update(&mut game, 100.0);
+        assert!(!ultra.is_in_warning(&game));
+        assert!(!ultra.take_warning_cue());
+
+        ultra.update(&mut game, 10.5);
+        assert!(ultra.is_in_warning(&game));
+        assert!(ultra.take_warning_cue());
+        // Taken only once
+        assert!(!ultra.take_warning_cue());
+
+        ultra.update(&mut game, 0.2);
+        assert!(!ultra.take_warning_cue());
+        ultra.update(&mut game, 0.5);
+        assert!(ultra.take_warning_cue());
+    }
+
+    #[test]
+    fn results_break_down_score_by_clear_type() {
+        let mut game = Game::new();
+        let ultra = Ultra::new();
+        game.lines_cleared = 6;
+        game.clear_stats.insert(ClearType::Tetris, ClearStats { count: 1, points: 800 });
+        game.clear_stats.insert(ClearType::Double, ClearStats { count: 1, points: 300 });
+
+        let results = ultra.results(&game);
+
+        assert!(results.contains(&("Tetris".to_string(), "1 x  800 pts".to_string())));
+        assert!(results.contains(&("Double".to_string(), "1 x  300 pts".to_string())));
+        assert!(results.contains(&("Efficiency".to_string(), "67%".to_string())));
+    }
+}
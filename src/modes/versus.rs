@@ -0,0 +1,210 @@
+use crate::{
+    bot::Bot,
+    core::game::Game,
+    enums::{BotDifficulty, MatchStatus},
+    modes::{format_time, pieces_per_second},
+};
+
+/// Local versus: two players with the same piece sequence on one screen, the first one
+/// to top out loses. Line clears send garbage to the opponents (see [`crate::core::attack`]).
+pub struct Versus {
+    pub players: Vec<Game>,
+    /// The bot playing for each player, `None` for human players.
+    pub bots: Vec<Option<Bot>>,
+    pub elapsed_seconds: f64,
+}
+
+impl Versus {
+    /// Creates a match between `player_count` players whose games are seeded with `seed`,
+    /// so every player gets the same pieces. `prepare` is applied to each game before it starts.
+    pub fn new(player_count: usize, seed: u64, prepare: impl Fn(&mut Game)) -> Self {
+        let players = (0..player_count)
+            .map(|_| {
+                let mut game = Game::new();
+                game.set_seed(seed);
+                prepare(&mut game);
+                game.start();
+                game
+            })
+            .collect();
+        Self {
+            players,
+            bots: (0..player_count).map(|_| None).collect(),
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    /// Creates a match between a human player and a bot playing at the given difficulty.
+    pub fn against_bot(difficulty: BotDifficulty, seed: u64, prepare: impl Fn(&mut Game)) -> Self {
+        let mut versus = Self::new(2, seed, prepare);
+        versus.bots[1] = Some(Bot::new(difficulty, seed));
+        versus
+    }
+
+    /// Name of the player shown in the HUD and results.
+    pub fn player_name(&self, player: usize) -> String {
+        match &self.bots[player] {
+            Some(bot) => format!("Bot ({})", bot.difficulty.name()),
+            None => format!("Player {}", player + 1),
+        }
+    }
+
+    /// Advances every player's game by `delta_seconds` while the match is running.
+    pub fn update(&mut self, delta_seconds: f64) {
+        if self.status() != MatchStatus::Playing {
+            return;
+        }
+
+        self.elapsed_seconds += delta_seconds;
+        for (game, bot) in self.players.iter_mut().zip(&mut self.bots) {
+            if let Some(bot) = bot {
+                bot.update(game, delta_seconds);
+            }
+            game.update(delta_seconds);
+        }
+        self.exchange_garbage();
+    }
+
+    /// Sends the attack of every player to all the other players still standing.
+    pub fn exchange_garbage(&mut self) {
+        for sender in 0..self.players.len() {
+            let attack = self.players[sender].take_outgoing_attack();
+            for (receiver, game) in self.players.iter_mut().enumerate() {
+                if receiver != sender && !game.is_game_over {
+                    game.receive_garbage(attack);
+                }
+            }
+        }
+    }
+
+    /// The match is won by the last player standing, or drawn when everyone tops out together.
+    pub fn status(&self) -> MatchStatus {
+        let standing: Vec<usize> = (0..self.players.len())
+            .filter(|&index| !self.players[index].is_game_over)
+            .collect();
+        match standing.as_slice() {
+            [] => MatchStatus::Draw,
+            [winner] if self.players.len() > 1 => MatchStatus::Won(*winner),
+            _ => MatchStatus::Playing,
+        }
+    }
+
+    /// Lines of text shown beside the board of the given player.
+    pub fn hud(&self, player: usize) -> Vec<String> {
+        let game = &self.players[player];
+        vec![
+            self.player_name(player),
+            format!("Lines: {}", game.lines_cleared),
+            format!("Score: {}", game.score),
+            format!("Sent: {}", game.lines_sent),
+            format!("Incoming: {}", game.incoming_garbage.total()),
+            format!("Time: {}", format_time(self.elapsed_seconds)),
+        ]
+    }
+
+    pub fn results(&self) -> Vec<(String, String)> {
+        let mut results = vec![("Time".to_string(), format_time(self.elapsed_seconds))];
+        for (index, game) in self.players.iter().enumerate() {
+            results.push((
+                self.player_name(index),
+                format!(
+                    "{} lines, {} sent, {} pieces, {:.2} PPS",
+                    game.lines_cleared,
+                    game.lines_sent,
+                    game.pieces_placed,
+                    pieces_per_second(game.pieces_placed, self.elapsed_seconds)
+                ),
+            ));
+        }
+        results
+    }
+
+    pub fn title(&self) -> String {
+        match self.status() {
+            MatchStatus::Won(winner) => format!("{} wins!", self.player_name(winner)),
+            MatchStatus::Draw => "Draw!".to_string(),
+            MatchStatus::Playing => "Versus".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLUE;
+
+    use super::*;
+    use crate::{
+        core::{
+            constants::{COLS, ROWS},
+            piece::Piece,
+            point_2d::Point2D,
+        },
+        enums::{CellType, PieceType, RotationDirection},
+    };
+
+    #[test]
+    fn players_get_the_same_pieces() {
+        let mut versus = Versus::new(2, 42, |_| {});
+        for _ in 0..10 {
+            let pieces: Vec<_> = versus
+                .players
+                .iter()
+                .map(|game| game.current_piece.as_ref().unwrap().piece_type.clone())
+                .collect();
+            assert_eq!(pieces[0], pieces[1]);
+            for game in &mut versus.players {
+                game.hard_drop();
+            }
+        }
+    }
+
+    #[test]
+    fn a_tetris_sends_four_lines_to_the_opponent() {
+        let mut versus = Versus::new(2, 7, |_| {});
+        let game = &mut versus.players[0];
+        for row in 16..ROWS {
+            for col in 0..COLS - 1 {
+                game.board.set_cell(row, col, CellType::Filled(BLUE));
+            }
+        }
+        game.board.set_cell(15, 0, CellType::Filled(BLUE));
+        let mut piece = Piece::new(&PieceType::I, Point2D::new(3, COLS as isize - 1)).unwrap();
+        piece.rotate(RotationDirection::Clockwise);
+        game.current_piece = Some(piece);
+        game.hard_drop();
+
+        versus.exchange_garbage();
+
+        assert_eq!(versus.players[1].incoming_garbage.total(), 4);
+        assert_eq!(versus.players[0].incoming_garbage.total(), 0);
+    }
+
+    #[test]
+    fn bot_plays_its_side_of_the_match() {
+        let mut versus = Versus::against_bot(BotDifficulty::Hard, 9, |_| {});
+        for _ in 0..60 * 5 {
+            versus.update(1.0 / 60.0);
+        }
+
+        assert_eq!(versus.players[0].pieces_placed, 0);
+        assert!(versus.players[1].pieces_placed >= 10);
+        assert_eq!(versus.player_name(1), "Bot (Hard)");
+    }
+
+    #[test]
+    fn first_player_to_top_out_loses() {
+        let mut versus = Versus::new(2, 1, |_| {});
+        assert_eq!(versus.status(), MatchStatus::Playing);
+
+        versus.players[0].is_game_over = true;
+        assert_eq!(versus.status(), MatchStatus::Won(1));
+        assert_eq!(versus.title(), "Player 2 wins!");
+
+        // The match stops once decided
+        versus.update(1.0);
+        assert_eq!(versus.elapsed_seconds, 0.0);
+
+        versus.players[1].is_game_over = true;
+        assert_eq!(versus.status(), MatchStatus::Draw);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    core::{
+        board::Board,
+        game::{Game, GameSnapshot},
+    },
+    enums::ModeStatus,
+    modes::GameMode,
+};
+
+/// Placements that can be undone by default.
+pub const ZEN_DEFAULT_UNDO_LIMIT: usize = 50;
+
+/// Zen: relaxed practice without gravity or game over, where placements can be undone and redone.
+pub struct Zen {
+    /// Most placements kept in the history.
+    pub undo_limit: usize,
+    /// Stack the game starts from instead of an empty board, e.g. a setup loaded from a fumen.
+    pub starting_board: Option<Board>,
+    /// Game states right after each placement, the last one being the current placement.
+    history: Vec<GameSnapshot>,
+    /// Undone states, the last one undone at the end.
+    redo_stack: Vec<GameSnapshot>,
+    pieces_seen: usize,
+}
+
+impl Zen {
+    pub fn new(undo_limit: usize) -> Self {
+        Self {
+            undo_limit,
+            starting_board: None,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            pieces_seen: 0,
+        }
+    }
+
+    pub fn with_starting_board(mut self, board: Board) -> Self {
+        self.starting_board = Some(board);
+        self
+    }
+
+    /// Number of placements that can currently be undone.
+    pub fn undo_count(&self) -> usize {
+        self.history.len().saturating_sub(1)
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    fn record(&mut self, game: &Game) {
+        self.history.push(game.snapshot());
+        if self.history.len() > self.undo_limit + 1 {
+            self.history.remove(0);
+        }
+        self.redo_stack.clear();
+        self.pieces_seen = game.pieces_placed;
+    }
+}
+
+impl GameMode for Zen {
+    fn name(&self) -> String {
+        "Zen".to_string()
+    }
+
+    fn setup(&mut self, game: &mut Game) {
+        game.has_level_progression = false;
+        // No gravity, and pieces only lock when hard dropped
+        game.timings.gravity = 0.0;
+        game.timings.lock_delay = u32::MAX;
+        if let Some(board) = &self.starting_board {
+            game.board = board.clone();
+        }
+        game.start();
+        self.record(game);
+    }
+
+    fn update(&mut self, game: &mut Game, _delta_seconds: f64) {
+        if game.is_game_over {
+            // Topping out clears the stack instead of ending the game, on a board of the same size
+            game.board = Board {
+                clock_seconds: game.board.clock_seconds,
+                ..Board::with_size(game.board.rows, game.board.cols)
+            };
+            game.is_game_over = false;
+        }
+
+        if game.pieces_placed != self.pieces_seen {
+            self.record(game);
+        }
+    }
+
+    fn undo(&mut self, game: &mut Game) {
+        if self.history.len() < 2 {
+            return;
+        }
+        if let Some(current) = self.history.pop() {
+            self.redo_stack.push(current);
+        }
+        if let Some(previous) = self.history.last() {
+            game.restore(previous);
+            self.pieces_seen = game.pieces_placed;
+        }
+    }
+
+    fn redo(&mut self, game: &mut Game) {
+        if let Some(next) = self.redo_stack.pop() {
+            game.restore(&next);
+            self.pieces_seen = game.pieces_placed;
+            self.history.push(next);
+        }
+    }
+
+    fn status(&self, _game: &Game) -> ModeStatus {
+        ModeStatus::Playing
+    }
+
+    fn hud(&self, game: &Game) -> Vec<String> {
+        vec![
+            format!("Pieces: {}", game.pieces_placed),
+            format!("Lines: {}", game.lines_cleared),
+            format!("Score: {}", game.score),
+            format!("Undo (U): {}", self.undo_count()),
+            format!("Redo (Y): {}", self.redo_count()),
+        ]
+    }
+
+    fn results(&self, game: &Game) -> Vec<(String, String)> {
+        vec![
+            ("Pieces".to_string(), game.pieces_placed.to_string()),
+            ("Lines".to_string(), game.lines_cleared.to_string()),
+            ("Score".to_string(), game.score.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::CellType;
+
+    fn place(game: &mut Game, zen: &mut Zen) {
+        game.hard_drop();
+        zen.update(game, 0.1);
+    }
+
+    #[test]
+    fn pieces_do_not_fall_or_lock_on_their_own() {
+        let mut game = Game::new();
+        let mut zen = Zen::new(ZEN_DEFAULT_UNDO_LIMIT);
+        zen.setup(&mut game);
+        let start = game.current_piece.as_ref().unwrap().position;
+
+        for _ in 0..600 {
+            game.step_frame();
+        }
+
+        assert_eq!(game.current_piece.as_ref().unwrap().position, start);
+        assert_eq!(game.pieces_placed, 0);
+    }
+
+    #[test]
+    fn placements_can_be_undone_and_redone() {
+        let mut game = Game::new();
+        let mut zen = Zen::new(ZEN_DEFAULT_UNDO_LIMIT);
+        zen.setup(&mut game);
+
+        place(&mut game, &mut zen);
+        let after_first = game.board.get_board_representation();
+        place(&mut game, &mut zen);
+        let after_second = game.board.get_board_representation();
+        assert_eq!(zen.undo_count(), 2);
+
+        zen.undo(&mut game);
+        assert_eq!(game.pieces_placed, 1);
+        assert_eq!(game.board.get_board_representation(), after_first);
+
+        zen.redo(&mut game);
+        assert_eq!(game.pieces_placed, 2);
+        assert_eq!(game.board.get_board_representation(), after_second);
+
+        // A new placement after undoing drops the redo history
+        zen.undo(&mut game);
+        place(&mut game, &mut zen);
+        assert_eq!(zen.redo_count(), 0);
+    }
+
+    #[test]
+    fn undo_history_is_limited() {
+        let mut game = Game::new();
+        let mut zen = Zen::new(2);
+        zen.setup(&mut game);
+
+        for _ in 0..4 {
+            place(&mut game, &mut zen);
+        }
+        assert_eq!(zen.undo_count(), 2);
+
+        zen.undo(&mut game);
+        zen.undo(&mut game);
+        zen.undo(&mut game);
+        assert_eq!(game.pieces_placed, 2);
+    }
+
+    #[test]
+    fn undoing_the_first_placement_returns_to_the_starting_board() {
+        let mut board = Board::new();
+        board.set_cell(19, 0, CellType::Garbage);
+        let mut game = Game::new();
+        let mut zen = Zen::new(ZEN_DEFAULT_UNDO_LIMIT).with_starting_board(board.clone());
+        zen.setup(&mut game);
+
+        place(&mut game, &mut zen);
+        zen.undo(&mut game);
+
+        assert_eq!(game.board.get_board_representation(), board.get_board_representation());
+    }
+
+    #[test]
+    fn topping_out_clears_the_board_instead_of_ending_the_game() {
+        let mut game = Game::new();
+        let mut zen = Zen::new(ZEN_DEFAULT_UNDO_LIMIT);
+        zen.setup(&mut game);
+        game.board = Board::with_size(22, 12);
+        game.board.set_cell(0, 0, CellType::Garbage);
+
+        game.is_game_over = true;
+        zen.update(&mut game, 0.1);
+
+        assert!(!game.is_game_over);
+        assert!(game.board.get_filled_cells().is_empty());
+        assert_eq!((game.board.rows, game.board.cols), (22, 12));
+        assert_eq!(zen.status(&game), ModeStatus::Playing);
+    }
+}
@@ -1,9 +1,11 @@
-use rodio::{Decoder, OutputStream, Sink, Source};
+use rodio::{Decoder, OutputStream, Sink, Source, source::SineWave};
 use std::fs::File;
 use std::io::BufReader;
+use std::time::Duration;
 
 pub struct AudioPlayer {
     sink: Option<Sink>,
+    effects_sink: Option<Sink>,
     _stream: Option<OutputStream>,
 }
 
@@ -13,12 +15,14 @@ impl AudioPlayer {
             if let Ok(sink) = Sink::try_new(&handle) {
                 return Self {
                     sink: Some(sink),
+                    effects_sink: Sink::try_new(&handle).ok(),
                     _stream: Some(_stream),
                 };
             }
         }
         Self {
             sink: None,
+            effects_sink: None,
             _stream: None,
         }
     }
@@ -33,6 +37,16 @@ impl AudioPlayer {
         }
     }
 
+    /// Plays a short beep over the music, used as a countdown warning.
+    pub fn play_warning_beep(&self) {
+        if let Some(sink) = &self.effects_sink {
+            let beep = SineWave::new(880.0)
+                .take_duration(Duration::from_millis(120))
+                .amplify(0.2);
+            sink.append(beep);
+        }
+    }
+
     pub fn stop(&self) {
         if let Some(sink) = &self.sink {
             sink.stop();
@@ -1,71 +1,212 @@
 use macroquad::{
-    color::{BLUE, Color, GRAY, WHITE},
+    color::{BLUE, Color, GRAY, ORANGE, RED, WHITE, YELLOW},
     shapes::{draw_line, draw_rectangle, draw_rectangle_lines},
+    text::draw_text,
+    window::{screen_height, screen_width},
 };
 
-use crate::{
-    core::{board::Board, constants::*, piece::Piece},
-    enums::cell_type::CellType,
+use tetris_rust::{
+    core::{board::Board, constants::*, piece::Piece, point_2d::Point2D},
+    enums::{StackVisibility, cell_type::CellType},
 };
 
-pub fn draw_board(board: &Board) {
-    draw_empty_board();
-    draw_filled_cells(board);
+/// Color of the cells of garbage rows.
+const GARBAGE_COLOR: Color = Color::new(0.45, 0.45, 0.45, 1.0);
+
+/// Where a board is drawn on the screen, its side panel (next, hold and HUD) is drawn to its right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// Top-left corner of the board.
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Viewport {
+    /// The viewport of a single player game.
+    pub const MAIN: Viewport = Viewport { x: BOARD_X, y: BOARD_Y };
+
+    /// Horizontal distance between the boards of two players besides the boards themselves,
+    /// side panel included.
+    const PLAYER_GAP: f32 = 250.0;
+
+    /// The viewport of the given player, from left to right, on boards of `cols` columns.
+    pub fn for_player(index: usize, cols: usize) -> Self {
+        Self {
+            x: BOARD_X + index as f32 * (cols as f32 * CELL_SIZE + Self::PLAYER_GAP),
+            y: BOARD_Y,
+        }
+    }
+
+    /// Width of the side panel of one player.
+    const SIDE_PANEL_WIDTH: f32 = 220.0;
+
+    /// The viewport whose side panel is the `index`-th one to the right of a board of `cols`
+    /// columns drawn at this viewport, for players sharing a wide board.
+    pub fn side_panel(self, cols: usize, index: usize) -> Self {
+        Self {
+            x: self.x + (cols as f32 - COLS as f32) * CELL_SIZE + index as f32 * Self::SIDE_PANEL_WIDTH,
+            y: self.y,
+        }
+    }
+}
+
+/// Seconds the outline of a hidden stack is shown after a piece locks.
+const LOCK_FLASH_SECONDS: f64 = 0.2;
+
+/// Color of the outline flashed around a hidden stack.
+const STACK_OUTLINE_COLOR: Color = Color::new(0.2, 0.2, 0.2, 1.0);
+
+/// Color of the cells outlined by a hint.
+const HINT_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.6);
+
+pub fn draw_board(board: &Board, visibility: StackVisibility, viewport: Viewport) {
+    draw_empty_board(board.rows, board.cols, viewport);
+    draw_filled_cells(board, visibility, viewport);
+
+    let is_flashing = board
+        .last_lock_seconds
+        .is_some_and(|lock| board.clock_seconds - lock < LOCK_FLASH_SECONDS);
+    if visibility != StackVisibility::Visible && is_flashing {
+        draw_stack_outline(board, viewport);
+    }
 }
 
-fn draw_empty_board() {
+fn draw_empty_board(rows: usize, cols: usize, viewport: Viewport) {
+    let width = cols as f32 * CELL_SIZE;
+    let height = rows as f32 * CELL_SIZE;
+
     // Draw board border
     draw_rectangle_lines(
-        BOARD_X - BORDER_THICKNESS / 2.0,
-        BOARD_Y - BORDER_THICKNESS / 2.0,
-        BOARD_WIDTH + BORDER_THICKNESS,
-        BOARD_HEIGHT + BORDER_THICKNESS,
+        viewport.x - BORDER_THICKNESS / 2.0,
+        viewport.y - BORDER_THICKNESS / 2.0,
+        width + BORDER_THICKNESS,
+        height + BORDER_THICKNESS,
         BORDER_THICKNESS,
         GRAY,
     );
 
     // Draw the background of the board
-    draw_rectangle(BOARD_X, BOARD_Y, BOARD_WIDTH, BOARD_HEIGHT, WHITE);
+    draw_rectangle(viewport.x, viewport.y, width, height, WHITE);
 
     // Draw horizontal lines
-    for row in 1..ROWS {
-        let y = BOARD_Y + row as f32 * CELL_SIZE;
-        draw_line(BOARD_X, y, BOARD_X + BOARD_WIDTH, y, 1.0, GRAY);
+    for row in 1..rows {
+        let y = viewport.y + row as f32 * CELL_SIZE;
+        draw_line(viewport.x, y, viewport.x + width, y, 1.0, GRAY);
     }
 
     // Draw vertical lines
-    for col in 1..COLS {
-        let x = BOARD_X + col as f32 * CELL_SIZE;
-        draw_line(x, BOARD_Y, x, BOARD_Y + BOARD_HEIGHT, 1.0, GRAY);
+    for col in 1..cols {
+        let x = viewport.x + col as f32 * CELL_SIZE;
+        draw_line(x, viewport.y, x, viewport.y + height, 1.0, GRAY);
     }
 }
 
-fn draw_filled_cells(board: &Board) {
-    // Draw filled cells
+fn draw_filled_cells(board: &Board, visibility: StackVisibility, viewport: Viewport) {
+    // Draw filled cells, faded out according to how long ago they were locked
     board
         .get_filled_cells()
         .iter()
         .for_each(|((row, col), cell_type)| {
-            if let CellType::Filled(color) = cell_type {
-                draw_cell_piece(*row, *col, *color);
+            let alpha = visibility.alpha(board.lock_age(*row, *col));
+            if alpha <= 0.0 {
+                return;
             }
+            let color = match cell_type {
+                CellType::Filled(color) => *color,
+                CellType::Garbage => GARBAGE_COLOR,
+                CellType::Empty => return,
+            };
+            draw_cell_piece(*row, *col, Color { a: color.a * alpha, ..color }, viewport);
         });
 }
 
-pub fn draw_piece(piece: &Piece) {
-    for block in &piece.blocks {
-        let row = (piece.position.y + block.y) as usize;
-        let col = (piece.position.x + block.x) as usize;
-        draw_cell_piece(row, col, piece.color);
+/// Draws the edges between filled and empty cells, showing the shape of a hidden stack.
+fn draw_stack_outline(board: &Board, viewport: Viewport) {
+    let is_filled = |row: isize, col: isize| {
+        row >= 0
+            && col >= 0
+            && (row as usize) < board.rows
+            && (col as usize) < board.cols
+            && board.cells[row as usize][col as usize] != CellType::Empty
+    };
+
+    for (row, col) in board.get_filled_cells().keys() {
+        let (r, c) = (*row as isize, *col as isize);
+        let left = viewport.x + CELL_SIZE * *col as f32;
+        let top = viewport.y + CELL_SIZE * *row as f32;
+        let (right, bottom) = (left + CELL_SIZE, top + CELL_SIZE);
+
+        if !is_filled(r - 1, c) {
+            draw_line(left, top, right, top, 2.0, STACK_OUTLINE_COLOR);
+        }
+        if !is_filled(r + 1, c) && *row + 1 < board.rows {
+            draw_line(left, bottom, right, bottom, 2.0, STACK_OUTLINE_COLOR);
+        }
+        if !is_filled(r, c - 1) && *col > 0 {
+            draw_line(left, top, left, bottom, 2.0, STACK_OUTLINE_COLOR);
+        }
+        if !is_filled(r, c + 1) && *col + 1 < board.cols {
+            draw_line(right, top, right, bottom, 2.0, STACK_OUTLINE_COLOR);
+        }
+    }
+}
+
+pub fn draw_piece(piece: &Piece, viewport: Viewport) {
+    for block in piece.get_blocks_position() {
+        // Blocks above the board (e.g. right after a rotation at spawn) are not drawn
+        if block.y < 0 {
+            continue;
+        }
+        draw_cell_piece(block.y as usize, block.x as usize, piece.color, viewport);
     }
 }
 
-pub fn draw_next_piece_section(next_piece: &Option<Piece>) {
-    let x_position_text_placement = BOARD_X + BOARD_WIDTH + 40.0;
-    let y_position_text_placement = BOARD_Y + 20.0;
-    let y_position_next_piece_container = BOARD_Y + 40.0;
+/// Outlines the given cells, e.g. where a hint suggests placing the current piece.
+pub fn draw_hint(cells: &[Point2D], viewport: Viewport) {
+    for cell in cells.iter().filter(|cell| cell.y >= 0) {
+        let x = viewport.x + CELL_SIZE * cell.x as f32;
+        let y = viewport.y + CELL_SIZE * cell.y as f32;
+        draw_rectangle_lines(x + 2.0, y + 2.0, CELL_SIZE - 4.0, CELL_SIZE - 4.0, 3.0, HINT_COLOR);
+    }
+}
+
+/// Offset of the hold piece section from the top of the board, below the next piece section.
+const HOLD_SECTION_OFFSET: f32 = 4.0 * CELL_SIZE + BORDER_THICKNESS + 80.0;
+
+/// Side of a cell of the smaller previews drawn under the next piece.
+const MINI_PREVIEW_CELL_SIZE: f32 = 7.0;
+
+/// Width of the slot of each smaller preview, four fitting under the next piece.
+const MINI_PREVIEW_SLOT_WIDTH: f32 = 6.0 * CELL_SIZE / 4.0;
+
+/// Draws the first of `previews` in the next piece box and the following ones smaller below it.
+/// Nothing is drawn when the rules show no previews.
+pub fn draw_next_piece_section(previews: &[&Piece], viewport: Viewport) {
+    let Some((next_piece, upcoming)) = previews.split_first() else {
+        return;
+    };
+    draw_piece_preview_section("Next Piece", Some(next_piece), viewport.y, viewport);
+
+    let x = viewport.x + BOARD_WIDTH + 40.0;
+    let y = viewport.y + 40.0 + 4.0 * CELL_SIZE + BORDER_THICKNESS + 8.0;
+    for (index, piece) in upcoming.iter().enumerate() {
+        let slot_x = x + index as f32 * MINI_PREVIEW_SLOT_WIDTH;
+        let height = 3.0 * MINI_PREVIEW_CELL_SIZE;
+        draw_piece_centered(piece, slot_x, y, MINI_PREVIEW_SLOT_WIDTH, height, MINI_PREVIEW_CELL_SIZE);
+    }
+}
+
+/// Draws the held piece below the next piece.
+pub fn draw_hold_piece_section(hold_piece: &Option<Piece>, viewport: Viewport) {
+    draw_piece_preview_section("Hold", hold_piece.as_ref(), viewport.y + HOLD_SECTION_OFFSET, viewport);
+}
+
+fn draw_piece_preview_section(title: &str, piece: Option<&Piece>, top: f32, viewport: Viewport) {
+    let x_position_text_placement = viewport.x + BOARD_WIDTH + 40.0;
+    let y_position_text_placement = top + 20.0;
+    let y_position_piece_container = top + 40.0;
     macroquad::text::draw_text(
-        "Next Piece",
+        title,
         x_position_text_placement,
         y_position_text_placement,
         24.0,
@@ -74,7 +215,7 @@ pub fn draw_next_piece_section(next_piece: &Option<Piece>) {
 
     macroquad::shapes::draw_rectangle_lines(
         x_position_text_placement,
-        y_position_next_piece_container,
+        y_position_piece_container,
         6.0 * CELL_SIZE + BORDER_THICKNESS,
         4.0 * CELL_SIZE + BORDER_THICKNESS,
         BORDER_THICKNESS,
@@ -83,32 +224,125 @@ pub fn draw_next_piece_section(next_piece: &Option<Piece>) {
 
     macroquad::shapes::draw_rectangle(
         x_position_text_placement + BORDER_THICKNESS / 2.0,
-        y_position_next_piece_container + BORDER_THICKNESS / 2.0,
+        y_position_piece_container + BORDER_THICKNESS / 2.0,
         6.0 * CELL_SIZE,
         4.0 * CELL_SIZE,
         WHITE,
     );
 
-    if let Some(next_piece) = next_piece {
-        draw_next_piece(next_piece);
+    if let Some(piece) = piece {
+        draw_preview_piece(piece, top, viewport);
     }
 }
 
-fn draw_next_piece(piece: &Piece) {
-    let x_position_text_placement = BOARD_X + BOARD_WIDTH - 12.0;
-    let y_position_next_piece_container = BOARD_Y + 40.0 + CELL_SIZE;
+fn draw_preview_piece(piece: &Piece, top: f32, viewport: Viewport) {
+    let container_x = viewport.x + BOARD_WIDTH + 40.0 + BORDER_THICKNESS / 2.0;
+    let container_y = top + 40.0 + BORDER_THICKNESS / 2.0;
+    draw_piece_centered(piece, container_x, container_y, 6.0 * CELL_SIZE, 4.0 * CELL_SIZE, CELL_SIZE);
+}
+
+/// Draws the blocks of `piece` with cells of `cell_size`, centered in the given rectangle
+/// whatever its size or number of blocks.
+fn draw_piece_centered(piece: &Piece, x: f32, y: f32, width: f32, height: f32, cell_size: f32) {
+    let min_x = piece.blocks.iter().map(|block| block.x).min().unwrap_or(0);
+    let max_x = piece.blocks.iter().map(|block| block.x).max().unwrap_or(0);
+    let min_y = piece.blocks.iter().map(|block| block.y).min().unwrap_or(0);
+    let max_y = piece.blocks.iter().map(|block| block.y).max().unwrap_or(0);
+    let offset_x = x + (width - (max_x - min_x + 1) as f32 * cell_size) / 2.0;
+    let offset_y = y + (height - (max_y - min_y + 1) as f32 * cell_size) / 2.0;
 
     for block in &piece.blocks {
-        let row = (piece.position.y + block.y) as usize;
-        let col = (piece.position.x + block.x) as usize;
-        let x = x_position_text_placement + CELL_SIZE * col as f32;
-        let y = y_position_next_piece_container + CELL_SIZE * row as f32;
-        draw_rectangle(x, y, CELL_SIZE, CELL_SIZE, piece.color);
+        let block_x = offset_x + cell_size * (block.x - min_x) as f32;
+        let block_y = offset_y + cell_size * (block.y - min_y) as f32;
+        draw_rectangle(block_x, block_y, cell_size, cell_size, piece.color);
     }
 }
 
-fn draw_cell_piece(row: usize, col: usize, color: Color) {
-    let x = BOARD_X + CELL_SIZE * col as f32;
-    let y = BOARD_Y + CELL_SIZE * row as f32;
+/// Draws the mode's status lines below the hold piece section.
+pub fn draw_hud(lines: &[String], viewport: Viewport) {
+    let x = viewport.x + BOARD_WIDTH + 40.0;
+    let y = viewport.y + HOLD_SECTION_OFFSET + 40.0 + 4.0 * CELL_SIZE + BORDER_THICKNESS + 40.0;
+    for (index, line) in lines.iter().enumerate() {
+        draw_text(line, x, y + index as f32 * 28.0, 24.0, WHITE);
+    }
+}
+
+/// Width of the incoming garbage meter drawn left of the board.
+const GARBAGE_METER_WIDTH: f32 = 8.0;
+
+/// Draws the incoming garbage as a bar rising along the left of a board of `rows` rows, one
+/// cell per line. Garbage still delayed is drawn orange, garbage ready to enter the board red.
+pub fn draw_garbage_meter(total: usize, ready: usize, rows: usize, viewport: Viewport) {
+    let x = viewport.x - BORDER_THICKNESS - GARBAGE_METER_WIDTH - 2.0;
+    let height = rows as f32 * CELL_SIZE;
+    let bottom = viewport.y + height;
+    let total_height = (total as f32 * CELL_SIZE).min(height);
+    let ready_height = (ready as f32 * CELL_SIZE).min(total_height);
+    draw_rectangle(x, bottom - total_height, GARBAGE_METER_WIDTH, total_height, ORANGE);
+    draw_rectangle(x, bottom - ready_height, GARBAGE_METER_WIDTH, ready_height, RED);
+}
+
+/// Draws a red border around a board of `rows` by `cols` cells that flashes once per second, in
+/// time with the warning beeps.
+pub fn draw_warning_border(time: f64, rows: usize, cols: usize, viewport: Viewport) {
+    if time.fract() >= 0.5 {
+        return;
+    }
+    draw_rectangle_lines(
+        viewport.x - BORDER_THICKNESS * 2.0,
+        viewport.y - BORDER_THICKNESS * 2.0,
+        cols as f32 * CELL_SIZE + BORDER_THICKNESS * 4.0,
+        rows as f32 * CELL_SIZE + BORDER_THICKNESS * 4.0,
+        BORDER_THICKNESS * 2.0,
+        RED,
+    );
+}
+
+/// Draws a list of options with the `selected` one highlighted, and the current
+/// modifier settings below it.
+pub fn draw_menu(title: &str, options: &[String], selected: usize, settings: &[String]) {
+    draw_text(title, BOARD_X, BOARD_Y + 40.0, 40.0, WHITE);
+    for (index, option) in options.iter().enumerate() {
+        let y = BOARD_Y + 100.0 + index as f32 * 32.0;
+        if index == selected {
+            draw_text(&format!("> {}", option), BOARD_X, y, 28.0, YELLOW);
+        } else {
+            draw_text(&format!("  {}", option), BOARD_X, y, 28.0, WHITE);
+        }
+    }
+    let settings_y = BOARD_Y + 120.0 + options.len() as f32 * 32.0;
+    for (index, setting) in settings.iter().enumerate() {
+        draw_text(setting, BOARD_X, settings_y + index as f32 * 28.0, 24.0, WHITE);
+    }
+    draw_text(
+        "Up/Down to choose, Enter to play, Escape to quit",
+        BOARD_X,
+        settings_y + 10.0 + settings.len() as f32 * 28.0,
+        20.0,
+        GRAY,
+    );
+}
+
+/// Draws the results screen on top of whatever is already drawn.
+pub fn draw_results(title: &str, rows: &[(String, String)]) {
+    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.8));
+    draw_text(title, BOARD_X, BOARD_Y + 60.0, 48.0, YELLOW);
+    for (index, (label, value)) in rows.iter().enumerate() {
+        let y = BOARD_Y + 120.0 + index as f32 * 32.0;
+        draw_text(label, BOARD_X, y, 28.0, WHITE);
+        draw_text(value, BOARD_X + 200.0, y, 28.0, WHITE);
+    }
+    draw_text(
+        "Press Enter to return to the menu",
+        BOARD_X,
+        BOARD_Y + 140.0 + rows.len() as f32 * 32.0,
+        20.0,
+        GRAY,
+    );
+}
+
+fn draw_cell_piece(row: usize, col: usize, color: Color, viewport: Viewport) {
+    let x = viewport.x + CELL_SIZE * col as f32;
+    let y = viewport.y + CELL_SIZE * row as f32;
     draw_rectangle(x, y, CELL_SIZE, CELL_SIZE, color);
 }